@@ -26,6 +26,8 @@ import (
 	"unsafe"
 
 	"github.com/consensys/gnark-crypto/ecc"
+	"github.com/consensys/gnark-crypto/ecc/bn254"
+	"github.com/consensys/gnark-crypto/ecc/bn254/fp"
 	"github.com/consensys/gnark/backend/groth16"
 	groth16_bn254 "github.com/consensys/gnark/backend/groth16/bn254"
 	"github.com/consensys/gnark/backend/witness"
@@ -210,6 +212,24 @@ func Verify(proofStr *C.char) *C.char {
 	return nil
 }
 
+//export ExportVerifierKey
+func ExportVerifierKey(assetDir *C.char) *C.char {
+	vk, err := LoadVerifierKey(C.GoString(assetDir))
+	if err != nil {
+		// Only log the error as in the Prove function.
+		Logger.Info().Msgf("failed to load verifier key: %v", err)
+		return nil
+	}
+
+	constants, err := GetVerifierKeyConstants(vk)
+	if err != nil {
+		Logger.Info().Msgf("failed to export verifier key: %v", err)
+		return nil
+	}
+
+	return C.CString(constants)
+}
+
 //export FreeString
 func FreeString(str *C.char) {
 	C.free(unsafe.Pointer(str))
@@ -392,6 +412,54 @@ func LoadVerifierKey(assetDir string) (groth16.VerifyingKey, error) {
 	return vk, nil
 }
 
+// The verification key as encoded in the constants of the Solidity verifier
+// contract, each coordinate as a hex string
+type VerifierKeyConstants struct {
+	Alpha    [2]string   `json:"alpha"`
+	BetaNeg  [4]string   `json:"beta_neg"`
+	GammaNeg [4]string   `json:"gamma_neg"`
+	DeltaNeg [4]string   `json:"delta_neg"`
+	K        [][2]string `json:"k"`
+}
+
+// Encode the verification key as in `ExportSolidity`: the G2 points are
+// negated, and the coordinates of the G2 points are ordered as in EIP-197.
+func GetVerifierKeyConstants(vk groth16.VerifyingKey) (string, error) {
+	bn254Vk, ok := vk.(*groth16_bn254.VerifyingKey)
+	if !ok {
+		return "", errors.New("verifying key is not over BN254")
+	}
+
+	fpHex := func(e *fp.Element) string {
+		return fmt.Sprintf("%#x", e.BigInt(new(big.Int)))
+	}
+	g1 := func(p *bn254.G1Affine) [2]string {
+		return [2]string{fpHex(&p.X), fpHex(&p.Y)}
+	}
+	g2Neg := func(p *bn254.G2Affine) [4]string {
+		var neg bn254.G2Affine
+		neg.Neg(p)
+		return [4]string{fpHex(&neg.X.A1), fpHex(&neg.X.A0), fpHex(&neg.Y.A1), fpHex(&neg.Y.A0)}
+	}
+
+	constants := VerifierKeyConstants{
+		Alpha:    g1(&bn254Vk.G1.Alpha),
+		BetaNeg:  g2Neg(&bn254Vk.G2.Beta),
+		GammaNeg: g2Neg(&bn254Vk.G2.Gamma),
+		DeltaNeg: g2Neg(&bn254Vk.G2.Delta),
+	}
+	for i := range bn254Vk.G1.K {
+		constants.K = append(constants.K, g1(&bn254Vk.G1.K[i]))
+	}
+
+	jsonConstants, err := json.Marshal(constants)
+	if err != nil {
+		return "", errors.Wrap(err, "marshal verifier key constants")
+	}
+
+	return string(jsonConstants), nil
+}
+
 func ProveCircuit(
 	verifierOnlyCircuitDataStr string,
 	proofWithPublicInputsStr string,
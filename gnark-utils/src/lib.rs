@@ -7,7 +7,7 @@ mod verify;
 
 pub use compile::compile_and_generate_assets;
 pub use prove::{init_prover, init_prover_from_bytes, prove};
-pub use verify::{init_verifier, verify};
+pub use verify::{export_verifier_key, init_verifier, verify};
 
 mod go {
     use std::os::raw::c_char;
@@ -47,6 +47,11 @@ mod go {
         /// otherwise it returns an error string.
         pub fn Verify(proof: *const c_char) -> *const c_char;
 
+        /// Export the verification key of `vk.bin` in the asset dir, as the
+        /// JSON of the constants of the Solidity verifier contract. Return null
+        /// if failed.
+        pub fn ExportVerifierKey(asset_dir: *const c_char) -> *const c_char;
+
         /// Free the C String returned from Go to Rust.
         pub fn FreeString(s: *const c_char);
     }
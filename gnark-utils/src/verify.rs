@@ -1,8 +1,8 @@
 //! Initialize the verifier and verify the proofs.
 
 use crate::{go, utils::handle_c_result};
-use anyhow::Result;
-use std::ffi::CString;
+use anyhow::{bail, Result};
+use std::ffi::{CStr, CString};
 
 /// Initialize the verifier.
 pub fn init_verifier(asset_dir: &str) -> Result<()> {
@@ -21,3 +21,22 @@ pub fn verify(proof: &str) -> Result<()> {
 
     handle_c_result(result)
 }
+
+/// Export the verification key of the asset dir as the JSON of the constants
+/// of the Solidity verifier contract.
+pub fn export_verifier_key(asset_dir: &str) -> Result<String> {
+    let asset_dir = CString::new(asset_dir)?;
+
+    let result = unsafe { go::ExportVerifierKey(asset_dir.as_ptr()) };
+
+    if result.is_null() {
+        bail!("Failed to export the verifier key");
+    }
+
+    let c_vk = unsafe { CStr::from_ptr(result) };
+    let vk = c_vk.to_str()?.to_string();
+
+    unsafe { go::FreeString(c_vk.as_ptr()) };
+
+    Ok(vk)
+}
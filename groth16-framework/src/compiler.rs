@@ -6,19 +6,112 @@ use crate::{
     },
     C, D, F,
 };
-use anyhow::{anyhow, Result};
-use plonky2::plonk::{circuit_data::CircuitData, config::GenericHashOut};
+use anyhow::{anyhow, bail, ensure, Result};
+use mr_plonky2_circuits::api::MIN_SECURITY_BITS;
+use plonky2::plonk::{
+    circuit_data::CircuitData,
+    config::{AlgebraicHasher, GenericConfig, GenericHashOut},
+};
 use plonky2x::backend::{
-    circuit::{DefaultParameters, Groth16WrapperParameters},
+    circuit::{DefaultParameters, Groth16WrapperParameters, PlonkParameters},
     wrapper::wrap::WrappedCircuit,
 };
 use std::{
-    fs::OpenOptions,
+    fs::{create_dir_all, read_dir, OpenOptions},
     io::{Read, Seek, SeekFrom, Write},
     path::Path,
+    time::{Duration, Instant},
 };
 
-type WrapCircuit = WrappedCircuit<DefaultParameters, Groth16WrapperParameters, D>;
+/// The circuit wrapping the proofs generated with the plonky2 parameters `P`
+/// into a circuit which could be verified by Groth16. The Groth16 wrapper
+/// parameters require the wrapped proofs to be over the same field `F`, while
+/// the configuration (e.g., the hasher) of the wrapped proofs could differ.
+type WrapCircuit<P> = WrappedCircuit<P, Groth16WrapperParameters, D>;
+
+/// Time spent in each stage of the asset files generation
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AssetGenTimings {
+    /// Serialization of the circuit data to `circuit.bin`
+    pub save_circuit_data: Duration,
+    /// Building of the wrapped circuit
+    pub wrap_circuit: Duration,
+    /// Extraction of the common and verifier data of the wrapped circuit
+    pub extract_verifier_data: Duration,
+    /// Compilation of the circuit and setup of the keys by gnark-utils, generating
+    /// `r1cs.bin`, `pk.bin`, `vk.bin` and `verifier.sol`
+    pub gnark_compile_and_setup: Duration,
+    /// Addition of the circuit digest to the verifier contract
+    pub add_circuit_digest: Duration,
+    /// Wall time of the whole generation
+    pub total: Duration,
+}
+
+impl AssetGenTimings {
+    /// Sum of the time spent in each stage
+    pub fn stages_total(&self) -> Duration {
+        self.save_circuit_data
+            + self.wrap_circuit
+            + self.extract_verifier_data
+            + self.gnark_compile_and_setup
+            + self.add_circuit_digest
+    }
+}
+
+/// Options of the asset files generation, to control where the files are
+/// generated and what to do with the files already found there
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssetOptions {
+    /// The dir where the asset files are generated
+    pub dir: String,
+    /// Overwrite the files of the dir if it's not empty; otherwise the
+    /// generation fails before compiling if the dir contains any file
+    pub overwrite: bool,
+    /// Create the dir and its parents if not exist; otherwise the generation
+    /// fails before compiling if the dir doesn't exist
+    pub create_dir_all: bool,
+}
+
+impl AssetOptions {
+    /// The options of `compile_and_generate_assets`, creating the dir if not
+    /// exist and overwriting the existing files.
+    pub fn new(dir: &str) -> Self {
+        Self {
+            dir: dir.to_string(),
+            overwrite: true,
+            create_dir_all: true,
+        }
+    }
+
+    /// Check the asset dir against the options, creating it if required.
+    fn prepare_dir(&self) -> Result<()> {
+        let dir = Path::new(&self.dir);
+        if !dir.exists() {
+            if !self.create_dir_all {
+                bail!("The asset dir {} doesn't exist", self.dir);
+            }
+            create_dir_all(dir)?;
+        } else if !self.overwrite && read_dir(dir)?.next().is_some() {
+            bail!(
+                "The asset dir {} is not empty, and overwriting is disabled",
+                self.dir
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Same as `compile_and_generate_assets`, but the asset dir is checked
+/// against the options before compiling.
+pub fn compile_and_generate_assets_with_opts(
+    circuit_data: CircuitData<F, C, D>,
+    options: &AssetOptions,
+) -> Result<String> {
+    options.prepare_dir()?;
+
+    compile_and_generate_assets(circuit_data, &options.dir)
+}
 
 /// Compile the circuit data and generate the asset files of `r1cs.bin`,
 /// `pk.bin`, `vk.bin` and `verifier.sol`.
@@ -27,19 +120,63 @@ pub fn compile_and_generate_assets(
     circuit_data: CircuitData<F, C, D>,
     dst_asset_dir: &str,
 ) -> Result<String> {
+    compile_and_generate_assets_with_timings(circuit_data, dst_asset_dir)
+        .map(|(verifier_contract_file_path, _)| verifier_contract_file_path)
+}
+
+/// Same as `compile_and_generate_assets`, but it also returns the time spent
+/// in each stage of the generation.
+pub fn compile_and_generate_assets_with_timings(
+    circuit_data: CircuitData<F, C, D>,
+    dst_asset_dir: &str,
+) -> Result<(String, AssetGenTimings)> {
+    compile_and_generate_assets_for::<DefaultParameters>(circuit_data, dst_asset_dir)
+}
+
+/// Same as `compile_and_generate_assets_with_timings`, but for a circuit
+/// built with the plonky2 parameters `P` instead of the default ones.
+pub fn compile_and_generate_assets_for<P: PlonkParameters<D, Field = F>>(
+    circuit_data: CircuitData<F, P::Config, D>,
+    dst_asset_dir: &str,
+) -> Result<(String, AssetGenTimings)>
+where
+    <P::Config as GenericConfig<D>>::Hasher: AlgebraicHasher<F>,
+{
+    // Circuits built with an insecure configuration, e.g. `test_config`, must never reach
+    // production verifiers, as their proofs could be forged. The conjectured security is
+    // computed from the FRI parameters, rather than trusting the `security_bits` of the config.
+    let fri_config = &circuit_data.common.config.fri_config;
+    let security_bits =
+        fri_config.rate_bits * fri_config.num_query_rounds + fri_config.proof_of_work_bits as usize;
+    ensure!(
+        security_bits >= MIN_SECURITY_BITS,
+        "circuit security of {security_bits} bits is below the minimum of {MIN_SECURITY_BITS} bits required to generate assets"
+    );
+
+    let mut timings = AssetGenTimings::default();
+    let start = Instant::now();
+
     // Save the circuit data to file `circuit.bin` in the asset dir. It could be
     // reused in proving.
+    let now = Instant::now();
     save_circuit_data(&circuit_data, dst_asset_dir)?;
+    timings.save_circuit_data = now.elapsed();
 
     // Create the wrapped circuit.
-    let wrapper = WrapCircuit::build_from_raw_circuit(circuit_data);
+    let now = Instant::now();
+    let wrapper = WrapCircuit::<P>::build_from_raw_circuit(circuit_data);
+    timings.wrap_circuit = now.elapsed();
 
     // Serialize the circuit data, verifier data and public inputs to JSON.
+    let now = Instant::now();
     let common_data = serde_json::to_string(&wrapper.wrapper_circuit.data.common)?;
     let verifier_data = serde_json::to_string(&wrapper.wrapper_circuit.data.verifier_only)?;
+    timings.extract_verifier_data = now.elapsed();
 
     // Generate these asset files by gnark-utils.
+    let now = Instant::now();
     gnark_utils::compile_and_generate_assets(&common_data, &verifier_data, dst_asset_dir)?;
+    timings.gnark_compile_and_setup = now.elapsed();
 
     // Generate the full file path of the Solidity verifier contract.
     let verifier_contract_file_path = Path::new(dst_asset_dir)
@@ -48,13 +185,24 @@ pub fn compile_and_generate_assets(
         .to_string();
 
     // Add a constant of circuit digest to the verifier contract file.
+    let now = Instant::now();
     add_circuit_digest_to_verifier_contract(&verifier_contract_file_path, &wrapper)?;
+    timings.add_circuit_digest = now.elapsed();
 
-    Ok(verifier_contract_file_path)
+    timings.total = start.elapsed();
+    log::info!("Groth16 asset files generated: {timings:?}");
+
+    Ok((verifier_contract_file_path, timings))
 }
 
 /// Save the circuit data to file `circuit.bin` in the asset dir.
-fn save_circuit_data(circuit_data: &CircuitData<F, C, D>, dst_asset_dir: &str) -> Result<()> {
+fn save_circuit_data<Cfg: GenericConfig<D, F = F> + 'static>(
+    circuit_data: &CircuitData<F, Cfg, D>,
+    dst_asset_dir: &str,
+) -> Result<()>
+where
+    Cfg::Hasher: AlgebraicHasher<F>,
+{
     // Serialize the circuit data.
     let data = serialize_circuit_data(circuit_data)?;
 
@@ -65,7 +213,10 @@ fn save_circuit_data(circuit_data: &CircuitData<F, C, D>, dst_asset_dir: &str) -
 
 /// Get the wrapped circuit digest.
 /// <https://github.com/succinctlabs/succinctx/blob/9df6a9db651507d60ffa2d75eda3fe526d13f90a/plonky2x/core/src/backend/function/mod.rs#L97>
-fn wrapped_circuit_digest(wrapper: &WrapCircuit) -> String {
+fn wrapped_circuit_digest<P: PlonkParameters<D, Field = F>>(wrapper: &WrapCircuit<P>) -> String
+where
+    <P::Config as GenericConfig<D>>::Hasher: AlgebraicHasher<F>,
+{
     // to_bytes() returns the representation as LE, but we want to save it on-chain as BE
     // because that is the format of the public input to the gnark plonky2 verifier.
     let mut circuit_digest_bytes = wrapper
@@ -93,10 +244,13 @@ fn wrapped_circuit_digest(wrapper: &WrapCircuit) -> String {
 }
 
 /// Add a constant of circuit digest to the verifier contract file.
-fn add_circuit_digest_to_verifier_contract(
+fn add_circuit_digest_to_verifier_contract<P: PlonkParameters<D, Field = F>>(
     contract_file_path: &str,
-    wrapper: &WrapCircuit,
-) -> Result<()> {
+    wrapper: &WrapCircuit<P>,
+) -> Result<()>
+where
+    <P::Config as GenericConfig<D>>::Hasher: AlgebraicHasher<F>,
+{
     // Get the wrapped circuit digest.
     let circuit_digest = wrapped_circuit_digest(wrapper);
 
@@ -122,3 +276,81 @@ fn add_circuit_digest_to_verifier_contract(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        utils::{deserialize_circuit_data, write_file},
+        FullProof, Groth16Proof, Groth16Prover,
+    };
+    use plonky2::plonk::{
+        circuit_builder::CircuitBuilder, circuit_data::CircuitConfig, proof::ProofWithPublicInputs,
+    };
+    use std::{env, fs::remove_dir_all};
+
+    /// Check that the generic compilation and proving instantiated with the
+    /// default parameters match the default field and config of the framework.
+    #[test]
+    fn test_default_parameters_instantiation() {
+        let _: fn(CircuitData<F, C, D>, &str) -> Result<(String, AssetGenTimings)> =
+            compile_and_generate_assets_for::<DefaultParameters>;
+        let _: fn(&str) -> Result<Groth16Prover> = Groth16Prover::<DefaultParameters>::new_for;
+        let _: fn(&Groth16Proof, &ProofWithPublicInputs<F, C, D>) -> Result<FullProof> =
+            FullProof::new::<C>;
+        let _: fn(&[u8]) -> Result<CircuitData<F, C, D>> = deserialize_circuit_data;
+    }
+
+    /// Test that the asset dir is checked against the options before
+    /// compiling.
+    #[test]
+    fn test_asset_options() {
+        let build_circuit = || {
+            let mut cb = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+            let x = cb.add_virtual_target();
+            cb.register_public_input(x);
+            cb.build::<C>()
+        };
+
+        let dir = env::temp_dir().join("asset_options");
+        let _ = remove_dir_all(&dir);
+        write_file(dir.join("full_proof.bin"), &[0; 32]).unwrap();
+        let options = AssetOptions {
+            overwrite: false,
+            ..AssetOptions::new(&dir.to_string_lossy())
+        };
+        let err = compile_and_generate_assets_with_opts(build_circuit(), &options)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("not empty"), "{err}");
+
+        let options = AssetOptions {
+            create_dir_all: false,
+            ..AssetOptions::new(&dir.join("missing").to_string_lossy())
+        };
+        let err = compile_and_generate_assets_with_opts(build_circuit(), &options)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("doesn't exist"), "{err}");
+    }
+
+    /// Test that circuits built with an insecure configuration are rejected
+    /// before generating any asset.
+    #[test]
+    fn test_insecure_config() {
+        let mut config = CircuitConfig::standard_recursion_config();
+        config.fri_config.num_query_rounds = 1;
+        // the claimed security is ignored, as it is computed from the FRI parameters
+        assert!(config.security_bits >= MIN_SECURITY_BITS);
+        let mut cb = CircuitBuilder::<F, D>::new(config);
+        let x = cb.add_virtual_target();
+        cb.register_public_input(x);
+
+        let dir = env::temp_dir().join("insecure_config");
+        let err = compile_and_generate_assets(cb.build::<C>(), &dir.to_string_lossy())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("below the minimum"), "{err}");
+        assert!(!dir.join(CIRCUIT_DATA_FILENAME).exists());
+    }
+}
@@ -0,0 +1,95 @@
+//! Debugging helpers for the plonky2 public inputs wrapped in Groth16
+
+use crate::F;
+use mr_plonky2_circuits::query2::revelation::revelation_layout;
+use mrp2_public_inputs::revelation::total_len;
+use plonky2::field::types::PrimeField64;
+use std::fmt::Write;
+
+/// Number of public inputs of a revelation proof not depending on the number
+/// of NFT IDs.
+const NUM_FIXED_PIS: usize = total_len(0);
+
+/// Format the public inputs of a revelation proof, one line for each region
+/// with its name, its range and its decoded value. The number of NFT IDs is
+/// derived from the number of public inputs.
+pub fn format_pis(pis: &[F]) -> String {
+    let mut output = String::new();
+    if pis.len() < NUM_FIXED_PIS {
+        writeln!(
+            output,
+            "{} public inputs, fewer than the {} of a revelation proof: {:?}",
+            pis.len(),
+            NUM_FIXED_PIS,
+            pis
+        )
+        .unwrap();
+        return output;
+    }
+
+    for (name, range) in revelation_layout(pis.len() - NUM_FIXED_PIS) {
+        let values = pis[range.clone()]
+            .iter()
+            .map(|f| f.to_canonical_u64())
+            .collect::<Vec<_>>();
+        let decoded = match name {
+            // The addresses and the block hash are exposed as u32 limbs with
+            // little-endian bytes.
+            "smart_contract_address" | "user_address" | "client_address" | "block_header" => {
+                let bytes = values
+                    .iter()
+                    .flat_map(|v| (*v as u32).to_le_bytes())
+                    .collect::<Vec<_>>();
+                format!("0x{}", hex::encode(bytes))
+            }
+            _ if values.len() == 1 => values[0].to_string(),
+            _ => format!("{values:?}"),
+        };
+        writeln!(output, "{name} [{}..{}]: {decoded}", range.start, range.end).unwrap();
+    }
+
+    output
+}
+
+/// Print the public inputs of a revelation proof, as formatted by
+/// `format_pis`.
+pub fn debug_print_pis(pis: &[F]) {
+    print!("{}", format_pis(pis));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use plonky2::field::types::Field;
+
+    #[test]
+    fn test_format_pis() {
+        const L: usize = 3;
+
+        let pis = (0..L + NUM_FIXED_PIS)
+            .map(F::from_canonical_usize)
+            .collect::<Vec<_>>();
+        let output = format_pis(&pis);
+
+        for label in [
+            "block_number [0..1]: 0",
+            "smart_contract_address [4..9]: 0x",
+            "nft_ids [16..19]: [16, 17, 18]",
+            "block_header [19..27]",
+            "result_commitment [27..35]",
+            "total_matching [35..36]: 35",
+            "commitment_hash [38..39]: 38",
+            "salt [39..40]: 39",
+            "query_commitment [40..48]",
+            "range_commitment [48..52]",
+            "block_db_root [52..56]",
+            "client_address [56..61]: 0x",
+            "keys_commitment [61..65]",
+        ] {
+            assert!(output.contains(label), "missing {label} in:\n{output}");
+        }
+
+        // Fewer public inputs than the fixed ones are printed raw.
+        assert!(format_pis(&pis[..10]).contains("fewer than"));
+    }
+}
@@ -6,6 +6,53 @@ use revm::{
     primitives::{CreateScheme, ExecutionResult, Output, TransactTo, TxEnv},
     InMemoryDB, EVM,
 };
+use std::{error::Error, fmt, thread, time::Duration};
+
+/// The default number of attempts to deploy the contract.
+pub const DEFAULT_DEPLOY_ATTEMPTS: usize = 3;
+
+/// The default delay before retrying a failed deployment, doubled at each
+/// retry.
+pub const DEFAULT_DEPLOY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Error returned when the contract deployment fails. It could be transient
+/// (e.g. under load in CI) and worth retrying, unlike a failure of the call to
+/// the deployed contract, which is a genuine verification failure.
+#[derive(Debug)]
+pub struct DeployError(String);
+
+impl fmt::Display for DeployError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for DeployError {}
+
+/// Run the operation up to `attempts` times, retrying with an exponential
+/// backoff starting from `backoff` only if it fails with a `DeployError`.
+/// Return the result of the last attempt.
+pub fn retry_deploy<T>(
+    attempts: usize,
+    backoff: Duration,
+    mut op: impl FnMut() -> Result<T>,
+) -> Result<T> {
+    let mut delay = backoff;
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Err(error) if attempt < attempts && error.is::<DeployError>() => {
+                log::warn!(
+                    "Failed to deploy the contract at attempt {attempt}/{attempts}, retrying in {delay:?}: {error}"
+                );
+                thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
 
 /// Deploy contract and then call with calldata.
 /// Return the gas_used and the output bytes of call to deployed contract if
@@ -23,21 +70,28 @@ pub fn deploy_and_call(deployment_code: Vec<u8>, calldata: Vec<u8>) -> Result<(u
         ..Default::default()
     };
 
-    let result = evm.transact_commit().unwrap();
+    let result = evm
+        .transact_commit()
+        .map_err(|err| DeployError(format!("Contract deployment transaction fails: {err:?}")))?;
     let contract = match result {
         ExecutionResult::Success {
             output: Output::Create(_, Some(contract)),
             ..
         } => contract,
-        ExecutionResult::Revert { gas_used, output } =>
-            bail!(
+        ExecutionResult::Revert { gas_used, output } => {
+            return Err(DeployError(format!(
                 "Contract deployment transaction reverts with gas_used {gas_used} and output {:#x}",
                 output
-            ),
-        ExecutionResult::Halt { reason, gas_used } => bail!(
+            ))
+            .into())
+        }
+        ExecutionResult::Halt { reason, gas_used } => {
+            return Err(DeployError(format!(
                 "Contract deployment transaction halts unexpectedly with gas_used {gas_used} and reason {:?}",
                 reason
-            ),
+            ))
+            .into())
+        }
         _ => unreachable!(),
     };
 
@@ -67,3 +121,42 @@ pub fn deploy_and_call(deployment_code: Vec<u8>, calldata: Vec<u8>) -> Result<(u
         _ => unreachable!(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_retry_deploy() {
+        // A transient deployment failure is retried until success.
+        let mut attempts = 0;
+        let result = retry_deploy(3, Duration::ZERO, || {
+            attempts += 1;
+            if attempts < 3 {
+                Err(DeployError("transient deployment failure".to_string()).into())
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        // The deployment is not retried beyond the configured attempts.
+        let mut attempts = 0;
+        let result: Result<()> = retry_deploy(2, Duration::ZERO, || {
+            attempts += 1;
+            Err(DeployError("transient deployment failure".to_string()).into())
+        });
+        assert!(result.unwrap_err().is::<DeployError>());
+        assert_eq!(attempts, 2);
+
+        // A verification failure is not retried.
+        let mut attempts = 0;
+        let result: Result<()> = retry_deploy(3, Duration::ZERO, || {
+            attempts += 1;
+            Err(anyhow!("Contract call transaction reverts"))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+}
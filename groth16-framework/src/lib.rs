@@ -68,13 +68,18 @@
 use plonky2::{field::goldilocks_field::GoldilocksField, plonk::config::PoseidonGoldilocksConfig};
 
 mod compiler;
+mod debug;
 mod evm;
 mod proof;
 pub mod prover;
+mod snarkjs;
 pub mod test_utils;
 pub mod utils;
 mod verifier;
 
+// The default field and config of the plonky2 proofs wrapped in Groth16. The
+// asset generation could also be instantiated for other configs over the same
+// field with `compile_and_generate_assets_for`.
 pub const D: usize = 2;
 pub type F = GoldilocksField;
 pub type C = PoseidonGoldilocksConfig;
@@ -82,10 +87,23 @@ pub type C = PoseidonGoldilocksConfig;
 // The function is used to generate the asset files of `circuit.bin`,
 // `r1cs.bin`, `pk.bin`, `vk.bin` and `verifier.sol`. It's only necessary to be
 // called for re-generating these asset files when the circuit code changes.
-pub use compiler::compile_and_generate_assets;
+pub use compiler::{
+    compile_and_generate_assets, compile_and_generate_assets_for,
+    compile_and_generate_assets_with_opts, compile_and_generate_assets_with_timings,
+    AssetGenTimings, AssetOptions,
+};
+
+// Print the plonky2 public inputs of a revelation proof with the name and the
+// decoded value of each region, for debugging.
+pub use debug::{debug_print_pis, format_pis};
+
+// The exported Groth16 proof struct, and the full proof combining it with the
+// plonky2 public inputs as saved in `full_proof.bin`
+pub use proof::{FullProof, Groth16Proof};
 
-// The exported Groth16 proof struct
-pub use proof::Groth16Proof;
+// Export the full proof to the `proof.json` and `public.json` files of
+// snarkjs, for verification with the existing JS tooling.
+pub use snarkjs::export_snarkjs;
 
 // The Groth16 prover is used to generate the proof which could be verified
 // both off-chain and on-chain.
@@ -95,7 +113,9 @@ pub use prover::groth16::Groth16Prover;
 
 pub use verifier::{
     // The EVM verifier is used for testing Solidity verification on-chain.
-    evm::EVMVerifier,
+    // Its bytecode could be checked against the verification key of `vk.bin`
+    // to catch a stale deployment.
+    evm::{EVMVerifier, VerifierKeyConstants},
     // The Groth16 verifier is used to verify the proof off-chain.
     // The asset dir must include `vk.bin` when creating the verifier.
     groth16::Groth16Verifier,
@@ -118,7 +138,7 @@ mod tests {
     };
     use rand::{thread_rng, Rng};
     use serial_test::serial;
-    use std::{array, path::Path};
+    use std::{array, path::Path, time::Duration};
 
     /// Test proving and verifying with a simple circuit.
     #[ignore] // Ignore for long running time in CI.
@@ -140,6 +160,30 @@ mod tests {
         test_groth16_proving_and_verification(ASSET_DIR, &proof);
     }
 
+    /// Test the timings of the asset files generation.
+    #[ignore] // Ignore for long running time in CI.
+    #[serial]
+    #[test]
+    fn test_asset_generation_timings() {
+        const ASSET_DIR: &str = "groth16_timings";
+
+        let (circuit_data, _) = plonky2_build_and_prove(ASSET_DIR);
+
+        let (_, timings) = compile_and_generate_assets_with_timings(circuit_data, ASSET_DIR)
+            .expect("Failed to generate the asset files");
+
+        // Each stage must take some time, and the gnark compilation and setup must dominate.
+        assert!(timings.save_circuit_data > Duration::ZERO);
+        assert!(timings.wrap_circuit > Duration::ZERO);
+        assert!(timings.extract_verifier_data > Duration::ZERO);
+        assert!(timings.gnark_compile_and_setup > Duration::ZERO);
+        assert!(timings.add_circuit_digest > Duration::ZERO);
+        // The stages must sum to roughly the total wall time.
+        let stages_total = timings.stages_total();
+        assert!(stages_total <= timings.total);
+        assert!(timings.total - stages_total < timings.total / 10);
+    }
+
     /// Build for the plonky2 circuit and generate the proof.
     fn plonky2_build_and_prove(asset_dir: &str) -> (CircuitData<F, C, D>, Vec<u8>) {
         let config = CircuitConfig::standard_recursion_config();
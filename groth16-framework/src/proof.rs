@@ -1,5 +1,12 @@
 //! The Groth16 proof struct
 
+use crate::{utils::hex_to_u256, D, F};
+use anyhow::{ensure, Result};
+use ethers::types::U256;
+use plonky2::{
+    field::types::PrimeField64,
+    plonk::{config::GenericConfig, proof::ProofWithPublicInputs},
+};
 use serde::{Deserialize, Serialize};
 
 /// Groth16 proof
@@ -16,3 +23,158 @@ pub struct Groth16Proof {
     /// The original raw public witness data is used to be verified off-chain.
     pub raw_public_witness: String,
 }
+
+/// The number of bytes of an U256 word in the full proof.
+const WORD_LEN: usize = 32;
+/// The number of bytes of a plonky2 public input in the full proof.
+const PI_LEN: usize = 8;
+/// The number of U256 words of the Groth16 proof points.
+const NUM_PROOF_WORDS: usize = 8;
+/// The number of U256 words of the Groth16 inputs.
+const NUM_INPUT_WORDS: usize = 3;
+
+/// The full proof saved as `full_proof.bin` and passed to the Solidity
+/// verifier. It's serialized as little-endian bytes of:
+/// `a + b + c + inputs + plonky2_public_inputs`.
+/// In the serialized bytes, each part has number as:
+/// - a, b, c: the Groth16 proof points, 8 * U256 = 256 bytes
+/// - inputs: the Groth16 inputs, 3 * U256 = 96 bytes
+/// - plonky2_public_inputs: 8 bytes for each public input exported by user
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FullProof {
+    /// The point A of the Groth16 proof
+    pub a: [U256; 2],
+    /// The point B of the Groth16 proof
+    pub b: [[U256; 2]; 2],
+    /// The point C of the Groth16 proof
+    pub c: [U256; 2],
+    /// The Groth16 inputs
+    pub inputs: [U256; NUM_INPUT_WORDS],
+    /// The plonky2 public inputs
+    pub plonky2_public_inputs: Vec<u64>,
+}
+
+impl FullProof {
+    /// Build the full proof from the Groth16 proof and the wrapped plonky2
+    /// proof, which could be generated with any config over the field `F`.
+    pub fn new<Cfg: GenericConfig<D, F = F>>(
+        groth16_proof: &Groth16Proof,
+        plonky2_proof: &ProofWithPublicInputs<F, Cfg, D>,
+    ) -> Result<Self> {
+        ensure!(
+            groth16_proof.proofs.len() == NUM_PROOF_WORDS,
+            "The Groth16 proof must have {NUM_PROOF_WORDS} proof words"
+        );
+        ensure!(
+            groth16_proof.inputs.len() == NUM_INPUT_WORDS,
+            "The Groth16 proof must have {NUM_INPUT_WORDS} input words"
+        );
+        let words = groth16_proof
+            .proofs
+            .iter()
+            .chain(&groth16_proof.inputs)
+            .map(|s| hex_to_u256(s))
+            .collect::<Result<Vec<_>>>()?;
+        let plonky2_public_inputs = plonky2_proof
+            .public_inputs
+            .iter()
+            .map(|f| f.to_canonical_u64())
+            .collect();
+
+        Ok(Self::from_words(&words, plonky2_public_inputs))
+    }
+
+    /// Deserialize the full proof from the bytes of `full_proof.bin`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let groth16_len = (NUM_PROOF_WORDS + NUM_INPUT_WORDS) * WORD_LEN;
+        ensure!(
+            bytes.len() >= groth16_len,
+            "The full proof must have at least {groth16_len} bytes, but got {}",
+            bytes.len()
+        );
+        let (groth16_bytes, pi_bytes) = bytes.split_at(groth16_len);
+        ensure!(
+            pi_bytes.len() % PI_LEN == 0,
+            "The length of the plonky2 public inputs bytes must be a multiple of {PI_LEN}"
+        );
+
+        let words: Vec<_> = groth16_bytes
+            .chunks(WORD_LEN)
+            .map(U256::from_little_endian)
+            .collect();
+        let plonky2_public_inputs = pi_bytes
+            .chunks(PI_LEN)
+            .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+
+        Ok(Self::from_words(&words, plonky2_public_inputs))
+    }
+
+    /// Serialize the full proof to the bytes of `full_proof.bin`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let groth16_bytes = self.proofs().into_iter().chain(self.inputs).flat_map(|u| {
+            let mut bytes = [0u8; WORD_LEN];
+            u.to_little_endian(&mut bytes);
+
+            bytes
+        });
+        let pi_bytes = self
+            .plonky2_public_inputs
+            .iter()
+            .flat_map(|pi| pi.to_le_bytes());
+
+        groth16_bytes.chain(pi_bytes).collect()
+    }
+
+    /// Return the proof words in the order expected by the `verifyProof`
+    /// function of the Solidity verifier contract.
+    pub fn proofs(&self) -> [U256; NUM_PROOF_WORDS] {
+        [
+            self.a[0],
+            self.a[1],
+            self.b[0][0],
+            self.b[0][1],
+            self.b[1][0],
+            self.b[1][1],
+            self.c[0],
+            self.c[1],
+        ]
+    }
+
+    fn from_words(words: &[U256], plonky2_public_inputs: Vec<u64>) -> Self {
+        Self {
+            a: [words[0], words[1]],
+            b: [[words[2], words[3]], [words[4], words[5]]],
+            c: [words[6], words[7]],
+            inputs: [words[8], words[9], words[10]],
+            plonky2_public_inputs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn test_full_proof_bytes_round_trip() {
+        let mut rng = thread_rng();
+        let bytes: Vec<u8> = (0..(NUM_PROOF_WORDS + NUM_INPUT_WORDS) * WORD_LEN + 5 * PI_LEN)
+            .map(|_| rng.gen())
+            .collect();
+
+        let proof = FullProof::from_bytes(&bytes).unwrap();
+        assert_eq!(proof.plonky2_public_inputs.len(), 5);
+        assert_eq!(proof.a[0], U256::from_little_endian(&bytes[..WORD_LEN]));
+        assert_eq!(
+            proof.inputs[2],
+            U256::from_little_endian(&bytes[10 * WORD_LEN..11 * WORD_LEN])
+        );
+        assert_eq!(proof.to_bytes(), bytes);
+
+        // Truncated bytes are rejected.
+        assert!(FullProof::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(FullProof::from_bytes(&bytes[..WORD_LEN]).is_err());
+    }
+}
@@ -1,31 +1,53 @@
 //! The prover used to generate the Groth16 proof.
 
 use crate::{
-    proof::Groth16Proof,
-    utils::{deserialize_circuit_data, hex_to_u256, read_file, CIRCUIT_DATA_FILENAME},
-    C, D, F,
+    proof::{FullProof, Groth16Proof},
+    utils::{deserialize_circuit_data, read_file, CIRCUIT_DATA_FILENAME},
+    D, F,
 };
 use anyhow::Result;
 use mr_plonky2_circuits::api::deserialize_proof;
-use plonky2::{
-    field::types::PrimeField64,
-    plonk::{circuit_data::CircuitData, proof::ProofWithPublicInputs},
+use plonky2::plonk::{
+    circuit_data::CircuitData,
+    config::{AlgebraicHasher, GenericConfig},
+    proof::ProofWithPublicInputs,
 };
 use plonky2x::backend::{
-    circuit::{DefaultParameters, Groth16WrapperParameters},
+    circuit::{DefaultParameters, Groth16WrapperParameters, PlonkParameters},
     wrapper::wrap::WrappedCircuit,
 };
 use std::path::Path;
 
-/// Groth16 prover
+/// Groth16 prover of the plonky2 proofs generated with the parameters `P`,
+/// which must be the same ones the asset files were generated for with
+/// `compile_and_generate_assets_for`.
 #[derive(Debug)]
-pub struct Groth16Prover {
+pub struct Groth16Prover<P: PlonkParameters<D, Field = F> = DefaultParameters>
+where
+    <P::Config as GenericConfig<D>>::Hasher: AlgebraicHasher<F>,
+{
     /// Wrapped circuit instance
-    wrapper: WrappedCircuit<DefaultParameters, Groth16WrapperParameters, D>,
+    wrapper: WrappedCircuit<P, Groth16WrapperParameters, D>,
 }
 
 impl Groth16Prover {
     pub fn new(asset_dir: &str) -> Result<Self> {
+        Self::new_for(asset_dir)
+    }
+
+    /// Initialize the Groth16 prover from bytes.
+    pub fn from_bytes(r1cs: Vec<u8>, pk: Vec<u8>, circuit: Vec<u8>) -> Result<Self> {
+        Self::from_bytes_for(r1cs, pk, circuit)
+    }
+}
+
+impl<P: PlonkParameters<D, Field = F>> Groth16Prover<P>
+where
+    <P::Config as GenericConfig<D>>::Hasher: AlgebraicHasher<F>,
+{
+    /// Same as `Groth16Prover::new`, but for the plonky2 parameters `P`
+    /// instead of the default ones.
+    pub fn new_for(asset_dir: &str) -> Result<Self> {
         // Initialize the Go prover.
         gnark_utils::init_prover(asset_dir)?;
 
@@ -38,8 +60,9 @@ impl Groth16Prover {
         Ok(Self { wrapper })
     }
 
-    /// Initialize the Groth16 prover from bytes.
-    pub fn from_bytes(r1cs: Vec<u8>, pk: Vec<u8>, circuit: Vec<u8>) -> Result<Self> {
+    /// Same as `Groth16Prover::from_bytes`, but for the plonky2 parameters `P`
+    /// instead of the default ones.
+    pub fn from_bytes_for(r1cs: Vec<u8>, pk: Vec<u8>, circuit: Vec<u8>) -> Result<Self> {
         // Deserialize the circuit data.
         let circuit_data = deserialize_circuit_data(&circuit)?;
 
@@ -65,7 +88,7 @@ impl Groth16Prover {
     /// - plonky2_proof.public_inputs: the little-endian bytes of public inputs exported by user
     pub fn prove(&self, plonky2_proof: &[u8]) -> Result<Vec<u8>> {
         // Deserialize the plonky2 proof.
-        let plonky2_proof = deserialize_proof(plonky2_proof)?;
+        let plonky2_proof = deserialize_proof::<F, P::Config, D>(plonky2_proof)?;
 
         // Generate the groth16 proof.
         let groth16_proof = self.generate_groth16_proof(&plonky2_proof)?;
@@ -76,7 +99,7 @@ impl Groth16Prover {
 
     pub(crate) fn generate_groth16_proof(
         &self,
-        plonky2_proof: &ProofWithPublicInputs<F, C, D>,
+        plonky2_proof: &ProofWithPublicInputs<F, P::Config, D>,
     ) -> Result<Groth16Proof> {
         // Generate the wrapped proof.
         let wrapped_output = self.wrapper.prove(plonky2_proof)?;
@@ -98,7 +121,12 @@ impl Groth16Prover {
 
 /// Read the circuit data from file `circuit.bin` in the asset dir. This is
 /// the circuit data of the final wrapped proof.
-fn load_circuit_data(asset_dir: &str) -> Result<CircuitData<F, C, D>> {
+fn load_circuit_data<Cfg: GenericConfig<D, F = F> + 'static>(
+    asset_dir: &str,
+) -> Result<CircuitData<F, Cfg, D>>
+where
+    Cfg::Hasher: AlgebraicHasher<F>,
+{
     // Read from file.
     let file_path = Path::new(asset_dir).join(CIRCUIT_DATA_FILENAME);
     let bytes = read_file(file_path)?;
@@ -113,34 +141,10 @@ fn load_circuit_data(asset_dir: &str) -> Result<CircuitData<F, C, D>> {
 /// - groth16_proof.proofs: 8 * U256 = 256 bytes
 /// - groth16_proof.inputs: 3 * U256 = 96 bytes
 /// - plonky2_proof.public_inputs: the little-endian bytes of public inputs exported by user
-pub fn combine_proofs(
+/// The combined bytes could be parsed back with `FullProof::from_bytes`.
+pub fn combine_proofs<Cfg: GenericConfig<D, F = F>>(
     groth16_proof: Groth16Proof,
-    plonky2_proof: ProofWithPublicInputs<F, C, D>,
+    plonky2_proof: ProofWithPublicInputs<F, Cfg, D>,
 ) -> Result<Vec<u8>> {
-    // Connect the proofs and inputs of the Groth16 proof, and convert to U256s.
-    let groth16_u256s = groth16_proof
-        .proofs
-        .into_iter()
-        .chain(groth16_proof.inputs)
-        .map(|s| hex_to_u256(&s))
-        .collect::<Result<Vec<_>>>()?;
-
-    // Convert the Groth16 U256s to bytes.
-    let groth16_bytes = groth16_u256s.iter().flat_map(|u| {
-        let mut bytes = [0u8; 32];
-        u.to_little_endian(&mut bytes);
-
-        bytes
-    });
-
-    // Convert the plonky2 public inputs to bytes.
-    let plonky2_pi_bytes = plonky2_proof
-        .public_inputs
-        .iter()
-        .flat_map(|f| f.to_canonical_u64().to_le_bytes());
-
-    // Connect the Groth16 bytes with the plonky2 public inputs bytes.
-    let bytes = groth16_bytes.chain(plonky2_pi_bytes).collect();
-
-    Ok(bytes)
+    Ok(FullProof::new(&groth16_proof, &plonky2_proof)?.to_bytes())
 }
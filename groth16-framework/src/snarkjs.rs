@@ -0,0 +1,115 @@
+//! Export the Groth16 proofs in the JSON format of snarkjs
+
+use crate::{proof::FullProof, utils::write_file};
+use anyhow::Result;
+use ethers::types::U256;
+use serde::Serialize;
+use std::path::Path;
+
+/// The filename of the exported snarkjs proof.
+pub const SNARKJS_PROOF_FILENAME: &str = "proof.json";
+
+/// The filename of the exported snarkjs public signals.
+pub const SNARKJS_PUBLIC_FILENAME: &str = "public.json";
+
+/// The Groth16 proof in the format of `proof.json` of snarkjs. The points are
+/// in projective coordinates, and each coordinate is a decimal string.
+#[derive(Debug, Serialize)]
+struct SnarkjsProof {
+    pi_a: [String; 3],
+    pi_b: [[String; 2]; 3],
+    pi_c: [String; 3],
+    protocol: &'static str,
+    curve: &'static str,
+}
+
+impl From<&FullProof> for SnarkjsProof {
+    fn from(proof: &FullProof) -> Self {
+        let [one, zero] = [U256::one(), U256::zero()].map(|u| u.to_string());
+        let [a, c] = [proof.a, proof.c].map(|[x, y]| [x.to_string(), y.to_string(), one.clone()]);
+        // The coordinates of point B are saved as `(c1, c0)` in the full proof,
+        // as expected by the EVM pairing precompile, while snarkjs expects
+        // them as `(c0, c1)`.
+        let [bx, by] = proof.b.map(|[c1, c0]| [c0.to_string(), c1.to_string()]);
+
+        Self {
+            pi_a: a,
+            pi_b: [bx, by, [one, zero]],
+            pi_c: c,
+            protocol: "groth16",
+            curve: "bn128",
+        }
+    }
+}
+
+/// Export the full proof (the bytes of `full_proof.bin`) to the `proof.json`
+/// and `public.json` files of snarkjs in the output dir, so that it could be
+/// verified by the existing JS tooling. The public signals are the Groth16
+/// inputs, while the plonky2 public inputs are not included since they are
+/// committed to by these inputs. This function creates the output dir if not
+/// exist.
+pub fn export_snarkjs(proof_bytes: &[u8], out_dir: &str) -> Result<()> {
+    let proof = FullProof::from_bytes(proof_bytes)?;
+
+    let snarkjs_proof = serde_json::to_vec_pretty(&SnarkjsProof::from(&proof))?;
+    write_file(
+        Path::new(out_dir).join(SNARKJS_PROOF_FILENAME),
+        &snarkjs_proof,
+    )?;
+
+    let public_signals = proof.inputs.map(|u| u.to_string());
+    let public_signals = serde_json::to_vec_pretty(&public_signals)?;
+    write_file(
+        Path::new(out_dir).join(SNARKJS_PUBLIC_FILENAME),
+        &public_signals,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::read_file;
+    use rand::{thread_rng, Rng};
+    use serde_json::Value;
+    use std::env;
+
+    #[test]
+    fn test_export_snarkjs() {
+        let mut rng = thread_rng();
+        // 8 proof words and 3 input words of 32 bytes, and 2 plonky2 public
+        // inputs of 8 bytes.
+        let bytes: Vec<u8> = (0..11 * 32 + 2 * 8).map(|_| rng.gen()).collect();
+        let out_dir = env::temp_dir().join("snarkjs_export");
+        let out_dir = out_dir.to_string_lossy();
+
+        export_snarkjs(&bytes, &out_dir).unwrap();
+
+        let read_json = |filename| -> Value {
+            let data = read_file(Path::new(&*out_dir).join(filename)).unwrap();
+            serde_json::from_slice(&data).unwrap()
+        };
+        let proof = read_json(SNARKJS_PROOF_FILENAME);
+        assert_eq!(proof["pi_a"].as_array().unwrap().len(), 3);
+        assert_eq!(proof["pi_c"].as_array().unwrap().len(), 3);
+        let pi_b = proof["pi_b"].as_array().unwrap();
+        assert_eq!(pi_b.len(), 3);
+        assert!(pi_b.iter().all(|p| p.as_array().unwrap().len() == 2));
+        assert_eq!(proof["protocol"], "groth16");
+        assert_eq!(proof["curve"], "bn128");
+
+        // The coordinates are decimal strings, with point B in snarkjs order.
+        let full_proof = FullProof::from_bytes(&bytes).unwrap();
+        assert_eq!(proof["pi_a"][0], full_proof.a[0].to_string());
+        assert_eq!(proof["pi_a"][2], "1");
+        assert_eq!(proof["pi_b"][0][0], full_proof.b[0][1].to_string());
+        assert_eq!(proof["pi_b"][2][1], "0");
+
+        let public = read_json(SNARKJS_PUBLIC_FILENAME);
+        let public = public.as_array().unwrap();
+        assert_eq!(public.len(), 3);
+        assert_eq!(public[2], full_proof.inputs[2].to_string());
+
+        // Invalid full proof bytes are rejected.
+        assert!(export_snarkjs(&bytes[..32], &out_dir).is_err());
+    }
+}
@@ -3,13 +3,43 @@
 use crate::{
     prover::groth16::combine_proofs,
     utils::{hex_to_u256, read_file, write_file},
-    EVMVerifier, Groth16Proof, Groth16Prover, Groth16Verifier, C, D, F,
+    EVMVerifier, FullProof, Groth16Proof, Groth16Prover, Groth16Verifier, VerifierKeyConstants, C,
+    D, F,
+};
+use anyhow::{anyhow, Result};
+use ethers::{
+    abi::{encode, Contract, Token},
+    types::U256,
 };
-use ethers::abi::{Contract, Token};
 use mr_plonky2_circuits::api::deserialize_proof;
 use plonky2::{field::types::PrimeField64, plonk::proof::ProofWithPublicInputs};
 use std::path::Path;
 
+/// The filename of the plonky2 proof in the golden test vector.
+pub const GOLDEN_PLONKY2_PROOF_FILENAME: &str = "plonky2_proof.bin";
+/// The filename of the ABI encoded query in the golden test vector.
+pub const GOLDEN_QUERY_FILENAME: &str = "query.bin";
+/// The filename of the full proof in the golden test vector.
+pub const GOLDEN_FULL_PROOF_FILENAME: &str = "full_proof.bin";
+/// The filename of the `processQuery` calldata in the golden test vector.
+pub const GOLDEN_CALLDATA_FILENAME: &str = "calldata.bin";
+/// The filename of the ABI encoded `processQuery` output in the golden test vector.
+pub const GOLDEN_EXPECTED_OUTPUT_FILENAME: &str = "expected_output.bin";
+/// The filename of the plonky2 public inputs in the golden test vector.
+pub const GOLDEN_PUBLIC_INPUTS_FILENAME: &str = "public_inputs.bin";
+/// The filename of the Groth16 verifying key in the golden test vector.
+pub const GOLDEN_VK_FILENAME: &str = "vk.bin";
+
+/// The deterministic files of the golden test vector, which external clients
+/// could compare byte by byte.
+pub const GOLDEN_DETERMINISTIC_FILENAMES: [&str; 5] = [
+    GOLDEN_PLONKY2_PROOF_FILENAME,
+    GOLDEN_QUERY_FILENAME,
+    GOLDEN_EXPECTED_OUTPUT_FILENAME,
+    GOLDEN_PUBLIC_INPUTS_FILENAME,
+    GOLDEN_VK_FILENAME,
+];
+
 /// Convert the plonky2 proof public inputs to bytes and save to a file
 /// `plonky2_proof_pis.bin` in the specified dir.
 pub fn save_plonky2_proof_pis(dir: &str, proof: &ProofWithPublicInputs<F, C, D>) {
@@ -24,6 +54,82 @@ pub fn save_plonky2_proof_pis(dir: &str, proof: &ProofWithPublicInputs<F, C, D>)
     write_file(file_path, &bytes).unwrap();
 }
 
+/// Write a golden test vector for the `processQuery` function of the query2
+/// Solidity verifier into `out_dir`, for the clients which are not written in
+/// Rust. The vector contains these files:
+/// - `plonky2_proof.bin`: the serialized plonky2 proof being wrapped
+/// - `query.bin`: the ABI encoded `query` argument of `processQuery`
+/// - `full_proof.bin`: the combined Groth16 proof and plonky2 public inputs
+/// - `calldata.bin`: the ABI encoded calldata of `processQuery`
+/// - `expected_output.bin`: the ABI encoded NFT IDs returned by `processQuery`
+/// - `public_inputs.bin`: the plonky2 public inputs, as found at the end of the
+///   full proof
+/// - `vk.bin`: the Groth16 verifying key of the asset dir
+/// The Groth16 proof (and so the full proof and calldata) is randomized by
+/// gnark at each call and can't be pinned, so clients should only verify it,
+/// while the files listed in `GOLDEN_DETERMINISTIC_FILENAMES` are the same at
+/// each call if the plonky2 proof is generated from a fixed seed with the
+/// same assets, and could be compared byte by byte.
+/// The asset dir must include the files required by the Groth16 prover. It
+/// returns the calldata.
+pub fn write_golden_vector(
+    out_dir: &str,
+    asset_dir: &str,
+    plonky2_proof: &[u8],
+    query: Token,
+    expected_nft_ids: &[u32],
+) -> Result<Vec<u8>> {
+    let out_dir = Path::new(out_dir);
+
+    // Generate the full proof.
+    let prover = Groth16Prover::new(asset_dir)?;
+    let full_proof = prover.prove(plonky2_proof)?;
+
+    // Encode the calldata of `processQuery`, the full proof is passed as an
+    // array of bytes32.
+    let contract =
+        Contract::load(read_file(Path::new("test_data").join("query2.abi"))?.as_slice())?;
+    let fun = contract
+        .functions
+        .get("processQuery")
+        .and_then(|funs| funs.first())
+        .ok_or(anyhow!("No processQuery function found in the query2 ABI"))?;
+    let data = Token::Array(
+        full_proof
+            .chunks(32)
+            .map(|b| Token::FixedBytes(b.to_vec()))
+            .collect(),
+    );
+    let calldata = fun.encode_input(&[data, query.clone()])?;
+
+    // Encode the expected output.
+    let expected_output = encode(&[Token::Array(
+        expected_nft_ids
+            .iter()
+            .map(|id| Token::Uint(U256::from(*id)))
+            .collect(),
+    )]);
+
+    write_file(out_dir.join(GOLDEN_PLONKY2_PROOF_FILENAME), plonky2_proof)?;
+    write_file(out_dir.join(GOLDEN_QUERY_FILENAME), &encode(&[query]))?;
+    write_file(out_dir.join(GOLDEN_FULL_PROOF_FILENAME), &full_proof)?;
+    write_file(out_dir.join(GOLDEN_CALLDATA_FILENAME), &calldata)?;
+    write_file(
+        out_dir.join(GOLDEN_EXPECTED_OUTPUT_FILENAME),
+        &expected_output,
+    )?;
+    let public_inputs: Vec<_> = FullProof::from_bytes(&full_proof)?
+        .plonky2_public_inputs
+        .iter()
+        .flat_map(|pi| pi.to_le_bytes())
+        .collect();
+    write_file(out_dir.join(GOLDEN_PUBLIC_INPUTS_FILENAME), &public_inputs)?;
+    let vk = read_file(Path::new(asset_dir).join(GOLDEN_VK_FILENAME))?;
+    write_file(out_dir.join(GOLDEN_VK_FILENAME), &vk)?;
+
+    Ok(calldata)
+}
+
 /// Test Groth16 proving, verification and Solidity verification.
 pub fn test_groth16_proving_and_verification(asset_dir: &str, plonky2_proof: &[u8]) {
     // Generate the Groth16 proof.
@@ -63,8 +169,17 @@ fn groth16_prove(asset_dir: &str, plonky2_proof: &[u8]) -> Groth16Proof {
     .unwrap();
 
     // Generate the full proof.
-    let full_proof = combine_proofs(groth16_proof.clone(), plonky2_proof).unwrap();
-    write_file(full_proof_path, &full_proof).unwrap();
+    let full_proof = combine_proofs(groth16_proof.clone(), plonky2_proof.clone()).unwrap();
+    write_file(&full_proof_path, &full_proof).unwrap();
+
+    // Check the on-disk full proof could be parsed back to the typed proof.
+    let full_proof = read_file(full_proof_path).unwrap();
+    let typed_full_proof = FullProof::from_bytes(&full_proof).unwrap();
+    assert_eq!(typed_full_proof.to_bytes(), full_proof);
+    assert_eq!(
+        typed_full_proof,
+        FullProof::new(&groth16_proof, &plonky2_proof).unwrap()
+    );
 
     groth16_proof
 }
@@ -103,6 +218,11 @@ fn evm_verify(asset_dir: &str, proof: &Groth16Proof) {
 
     let verifier =
         EVMVerifier::new(&solidity_file_path).expect("Failed to initialize the EVM verifier");
+    let vk = VerifierKeyConstants::from_asset_dir(asset_dir)
+        .expect("Failed to load the verification key");
+    verifier
+        .assert_vk_matches(&vk)
+        .expect("The verifier contract doesn't match the verification key");
 
     let verified = verifier.verify(calldata);
     assert!(verified.is_ok());
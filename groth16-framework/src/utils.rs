@@ -1,17 +1,26 @@
 //! Utility functions
 
-use crate::{C, D, F};
+use crate::{D, F};
 use anyhow::{anyhow, Result};
 use ethers::types::U256;
-use plonky2::plonk::circuit_data::CircuitData;
+use plonky2::{
+    fri::oracle::PolynomialBatch,
+    plonk::{
+        circuit_data::{CircuitData, ProverOnlyCircuitData},
+        config::{AlgebraicHasher, GenericConfig},
+    },
+    util::serialization::{Buffer, IoResult, Read as _, Write as _},
+};
 use recursion_framework::serialization::circuit_data_serialization::{
     CustomGateSerializer, CustomGeneratorSerializer,
 };
 use std::{
+    error::Error,
+    fmt,
     fs::{create_dir_all, File},
-    io::{Read, Write},
+    io::{self, Read, Write},
     marker::PhantomData,
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 /// The filename of the cached circuit data. This is the circuit data of the final wrapped proof.
@@ -31,11 +40,52 @@ pub fn hex_to_u256(s: &str) -> Result<U256> {
     Ok(u)
 }
 
+/// Error returned when an asset file cannot be read, keeping track of the
+/// offending path.
+#[derive(Debug)]
+pub struct FileReadError {
+    /// The path of the file failed to be read
+    pub path: PathBuf,
+    /// The underlying IO error
+    pub source: io::Error,
+}
+
+impl fmt::Display for FileReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Failed to read file {}: {}",
+            self.path.display(),
+            self.source
+        )?;
+        if self.source.kind() == io::ErrorKind::NotFound {
+            write!(
+                f,
+                " (did you run compile_and_generate_assets to generate the asset files?)"
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for FileReadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
 /// Read the data from a file.
 pub fn read_file<P: AsRef<Path>>(file_path: P) -> Result<Vec<u8>> {
+    let file_path = file_path.as_ref();
+    let to_error = |source| FileReadError {
+        path: file_path.to_path_buf(),
+        source,
+    };
+
     let mut data = vec![];
-    let mut fd = File::open(file_path)?;
-    fd.read_to_end(&mut data)?;
+    let mut fd = File::open(file_path).map_err(to_error)?;
+    fd.read_to_end(&mut data).map_err(to_error)?;
 
     Ok(data)
 }
@@ -54,35 +104,169 @@ pub fn write_file<P: AsRef<Path>>(file_path: P, data: &[u8]) -> Result<()> {
     Ok(())
 }
 
-/// Serialize the circuit data to bytes.
-pub fn serialize_circuit_data(circuit_data: &CircuitData<F, C, D>) -> Result<Vec<u8>> {
+/// Serialize the circuit data to bytes. The circuit data could be built with
+/// any config over the field `F`, not only the default config `C`.
+pub fn serialize_circuit_data<Cfg: GenericConfig<D, F = F> + 'static>(
+    circuit_data: &CircuitData<F, Cfg, D>,
+) -> Result<Vec<u8>>
+where
+    Cfg::Hasher: AlgebraicHasher<F>,
+{
     // Assume that the circuit data could always be serialized by the custom
     // gate and generator serializers of recursive-framework.
     circuit_data
         .to_bytes(
             &CustomGateSerializer,
-            &CustomGeneratorSerializer::<C, D> {
+            &CustomGeneratorSerializer::<Cfg, D> {
                 _phantom: PhantomData,
             },
         )
         .map_err(|err| anyhow!("Failed to serialize circuit data: {err:?}"))
 }
 
-/// Deserialize bytes to the circuit data.
-pub fn deserialize_circuit_data(bytes: &[u8]) -> Result<CircuitData<F, C, D>> {
+/// Deserialize bytes to the circuit data, built with any config over the
+/// field `F`.
+pub fn deserialize_circuit_data<Cfg: GenericConfig<D, F = F> + 'static>(
+    bytes: &[u8],
+) -> Result<CircuitData<F, Cfg, D>>
+where
+    Cfg::Hasher: AlgebraicHasher<F>,
+{
     // Assume that the circuit data could always be deserialized by the custom
     // gate and generator serializers of recursive-framework.
     CircuitData::from_bytes(
         bytes,
         &CustomGateSerializer,
-        &CustomGeneratorSerializer::<C, D> {
+        &CustomGeneratorSerializer::<Cfg, D> {
             _phantom: PhantomData,
         },
     )
     .map_err(|err| anyhow!("Failed to deserialize circuit data: {err:?}"))
 }
 
-/// Serialize reference of circuit data, then deserialize to implement clone.
-pub fn clone_circuit_data(circuit_data: &CircuitData<F, C, D>) -> Result<CircuitData<F, C, D>> {
-    deserialize_circuit_data(&serialize_circuit_data(circuit_data)?)
+/// Clone the circuit data. Everything is cloned in memory, including the
+/// constants and sigmas commitment of the prover-only data, except for the
+/// witness generators: they are boxed trait objects which cannot be cloned, so
+/// only them are serialized then deserialized. It avoids serializing the
+/// gates and the commitment, which is the slow part of
+/// `deserialize_circuit_data(&serialize_circuit_data(circuit_data)?)`.
+pub fn clone_circuit_data<Cfg: GenericConfig<D, F = F> + 'static>(
+    circuit_data: &CircuitData<F, Cfg, D>,
+) -> Result<CircuitData<F, Cfg, D>>
+where
+    Cfg::Hasher: AlgebraicHasher<F>,
+{
+    let common = circuit_data.common.clone();
+    let verifier_only = circuit_data.verifier_only.clone();
+    let prover_only = &circuit_data.prover_only;
+
+    // Assume that the witness generators could always be serialized by the
+    // custom generator serializer of recursive-framework.
+    let generator_serializer = CustomGeneratorSerializer::<Cfg, D> {
+        _phantom: PhantomData,
+    };
+    let mut bytes = vec![];
+    let generators = prover_only
+        .generators
+        .iter()
+        .try_for_each(|generator| bytes.write_generator(generator, &generator_serializer, &common))
+        .and_then(|_| {
+            let mut buffer = Buffer::new(&bytes);
+            prover_only
+                .generators
+                .iter()
+                .map(|_| buffer.read_generator(&generator_serializer, &common))
+                .collect::<IoResult<Vec<_>>>()
+        })
+        .map_err(|err| anyhow!("Failed to clone the witness generators: {err:?}"))?;
+
+    let commitment = &prover_only.constants_sigmas_commitment;
+    let prover_only = ProverOnlyCircuitData {
+        generators,
+        generator_indices_by_watches: prover_only.generator_indices_by_watches.clone(),
+        constants_sigmas_commitment: PolynomialBatch {
+            polynomials: commitment.polynomials.clone(),
+            merkle_tree: commitment.merkle_tree.clone(),
+            degree_log: commitment.degree_log,
+            rate_bits: commitment.rate_bits,
+            blinding: commitment.blinding,
+        },
+        sigmas: prover_only.sigmas.clone(),
+        subgroup: prover_only.subgroup.clone(),
+        public_inputs: prover_only.public_inputs.clone(),
+        representative_map: prover_only.representative_map.clone(),
+        fft_root_table: prover_only.fft_root_table.clone(),
+        circuit_digest: prover_only.circuit_digest,
+        lookup_rows: prover_only.lookup_rows.clone(),
+        lut_to_lookups: prover_only.lut_to_lookups.clone(),
+    };
+
+    Ok(CircuitData {
+        prover_only,
+        verifier_only,
+        common,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::C;
+    use plonky2::{
+        field::types::{Field, Sample},
+        iop::witness::{PartialWitness, WitnessWrite},
+        plonk::{circuit_builder::CircuitBuilder, circuit_data::CircuitConfig},
+    };
+    use std::time::Instant;
+
+    #[test]
+    fn test_clone_circuit_data() {
+        // Build a circuit proving the knowledge of `x` such that `x^3 + x = y`.
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let x_cube = builder.exp_u64(x, 3);
+        let y = builder.add(x_cube, x);
+        builder.register_public_input(y);
+        let circuit_data = builder.build::<C>();
+
+        let now = Instant::now();
+        let cloned = clone_circuit_data(&circuit_data).unwrap();
+        let clone_time = now.elapsed();
+        let now = Instant::now();
+        let round_trip =
+            deserialize_circuit_data::<C>(&serialize_circuit_data(&circuit_data).unwrap()).unwrap();
+        let round_trip_time = now.elapsed();
+        log::info!(
+            "Circuit data cloned in {clone_time:?}, serialization round trip in {round_trip_time:?}"
+        );
+
+        // The cloned circuit data must be the same as the one re-serialized.
+        assert_eq!(
+            serialize_circuit_data(&cloned).unwrap(),
+            serialize_circuit_data(&round_trip).unwrap(),
+        );
+
+        // The cloned circuit data must still prove and verify.
+        let x_value = F::rand();
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, x_value);
+        let proof = cloned.prove(pw).unwrap();
+        assert_eq!(proof.public_inputs, [x_value.cube() + x_value]);
+        cloned.verify(proof.clone()).unwrap();
+        circuit_data.verify(proof).unwrap();
+    }
+
+    #[test]
+    fn test_read_nonexistent_file() {
+        let file_path = Path::new("nonexistent_asset_dir").join(CIRCUIT_DATA_FILENAME);
+        let err = read_file(&file_path).unwrap_err();
+
+        let msg = err.to_string();
+        assert!(msg.contains(&file_path.display().to_string()));
+        assert!(msg.contains("compile_and_generate_assets"));
+
+        let err = err.downcast::<FileReadError>().unwrap();
+        assert_eq!(err.path, file_path);
+        assert_eq!(err.source.kind(), io::ErrorKind::NotFound);
+    }
 }
@@ -1,16 +1,109 @@
 //! The verifier used to test the Solidity verification.
 
 use crate::{
-    evm::{executor::deploy_and_call, utils::compile_solidity},
+    evm::{
+        executor::{
+            deploy_and_call, retry_deploy, DEFAULT_DEPLOY_ATTEMPTS, DEFAULT_DEPLOY_BACKOFF,
+        },
+        utils::compile_solidity,
+    },
     utils::read_file,
 };
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
+use ethers::{
+    abi::{Contract, Token},
+    types::U256,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, time::Duration};
+
+/// The opcodes of the first and the last push instructions, whose immediate
+/// values are the constants inlined by solc in the bytecode
+const PUSH1: u8 = 0x60;
+const PUSH32: u8 = 0x7f;
+
+/// The Groth16 verification key as encoded in the constants of the Solidity
+/// verifier contract: the G2 points are negated, and their coordinates are
+/// ordered as in EIP-197, i.e., `[x_1, x_0, y_1, y_0]`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifierKeyConstants {
+    pub alpha: [U256; 2],
+    pub beta_neg: [U256; 4],
+    pub gamma_neg: [U256; 4],
+    pub delta_neg: [U256; 4],
+    /// The constant term followed by the points of the public inputs
+    pub k: Vec<[U256; 2]>,
+}
+
+impl VerifierKeyConstants {
+    /// Load the verification key from the `vk.bin` file generated in the
+    /// asset dir by `compile_and_generate_assets`.
+    pub fn from_asset_dir(asset_dir: &str) -> Result<Self> {
+        let vk = gnark_utils::export_verifier_key(asset_dir)?;
+
+        Ok(serde_json::from_str(&vk)?)
+    }
+
+    /// Return the coordinates of the verification key with the names of the
+    /// corresponding constants of the verifier contract.
+    fn named_constants(&self) -> Vec<(String, U256)> {
+        let g2_coordinates = ["X_0", "X_1", "Y_0", "Y_1"];
+        let mut constants = vec![
+            ("ALPHA_X".to_string(), self.alpha[0]),
+            ("ALPHA_Y".to_string(), self.alpha[1]),
+        ];
+        for (prefix, point) in [
+            ("BETA_NEG", &self.beta_neg),
+            ("GAMMA_NEG", &self.gamma_neg),
+            ("DELTA_NEG", &self.delta_neg),
+        ] {
+            constants.extend(
+                g2_coordinates
+                    .iter()
+                    .zip(point)
+                    .map(|(coordinate, value)| (format!("{prefix}_{coordinate}"), *value)),
+            );
+        }
+        for (i, [x, y]) in self.k.iter().enumerate() {
+            let prefix = match i {
+                0 => "CONSTANT".to_string(),
+                _ => format!("PUB_{}", i - 1),
+            };
+            constants.push((format!("{prefix}_X"), *x));
+            constants.push((format!("{prefix}_Y"), *y));
+        }
+
+        constants
+    }
+}
+
+/// Return the values pushed on the stack by the bytecode, which include the
+/// values of the constants of the contract inlined by solc.
+fn pushed_values(code: &[u8]) -> BTreeSet<U256> {
+    let mut values = BTreeSet::new();
+    let mut pc = 0;
+    while pc < code.len() {
+        let opcode = code[pc];
+        pc += 1;
+        if (PUSH1..=PUSH32).contains(&opcode) {
+            let end = code.len().min(pc + (opcode - PUSH1 + 1) as usize);
+            values.insert(U256::from_big_endian(&code[pc..end]));
+            pc = end;
+        }
+    }
+
+    values
+}
 
 /// EVM verifier
 #[derive(Debug)]
 pub struct EVMVerifier {
     /// The compiled deployment code of Solidity verifier contract
     deployment_code: Vec<u8>,
+    /// The number of attempts to deploy the verifier contract
+    deploy_attempts: usize,
+    /// The delay before retrying a failed deployment, doubled at each retry
+    deploy_backoff: Duration,
 }
 
 impl EVMVerifier {
@@ -21,13 +114,53 @@ impl EVMVerifier {
         // Compile the Solidity code.
         let deployment_code = compile_solidity(&solidity_code);
 
-        Ok(Self { deployment_code })
+        Ok(Self {
+            deployment_code,
+            deploy_attempts: DEFAULT_DEPLOY_ATTEMPTS,
+            deploy_backoff: DEFAULT_DEPLOY_BACKOFF,
+        })
+    }
+
+    /// Set the number of attempts to deploy the verifier contract, and the
+    /// delay before the first retry, which is doubled at each retry. Only the
+    /// deployment failures are retried, not the verification failures.
+    pub fn with_deploy_retries(mut self, attempts: usize, backoff: Duration) -> Self {
+        self.deploy_attempts = attempts.max(1);
+        self.deploy_backoff = backoff;
+
+        self
+    }
+
+    /// Check that the compiled verifier contract embeds the expected
+    /// verification key, e.g., the one generated for the circuit by
+    /// `compile_and_generate_assets`, to catch the deployment of a verifier
+    /// generated from stale asset files. Each coordinate of the key must be
+    /// inlined in the bytecode.
+    pub fn assert_vk_matches(&self, expected_vk: &VerifierKeyConstants) -> Result<()> {
+        let pushed_values = pushed_values(&self.deployment_code);
+        let mismatches = expected_vk
+            .named_constants()
+            .into_iter()
+            .filter_map(|(name, value)| (!pushed_values.contains(&value)).then_some(name))
+            .collect::<Vec<_>>();
+        if !mismatches.is_empty() {
+            bail!(
+                "The verifier contract doesn't match the expected verification key, \
+                constants missing from the bytecode: {mismatches:?} (was the verifier contract \
+                generated from stale asset files?)"
+            );
+        }
+
+        Ok(())
     }
 
     /// Verify the calldata with Solidity verifier contract.
     /// Return the gas_used and the output bytes if success.
     pub fn verify(&self, calldata: Vec<u8>) -> Result<(u64, Vec<u8>)> {
-        match deploy_and_call(self.deployment_code.clone(), calldata) {
+        let result = retry_deploy(self.deploy_attempts, self.deploy_backoff, || {
+            deploy_and_call(self.deployment_code.clone(), calldata.clone())
+        });
+        match result {
             Ok(result) => {
                 log::debug!(
                     "Succeeded to do EVM verification: gas_used = {}, output = {:?}",
@@ -42,4 +175,76 @@ impl EVMVerifier {
             }
         }
     }
+
+    /// Call the function `name` of the verifier contract, whose ABI is
+    /// `abi`, with the arguments `args`. The calldata is ABI encoded, the
+    /// call is run in revm as in `verify`, and the decoded outputs of the
+    /// function are returned. The first function is called if `name` is
+    /// overloaded.
+    pub fn call_function(&self, abi: &Contract, name: &str, args: &[Token]) -> Result<Vec<Token>> {
+        let fun = abi
+            .function(name)
+            .map_err(|err| anyhow!("Function {name} not found in the contract ABI: {err}"))?;
+        let calldata = fun
+            .encode_input(args)
+            .map_err(|err| anyhow!("Failed to encode the inputs of function {name}: {err}"))?;
+
+        let (_, output) = self.verify(calldata)?;
+
+        fun.decode_output(&output)
+            .map_err(|err| anyhow!("Failed to decode the outputs of function {name}: {err}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::write_file;
+    use std::env;
+
+    /// Test that a verifier contract compiled from another circuit fails the
+    /// verification key assertion.
+    #[test]
+    fn test_evm_verifier_assert_vk_matches() {
+        const SOLIDITY_FILE_PATH: &str = "test_data/verifier.sol";
+        // The verification key of the test verifier contract
+        const VK_FILE_PATH: &str = "test_data/verifier_vk.json";
+
+        let expected_vk: VerifierKeyConstants =
+            serde_json::from_slice(&read_file(VK_FILE_PATH).unwrap()).unwrap();
+
+        let verifier = EVMVerifier::new(SOLIDITY_FILE_PATH).unwrap();
+        verifier.assert_vk_matches(&expected_vk).unwrap();
+
+        // Replace the constant of the first public input, as generated for
+        // another circuit.
+        let code = String::from_utf8(read_file(SOLIDITY_FILE_PATH).unwrap()).unwrap();
+        let other_code = code.replace(
+            &expected_vk.k[1][0].to_string(),
+            &expected_vk.k[2][0].to_string(),
+        );
+        let other_file_path = env::temp_dir().join("other_circuit_verifier.sol");
+        write_file(&other_file_path, other_code.as_bytes()).unwrap();
+
+        let other_verifier = EVMVerifier::new(&other_file_path.to_string_lossy()).unwrap();
+        let err = other_verifier
+            .assert_vk_matches(&expected_vk)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("PUB_0_X"), "{err}");
+    }
+
+    /// Test that calling a function missing from the ABI fails before
+    /// running the contract.
+    #[test]
+    fn test_evm_verifier_call_missing_function() {
+        let verifier = EVMVerifier::new("test_data/query2_verifier.sol").unwrap();
+        let abi = Contract::load(read_file("test_data/query2.abi").unwrap().as_slice()).unwrap();
+
+        let err = verifier
+            .call_function(&abi, "missingFunction", &[])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("missingFunction"), "{err}");
+    }
 }
@@ -564,7 +564,12 @@ contract Verifier {
     // The total length of the plonky2 public inputs. Each input value is
     // serialized as an uint64. It's related with both the full proof
     // serialization and the wrapped circuit code.
-    uint32 constant PI_TOTAL_LEN = (L + 24) * 8;
+    uint32 constant PI_TOTAL_LEN = (L + 62) * 8;
+
+    // The maximum number of plonky2 public inputs decoded by this contract,
+    // checked in `parsePlonky2Inputs`. It must match `SOLIDITY_MAX_PI` of the
+    // revelation circuit.
+    uint32 constant MAX_PI_LEN = 128;
 
     // The min block number offset in the plonky2 public inputs.
     uint32 constant PI_MIN_BLOCK_NUM_OFFSET = 2 * 8;
@@ -584,6 +589,23 @@ contract Verifier {
     // The block hash offset in the plonky2 public inputs.
     uint32 constant PI_BLOCK_HASH_OFFSET = PI_NFT_IDS_OFFSET + L * 8;
 
+    // The result commitment offset in the plonky2 public inputs.
+    uint32 constant PI_RESULT_COMMITMENT_OFFSET = PI_BLOCK_HASH_OFFSET + PACKED_HASH_LEN * 8;
+
+    // The offset of the number of keys matching the query in the plonky2 public inputs.
+    uint32 constant PI_TOTAL_MATCHING_OFFSET = PI_RESULT_COMMITMENT_OFFSET + PACKED_HASH_LEN * 8;
+
+    // The offset of the first revealed key in the plonky2 public inputs.
+    uint32 constant PI_PAGE_OFFSET_OFFSET = PI_TOTAL_MATCHING_OFFSET + 8;
+
+    // The offset of the maximum number of revealed keys in the plonky2 public inputs.
+    uint32 constant PI_PAGE_LIMIT_OFFSET = PI_PAGE_OFFSET_OFFSET + 8;
+
+    // The offset of the address of the client allowed to consume the proof in the plonky2
+    // public inputs, after the commitment hash, the salt, the query and range commitments
+    // and the block db root.
+    uint32 constant PI_CLIENT_ADDR_OFFSET = (L + 53) * 8;
+
     // The query struct used to check with the public inputs.
     struct Query {
         address contractAddress;
@@ -602,7 +624,8 @@ contract Verifier {
     //    Then asset this hash value must be equal to the last Groth16 input (groth16_inputs[2]).
     // 4. Parse a Query instance from the plonky2 public inputs, and asset it must be equal to the
     //    expected `query` argument.
-    // 5. Parse and return `L` NFT IDs (uint32) from the plonky2 public inputs.
+    // 5. Parse and return the revealed NFT IDs (uint32) from the plonky2 public inputs, i.e. at
+    //    most `L` IDs, and none if no key matches the query.
     function processQuery(bytes32[] calldata data, Query memory query) public view returns (uint256[] memory) {
         // 1. Do Groth16 verification.
         uint256[3] memory groth16_inputs = verifyGroth16Proof(data);
@@ -643,6 +666,7 @@ contract Verifier {
 
     // Parse the plonky2 public inputs.
     function parsePlonky2Inputs(bytes32[] calldata data) internal pure returns (bytes memory) {
+        require(PI_TOTAL_LEN <= MAX_PI_LEN * 8, "Too many plonky2 public inputs");
         bytes memory pis = new bytes(PI_TOTAL_LEN);
 
         uint32 bytes32_len = PI_TOTAL_LEN / 32;
@@ -706,13 +730,35 @@ contract Verifier {
             block_hash == query.blockHash,
             "The parsed block hash must be equal to the expected one in query."
         );
+
+        // A proof bound to no client, i.e. to the zero address, can be consumed by any client.
+        address client_address = convertToAddress(pis, PI_CLIENT_ADDR_OFFSET);
+        require(
+            client_address == address(0) || client_address == query.clientAddress,
+            "The parsed client address must be zero or equal to the expected one in query."
+        );
     }
 
-    // Parse the `L` NFT IDs from the plonky2 public inputs.
+    // Parse the NFT IDs revealed by the plonky2 public inputs, i.e. the ones in the window
+    // [page_offset, page_offset + page_limit) whose position is smaller than the number of keys
+    // matching the query. An empty array is returned if no key matches the query.
     function parseNftIds(bytes memory pis) internal pure returns (uint256[] memory) {
-        uint256[] memory nft_ids = new uint256[](L);
-        for (uint32 i = 0; i < L; ++i) {
-            nft_ids[i] = uint256(convertToLeftPaddingU32(pis, PI_NFT_IDS_OFFSET + i * 8));
+        uint32 total_matching = convertToU32(pis, PI_TOTAL_MATCHING_OFFSET);
+        uint32 page_offset = convertToU32(pis, PI_PAGE_OFFSET_OFFSET);
+        uint32 page_limit = convertToU32(pis, PI_PAGE_LIMIT_OFFSET);
+
+        uint32 end = page_offset + page_limit;
+        if (end > total_matching) {
+            end = total_matching;
+        }
+        if (end > L) {
+            end = L;
+        }
+        uint32 start = page_offset < end ? page_offset : end;
+
+        uint256[] memory nft_ids = new uint256[](end - start);
+        for (uint32 i = start; i < end; ++i) {
+            nft_ids[i - start] = uint256(convertToLeftPaddingU32(pis, PI_NFT_IDS_OFFSET + i * 8));
         }
 
         return nft_ids;
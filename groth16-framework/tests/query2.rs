@@ -4,14 +4,17 @@ use anyhow::Result;
 use ethers::abi::{Contract, Token};
 use ethers::types::{Address, U256};
 use groth16_framework::{
-    compile_and_generate_assets,
-    test_utils::{save_plonky2_proof_pis, test_groth16_proving_and_verification},
-    utils::{clone_circuit_data, read_file},
-    EVMVerifier, C, D, F,
+    compile_and_generate_assets, debug_print_pis,
+    test_utils::{
+        save_plonky2_proof_pis, test_groth16_proving_and_verification, write_golden_vector,
+        GOLDEN_CALLDATA_FILENAME, GOLDEN_DETERMINISTIC_FILENAMES, GOLDEN_EXPECTED_OUTPUT_FILENAME,
+    },
+    utils::{clone_circuit_data, deserialize_circuit_data, read_file, serialize_circuit_data},
+    EVMVerifier, FullProof, C, D, F,
 };
 use itertools::Itertools;
 use mr_plonky2_circuits::{
-    api::{deserialize_proof, serialize_proof, ProofWithVK},
+    api::{deserialize_proof, revealed_nft_ids, serialize_proof, ProofWithVK},
     block::{
         empty_merkle_root, PublicInputs as BlockDbPublicInputs,
         NUM_IVC_PUBLIC_INPUTS as BLOCK_DB_NUM_IO,
@@ -19,10 +22,14 @@ use mr_plonky2_circuits::{
     query2::{
         block::BlockPublicInputs,
         block::NUM_IO as QUERY2_BLOCK_NUM_IO,
-        revelation::{Parameters, RevelationRecursiveInput},
+        revelation::{
+            estimate_verification_gas, Parameters, RevelationPublicInputs,
+            RevelationRecursiveInput, GAS_ESTIMATE_TOLERANCE, SOLIDITY_MAX_PI,
+        },
         CircuitInput, PublicParameters,
     },
 };
+use mrp2_public_inputs::revelation;
 use mrp2_utils::{
     eth::{left_pad, left_pad32},
     group_hashing,
@@ -38,17 +45,24 @@ use plonky2::{
     hash::hash_types::{HashOut, NUM_HASH_OUT_ELTS},
     plonk::{circuit_builder::CircuitBuilder, circuit_data::CircuitData},
 };
-use rand::{thread_rng, Rng};
+use rand::{rngs::StdRng, SeedableRng};
 use recursion_framework::framework_testing::TestingRecursiveCircuits;
 use serial_test::serial;
-use std::{iter::once, path::Path, str::FromStr};
+use std::{iter::once, path::Path, str::FromStr, time::Instant};
 
 /// Set the number of NFT IDs and block DB depth.
 const L: usize = 5;
 const BLOCK_DB_DEPTH: usize = 2;
 
-/// Test NFT IDs
-const TEST_NFT_IDS: [u32; L] = [0, 0, 0, 0, 0];
+/// Test NFT IDs, distinct and sorted as the revealed ones.
+const TEST_NFT_IDS: [u32; L] = [1, 2, 3, 4, 5];
+
+/// The seed used to generate the plonky2 proof deterministically.
+const GOLDEN_SEED: u64 = 0x9017de4;
+
+/// The gas budget to verify the query2 proof in Solidity, well above the
+/// Groth16 pairing check and the hashing of the plonky2 public inputs.
+const VERIFICATION_GAS_CEILING: u64 = 1_000_000;
 
 /// The query struct used to check with the plonky2 public inputs in Solidity.
 struct Query {
@@ -92,7 +106,9 @@ fn test_groth16_proving_for_query2() {
     let query = Query::new_test();
 
     // Build for the query2 circuit and generate the plonky2 proof.
-    let (circuit_data, proof) = plonky2_build_and_prove(ASSET_DIR, &query);
+    let (circuit_data, proof) = plonky2_build_and_prove(ASSET_DIR, &query, &test_mapping_keys());
+    debug_print_pis(&deserialize_proof::<F, C, D>(&proof).unwrap().public_inputs);
+    assert_eq!(revealed_nft_ids::<L>(&proof).unwrap(), TEST_NFT_IDS);
 
     // Generate the Groth16 asset files.
     compile_and_generate_assets(circuit_data, ASSET_DIR)
@@ -106,11 +122,136 @@ fn test_groth16_proving_for_query2() {
     // TODO: In practice, the separate `query2.sol` and `verifier.sol` should be
     // used, but the `revm` (Rust EVM) cannot support compilated contract
     // deployment (as inheritance) for now.
-    verify_query2_solidity_fun(ASSET_DIR, &query);
+    verify_query2_solidity_fun(ASSET_DIR, &query, &TEST_NFT_IDS);
+
+    // The proof is bound to the client of the query, so another client can't consume it.
+    verify_query2_rejects_other_client(ASSET_DIR, &query);
+
+    // Write the golden test vector and check it verifies in Solidity.
+    verify_golden_vector(ASSET_DIR, &proof, &query);
+}
+
+/// Test proving for the query2 circuit when no key matches the query, which
+/// must reveal no NFT ID rather than zero-valued ones.
+#[ignore] // Ignore for long running time in CI.
+#[serial]
+#[test]
+fn test_groth16_proving_for_empty_query2() {
+    const ASSET_DIR: &str = "groth16_query2_empty";
+
+    let query = Query::new_test();
+    let (circuit_data, proof) = plonky2_build_and_prove(ASSET_DIR, &query, &[]);
+
+    // The plonky2 public inputs report no matching key.
+    let pis = deserialize_proof::<F, C, D>(&proof).unwrap().public_inputs;
+    let pis = RevelationPublicInputs::<F, L>::from(pis.as_slice());
+    assert!(pis.revealed_nft_ids().is_empty());
+
+    compile_and_generate_assets(circuit_data, ASSET_DIR)
+        .expect("Failed to generate the Groth16 asset files");
+    test_groth16_proving_and_verification(ASSET_DIR, &proof);
+
+    // The Query2 Solidity function returns an empty array.
+    verify_query2_solidity_fun(ASSET_DIR, &query, &[]);
+}
+
+/// Test the estimate of the gas spent to verify a revelation proof on-chain
+/// against the gas used by the Query2 Solidity function in revm.
+#[ignore] // Ignore for long running time in CI.
+#[serial]
+#[test]
+fn test_query2_verification_gas_estimate() {
+    const ASSET_DIR: &str = "groth16_query2_gas";
+
+    let query = Query::new_test();
+    let (circuit_data, proof) = plonky2_build_and_prove(ASSET_DIR, &query, &test_mapping_keys());
+    compile_and_generate_assets(circuit_data, ASSET_DIR)
+        .expect("Failed to generate the Groth16 asset files");
+    test_groth16_proving_and_verification(ASSET_DIR, &proof);
+
+    let solidity_file_path = Path::new("test_data")
+        .join("query2_verifier.sol")
+        .to_string_lossy()
+        .to_string();
+    let verifier =
+        EVMVerifier::new(&solidity_file_path).expect("Failed to initialize the EVM verifier");
+    let (gas_used, _) = verifier
+        .verify(query2_calldata(ASSET_DIR, &query))
+        .expect("Failed to verify in Solidity");
+
+    let num_public_inputs = deserialize_proof::<F, C, D>(&proof)
+        .unwrap()
+        .public_inputs
+        .len();
+    let estimate = estimate_verification_gas(num_public_inputs).total();
+    let error = (estimate as f64 - gas_used as f64).abs() / gas_used as f64;
+    assert!(
+        error <= GAS_ESTIMATE_TOLERANCE,
+        "Estimated {estimate} gas for the verification, while {gas_used} gas were used"
+    );
+}
+
+/// Test that the Solidity verifier accepts at most `SOLIDITY_MAX_PI` plonky2
+/// public inputs, the limit checked when building the revelation parameters.
+#[test]
+fn test_solidity_max_public_inputs() {
+    for file in ["query2.sol", "query2_verifier.sol"] {
+        let code =
+            String::from_utf8(read_file(Path::new("test_data").join(file)).unwrap()).unwrap();
+        let declaration = format!("uint32 constant MAX_PI_LEN = {SOLIDITY_MAX_PI};");
+        assert!(
+            code.contains(&declaration),
+            "{file} must declare `{declaration}`"
+        );
+    }
+}
+
+/// Test that the length of the plonky2 public inputs decoded by the Solidity
+/// verifier is the one of the revelation public inputs layout.
+#[test]
+fn test_solidity_public_inputs_len() {
+    for file in ["query2.sol", "query2_verifier.sol"] {
+        let code =
+            String::from_utf8(read_file(Path::new("test_data").join(file)).unwrap()).unwrap();
+        let declaration = format!(
+            "uint32 constant PI_TOTAL_LEN = (L + {}) * 8;",
+            revelation::total_len(0)
+        );
+        assert!(
+            code.contains(&declaration),
+            "{file} must declare `{declaration}`"
+        );
+    }
+}
+
+/// Test that the offset of the client address decoded by the Solidity verifier
+/// is the one of the revelation public inputs layout.
+#[test]
+fn test_solidity_client_address_offset() {
+    let (_, client_address) = revelation::layout(0)
+        .into_iter()
+        .find(|(name, _)| *name == "client_address")
+        .unwrap();
+    for file in ["query2.sol", "query2_verifier.sol"] {
+        let code =
+            String::from_utf8(read_file(Path::new("test_data").join(file)).unwrap()).unwrap();
+        let declaration = format!(
+            "uint32 constant PI_CLIENT_ADDR_OFFSET = (L + {}) * 8;",
+            client_address.start
+        );
+        assert!(
+            code.contains(&declaration),
+            "{file} must declare `{declaration}`"
+        );
+    }
 }
 
 /// Build for the plonky2 circuit and generate the proof.
-fn plonky2_build_and_prove(asset_dir: &str, query: &Query) -> (CircuitData<F, C, D>, Vec<u8>) {
+fn plonky2_build_and_prove(
+    asset_dir: &str,
+    query: &Query,
+    mapping_keys: &[[u8; MAPPING_KEY_LEN]],
+) -> (CircuitData<F, C, D>, Vec<u8>) {
     // Generate a fake query2/block circuit set.
     let query2_testing_framework =
         TestingRecursiveCircuits::<F, C, D, QUERY2_BLOCK_NUM_IO>::default();
@@ -127,23 +268,22 @@ fn plonky2_build_and_prove(asset_dir: &str, query: &Query) -> (CircuitData<F, C,
         query2_block_circuit_set,
         block_db_circuit_set,
         block_db_vk,
-    );
+    )
+    .unwrap();
+
+    // Use a fixed seed to generate the same proof at each run.
+    let mut rng = StdRng::seed_from_u64(GOLDEN_SEED);
 
     // Generate a fake block db proof.
     let init_root = empty_merkle_root::<GoldilocksField, 2, BLOCK_DB_DEPTH>();
     let last_root = HashOut {
-        elements: F::rand_vec(NUM_HASH_OUT_ELTS).try_into().unwrap(),
+        elements: [0; NUM_HASH_OUT_ELTS].map(|_| F::sample(&mut rng)),
     };
     let init_block_number = F::ONE;
     let last_block_number = F::from_canonical_u32(query.max_block_number + 1);
-    let last_block_hash = query
-        .block_hash
-        .0
-        .iter()
-        .flat_map(|u| [*u as u32, (u >> 32) as u32].map(F::from_canonical_u32))
-        .collect::<Vec<_>>()
-        .try_into()
-        .unwrap();
+    let mut last_block_hash = [0; 32];
+    query.block_hash.to_little_endian(&mut last_block_hash);
+    let last_block_hash = BlockDbPublicInputs::from_block_hash_bytes(&last_block_hash);
 
     let block_db_inputs: [F; BLOCK_DB_NUM_IO] = BlockDbPublicInputs::from_parts(
         &init_root.elements,
@@ -173,9 +313,8 @@ fn plonky2_build_and_prove(asset_dir: &str, query: &Query) -> (CircuitData<F, C,
     };
     let smc_address = query.contract_address;
     let user_address = query.user_address;
-    let mapping_slot = F::rand();
-    let length_slot = F::rand();
-    let mapping_keys = test_mapping_keys();
+    let mapping_slot = F::sample(&mut rng);
+    let length_slot = F::sample(&mut rng);
     let packed_field_mks = mapping_keys
         .iter()
         .map(|x| x.pack().to_fields())
@@ -183,11 +322,7 @@ fn plonky2_build_and_prove(asset_dir: &str, query: &Query) -> (CircuitData<F, C,
 
     log::info!("NFT IDs to set before proving: {packed_field_mks:?}");
 
-    let digests = packed_field_mks
-        .iter()
-        .map(|i| group_hashing::map_to_curve_point(i))
-        .collect::<Vec<_>>();
-    let single_digest = group_hashing::add_curve_point(&digests);
+    let single_digest = group_hashing::map_and_add(&packed_field_mks);
     let pis = BlockPublicInputs::from_parts(
         query_max_number,
         query_range,
@@ -219,13 +354,14 @@ fn plonky2_build_and_prove(asset_dir: &str, query: &Query) -> (CircuitData<F, C,
 
     // Create the revelation input.
     let revelation_inputs = RevelationRecursiveInput::<L>::new(
-        mapping_keys.into_iter().map(|x| x.to_vec()).collect(),
+        mapping_keys.iter().map(|x| x.to_vec()).collect(),
         query_min_number.to_canonical_u64() as usize,
         query_max_number.to_canonical_u64() as usize,
         q2_proof_buff,
         block_db_buff,
     )
-    .unwrap();
+    .unwrap()
+    .with_client_address(query.client_address);
 
     // Generate the proof.
     let proof = params.generate_proof(revelation_inputs).unwrap();
@@ -233,21 +369,78 @@ fn plonky2_build_and_prove(asset_dir: &str, query: &Query) -> (CircuitData<F, C,
     // Save the public inputs to a file for debugging.
     save_plonky2_proof_pis(asset_dir, &deserialize_proof(&proof).unwrap());
 
-    // Get the circuit data.
+    // Get the circuit data, comparing the cost of cloning the revelation
+    // circuit with the one of the full serialization round trip.
+    let now = Instant::now();
     let circuit_data = clone_circuit_data(params.circuit_data()).unwrap();
+    let clone_time = now.elapsed();
+    let now = Instant::now();
+    let round_trip =
+        deserialize_circuit_data::<C>(&serialize_circuit_data(params.circuit_data()).unwrap())
+            .unwrap();
+    let round_trip_time = now.elapsed();
+    log::info!(
+        "Revelation circuit data cloned in {clone_time:?}, serialization round trip in {round_trip_time:?}"
+    );
+    assert_eq!(
+        circuit_data.verifier_only, round_trip.verifier_only,
+        "The cloned revelation circuit differs from the deserialized one"
+    );
 
     (circuit_data, proof)
 }
 
-/// Generate the test mapping keys.
+/// Convert the query to the `query` argument of the Query2 Solidity function.
+fn query_token(query: &Query) -> Token {
+    let mut block_hash_bytes = vec![0; 32];
+    query.block_hash.to_little_endian(&mut block_hash_bytes);
+
+    Token::Tuple(vec![
+        Token::Address(query.contract_address),
+        Token::Address(query.user_address),
+        Token::Address(query.client_address),
+        Token::Uint(query.min_block_number.into()),
+        Token::Uint(query.max_block_number.into()),
+        Token::FixedBytes(block_hash_bytes),
+    ])
+}
+
+/// Generate the test mapping keys. The Solidity function decodes each NFT ID
+/// from the last 4 bytes of the key as a big-endian u32.
 fn test_mapping_keys() -> Vec<[u8; MAPPING_KEY_LEN]> {
     (0..L)
-        .map(|i| left_pad::<MAPPING_KEY_LEN>(&TEST_NFT_IDS[i].to_le_bytes()))
+        .map(|i| left_pad::<MAPPING_KEY_LEN>(&TEST_NFT_IDS[i].to_be_bytes()))
         .collect()
 }
 
-/// Verify the Query2 Solidity function.
-fn verify_query2_solidity_fun(asset_dir: &str, query: &Query) {
+/// Encode the calldata of the Query2 Solidity function for the full proof
+/// generated in the asset dir.
+fn query2_calldata(asset_dir: &str, query: &Query) -> Vec<u8> {
+    let contract = Contract::load(
+        read_file(Path::new("test_data").join("query2.abi"))
+            .unwrap()
+            .as_slice(),
+    )
+    .expect("Failed to load the Solidity verifier contract from ABI");
+
+    // The full proof is passed as a bytes32 array.
+    let proof_bytes = read_file(Path::new(asset_dir).join("full_proof.bin")).unwrap();
+    let data = Token::Array(
+        proof_bytes
+            .chunks(32)
+            .map(|b| Token::FixedBytes(b.to_vec()))
+            .collect(),
+    );
+
+    contract
+        .function("processQuery")
+        .unwrap()
+        .encode_input(&[data, query_token(query)])
+        .expect("Failed to encode the inputs of Solidity contract function processQuery")
+}
+
+/// Verify the Query2 Solidity function, which must return the expected NFT IDs.
+fn verify_query2_solidity_fun(asset_dir: &str, query: &Query, expected_nft_ids: &[u32]) {
     let solidity_file_path = Path::new("test_data")
         .join("query2_verifier.sol")
         .to_string_lossy()
@@ -267,49 +460,147 @@ fn verify_query2_solidity_fun(asset_dir: &str, query: &Query) {
     let data = Token::Array(
         proof_bytes
             .chunks(32)
-            .map(|b| {
-                let u = U256::from_little_endian(b);
-                println!("0x{:x}", u);
-                Token::FixedBytes(b.to_vec())
-            })
+            .map(|b| Token::FixedBytes(b.to_vec()))
             .collect(),
     );
 
-    let mut block_hash_bytes = vec![0; 32];
-    query.block_hash.to_little_endian(&mut block_hash_bytes);
+    let verifier =
+        EVMVerifier::new(&solidity_file_path).expect("Failed to initialize the EVM verifier");
 
-    let query = Token::Tuple(vec![
-        Token::Address(query.contract_address),
-        Token::Address(query.user_address),
-        Token::Address(query.client_address),
-        Token::Uint(query.min_block_number.into()),
-        Token::Uint(query.max_block_number.into()),
-        Token::FixedBytes(block_hash_bytes),
-    ]);
+    // Encode the arguments, verify in Solidity and decode the output.
+    let output = verifier
+        .call_function(&contract, "processQuery", &[data, query_token(query)])
+        .expect("Failed to verify in Solidity");
+    let nft_ids = match output.as_slice() {
+        [Token::Array(arr)] => arr
+            .iter()
+            .map(|token| match token {
+                Token::Uint(u) => u.as_u32(),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>(),
+        _ => unreachable!(),
+    };
 
-    // Build the ABI encoded data.
-    let args = vec![data, query];
-    let fun = &contract.functions["processQuery"][0];
-    let calldata = fun
-        .encode_input(&args)
-        .expect("Failed to encode the inputs of Solidity respond function");
+    // Check the returned NFT IDs.
+    assert_eq!(nft_ids, expected_nft_ids);
 
+    // Check the NFT IDs decoded in Rust from the plonky2 public inputs are the same as the
+    // ones returned by Solidity.
+    let full_proof = FullProof::from_bytes(&proof_bytes).unwrap();
+    let pis = full_proof
+        .plonky2_public_inputs
+        .iter()
+        .map(|pi| F::from_canonical_u64(*pi))
+        .collect_vec();
+    let pis = RevelationPublicInputs::<F, L>::from(pis.as_slice());
+    let expected_ids = match output.as_slice() {
+        [Token::Array(arr)] => arr
+            .iter()
+            .map(|token| token.clone().into_uint().unwrap())
+            .collect_vec(),
+        _ => unreachable!(),
+    };
+    assert_eq!(pis.revealed_nft_ids(), expected_ids);
+}
+
+/// Verify the Query2 Solidity function reverts when the proof, bound to the
+/// client of `query`, is consumed by another client.
+fn verify_query2_rejects_other_client(asset_dir: &str, query: &Query) {
+    let solidity_file_path = Path::new("test_data")
+        .join("query2_verifier.sol")
+        .to_string_lossy()
+        .to_string();
     let verifier =
         EVMVerifier::new(&solidity_file_path).expect("Failed to initialize the EVM verifier");
 
-    // Verify in Solidity.
-    let output = verifier
+    let other_query = Query {
+        client_address: Address::from_low_u64_be(0xc1),
+        ..*query
+    };
+    assert_ne!(other_query.client_address, query.client_address);
+    assert!(verifier
+        .verify(query2_calldata(asset_dir, &other_query))
+        .is_err());
+    // The same calldata with the expected client is accepted.
+    verifier
+        .verify(query2_calldata(asset_dir, query))
+        .expect("Failed to verify in Solidity");
+}
+
+/// Write the golden test vector, and check it verifies with the Query2
+/// Solidity function returning the expected NFT IDs.
+fn verify_golden_vector(asset_dir: &str, plonky2_proof: &[u8], query: &Query) {
+    let golden_dir = Path::new(asset_dir).join("golden");
+    let golden_dir = golden_dir.to_str().unwrap();
+
+    let calldata = write_golden_vector(
+        golden_dir,
+        asset_dir,
+        plonky2_proof,
+        query_token(query),
+        &TEST_NFT_IDS,
+    )
+    .expect("Failed to write the golden test vector");
+    assert_eq!(
+        read_file(Path::new(golden_dir).join(GOLDEN_CALLDATA_FILENAME)).unwrap(),
+        calldata
+    );
+
+    // Check the deterministic files are the same when the vector is written
+    // again, while the Groth16 proof is randomized by gnark.
+    let other_golden_dir = Path::new(asset_dir).join("golden_other");
+    let other_golden_dir = other_golden_dir.to_str().unwrap();
+    write_golden_vector(
+        other_golden_dir,
+        asset_dir,
+        plonky2_proof,
+        query_token(query),
+        &TEST_NFT_IDS,
+    )
+    .expect("Failed to write the golden test vector again");
+    for filename in GOLDEN_DETERMINISTIC_FILENAMES {
+        assert_eq!(
+            read_file(Path::new(golden_dir).join(filename)).unwrap(),
+            read_file(Path::new(other_golden_dir).join(filename)).unwrap(),
+            "The golden file {filename} is not deterministic"
+        );
+    }
+
+    // Verify the golden calldata in Solidity.
+    let solidity_file_path = Path::new("test_data")
+        .join("query2_verifier.sol")
+        .to_string_lossy()
+        .to_string();
+    let verifier =
+        EVMVerifier::new(&solidity_file_path).expect("Failed to initialize the EVM verifier");
+    let (gas_used, output) = verifier
         .verify(calldata)
-        .expect("Failed to verify in Solidity")
-        .1;
+        .expect("Failed to verify the golden vector in Solidity");
+
+    // Check the verification stays within the gas budget.
+    assert!(gas_used > 0);
+    assert!(
+        gas_used < VERIFICATION_GAS_CEILING,
+        "Verification used {gas_used} gas, above the budget of {VERIFICATION_GAS_CEILING}"
+    );
 
-    // Parse the Solidity output.
-    let output = fun
+    // Check the output is the expected one, and the decoded IDs match the query.
+    let expected_output =
+        read_file(Path::new(golden_dir).join(GOLDEN_EXPECTED_OUTPUT_FILENAME)).unwrap();
+    assert_eq!(output, expected_output);
+    let contract = Contract::load(
+        read_file(Path::new("test_data").join("query2.abi"))
+            .unwrap()
+            .as_slice(),
+    )
+    .unwrap();
+    let output = contract.functions["processQuery"][0]
         .decode_output(&output)
-        .expect("Failed to decode the Solidity output");
+        .unwrap();
     let nft_ids = match output.as_slice() {
         [Token::Array(arr)] => arr
-            .into_iter()
+            .iter()
             .map(|token| match token {
                 Token::Uint(u) => u.as_u32(),
                 _ => unreachable!(),
@@ -317,7 +608,5 @@ fn verify_query2_solidity_fun(asset_dir: &str, query: &Query) {
             .collect::<Vec<_>>(),
         _ => unreachable!(),
     };
-
-    // Check the returned NFT IDs.
     assert_eq!(nft_ids, TEST_NFT_IDS);
 }
@@ -1,15 +1,29 @@
 use anyhow::Result;
-use plonky2::plonk::{
-    circuit_builder::CircuitBuilder,
-    circuit_data::{CircuitConfig, VerifierCircuitData, VerifierOnlyCircuitData},
-    config::{AlgebraicHasher, GenericConfig, PoseidonGoldilocksConfig},
-    proof::{ProofWithPublicInputs, ProofWithPublicInputsTarget},
+use plonky2::{
+    field::types::PrimeField64,
+    hash::{
+        hash_types::{HashOut, RichField},
+        poseidon::PoseidonHash,
+    },
+    plonk::{
+        circuit_builder::CircuitBuilder,
+        circuit_data::{CircuitConfig, VerifierCircuitData, VerifierOnlyCircuitData},
+        config::{
+            AlgebraicHasher, GenericConfig, GenericHashOut, Hasher, PoseidonGoldilocksConfig,
+        },
+        proof::{ProofWithPublicInputs, ProofWithPublicInputsTarget},
+    },
 };
 use recursion_framework::{
     framework::RecursiveCircuits,
     serialization::{circuit_data_serialization::SerializableRichField, deserialize, serialize},
 };
 use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    io::{self, Read},
+    iter::once,
+};
 
 pub use crate::storage::{
     self,
@@ -24,7 +38,9 @@ pub use crate::state::{
 
 use crate::{
     block::Inputs,
+    query2::revelation::RevelationPublicInputs,
     state::{block_linking, lpn::api::ProofInputs},
+    utils::keccak256,
 };
 
 use self::storage::{digest_equal, length_match};
@@ -35,6 +51,96 @@ pub(crate) const D: usize = 2;
 pub(crate) type C = PoseidonGoldilocksConfig;
 pub(crate) type F = <C as GenericConfig<D>>::F;
 
+/// Errors raised when handling proofs and parameters through this module. The underlying error,
+/// if any, is kept as the source of the `ApiError`; as `ApiError` implements
+/// `std::error::Error`, it converts into `anyhow::Error` with `?`
+#[derive(Debug)]
+pub enum ApiError {
+    /// Encoding a proof or some parameters to bytes failed
+    Serialization(bincode::Error),
+    /// The bytes provided are not a valid encoding of a proof or of some parameters
+    Deserialization(bincode::Error),
+    /// Reading the encoded proof or parameters failed
+    Io(io::Error),
+    /// The proof exposes a number of public inputs different from the expected one, i.e., it
+    /// was most likely generated by another circuit
+    PublicInputMismatch {
+        /// Number of public inputs exposed by the proof
+        got: usize,
+        /// Number of public inputs exposed by the proofs of the expected circuit
+        expected: usize,
+    },
+    /// The proof was rejected by the verifier of the circuit
+    VerificationFailed(anyhow::Error),
+    /// The checksum found in the header of the serialized bytes doesn't match their content,
+    /// i.e., the bytes were truncated or altered
+    Corrupted {
+        /// Checksum found in the header
+        expected: u32,
+        /// Checksum computed over the bytes following the header
+        computed: u32,
+    },
+    /// The public inputs bundled in a `ProofEnvelope` differ from the ones exposed by the
+    /// proof, i.e., the envelope was tampered with
+    EnvelopeMismatch,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Serialization(err) => write!(f, "serialization failed: {}", err),
+            Self::Deserialization(err) => write!(f, "deserialization failed: {}", err),
+            Self::Io(err) => write!(f, "I/O error: {}", err),
+            Self::PublicInputMismatch { got, expected } => write!(
+                f,
+                "proof exposes {} public inputs, while {} are expected",
+                got, expected
+            ),
+            Self::VerificationFailed(err) => write!(f, "proof verification failed: {}", err),
+            Self::Corrupted { expected, computed } => write!(
+                f,
+                "corrupted bytes: checksum is {:#010x}, while {:#010x} is expected",
+                computed, expected
+            ),
+            Self::EnvelopeMismatch => write!(
+                f,
+                "public inputs in the envelope don't match the ones exposed by the proof"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Serialization(err) | Self::Deserialization(err) => Some(err),
+            Self::Io(err) => Some(err),
+            Self::VerificationFailed(err) => Some(&**err),
+            Self::PublicInputMismatch { .. } | Self::Corrupted { .. } | Self::EnvelopeMismatch => {
+                None
+            }
+        }
+    }
+}
+
+impl ApiError {
+    /// I/O errors are reported apart, as they are not due to the bytes being decoded, except
+    /// for unexpected EOFs, which are raised by `bincode` for truncated bytes
+    pub(crate) fn deserialization(err: bincode::Error) -> Self {
+        match *err {
+            bincode::ErrorKind::Io(err) if err.kind() != io::ErrorKind::UnexpectedEof => {
+                Self::Io(err)
+            }
+            err => Self::Deserialization(Box::new(err)),
+        }
+    }
+
+    /// Deserialization error for bytes rejected before being decoded, e.g. due to their header
+    fn malformed(msg: String) -> Self {
+        Self::Deserialization(Box::new(bincode::ErrorKind::Custom(msg)))
+    }
+}
+
 /// Set of inputs necessary to generate proofs for each circuit employed in the pre-processing
 /// stage of LPN
 pub enum CircuitInput<const MAX_DEPTH: usize> {
@@ -77,15 +183,17 @@ pub struct BlockDBCircuitInfo<const MAX_DEPTH: usize> {
     circuit_set: RecursiveCircuits<F, C, D>,
     #[serde(serialize_with = "serialize", deserialize_with = "deserialize")]
     verifier_data: VerifierOnlyCircuitData<C, D>,
+    #[serde(serialize_with = "serialize", deserialize_with = "deserialize")]
+    merge_verifier_data: VerifierOnlyCircuitData<C, D>,
 }
 
 impl<const MAX_DEPTH: usize> BlockDBCircuitInfo<MAX_DEPTH> {
-    pub(crate) fn serialize(&self) -> Result<Vec<u8>> {
-        Ok(bincode::serialize(self)?)
+    pub(crate) fn serialize(&self) -> Result<Vec<u8>, ApiError> {
+        bincode::serialize(self).map_err(ApiError::Serialization)
     }
 
-    pub(crate) fn deserialize(bytes: &[u8]) -> Result<Self> {
-        Ok(bincode::deserialize(bytes)?)
+    pub(crate) fn deserialize(bytes: &[u8]) -> Result<Self, ApiError> {
+        bincode::deserialize(bytes).map_err(ApiError::deserialization)
     }
 
     pub(crate) fn get_block_db_circuit_set(&self) -> &RecursiveCircuits<F, C, D> {
@@ -95,6 +203,21 @@ impl<const MAX_DEPTH: usize> BlockDBCircuitInfo<MAX_DEPTH> {
     pub(crate) fn get_block_db_vk(&self) -> &VerifierOnlyCircuitData<C, D> {
         &self.verifier_data
     }
+
+    /// Verifier data of the circuits generating the block DB proofs accepted by query circuits,
+    /// i.e., the IVC circuit and the circuit merging proofs over consecutive ranges of blocks
+    pub(crate) fn get_block_db_vks(&self) -> [VerifierOnlyCircuitData<C, D>; 2] {
+        [self.verifier_data.clone(), self.merge_verifier_data.clone()]
+    }
+}
+
+impl<const MAX_DEPTH: usize> PublicParameters<MAX_DEPTH> {
+    /// Insert the blocks with the LPN state proofs `state_proofs` in an empty block DB, refer
+    /// to `block::build_block_db_proof` for further details
+    pub(crate) fn build_block_db_proof(&self, state_proofs: &[Vec<u8>]) -> Result<Vec<u8>> {
+        self.block_db
+            .build_block_db_proof(self.lpn_state.get_lpn_state_circuit_set(), state_proofs)
+    }
 }
 
 /// Retrieve a common `CircuitConfig` to be employed to generate the parameters for the circuits
@@ -102,6 +225,29 @@ impl<const MAX_DEPTH: usize> BlockDBCircuitInfo<MAX_DEPTH> {
 pub(crate) fn default_config() -> CircuitConfig {
     CircuitConfig::standard_recursion_config()
 }
+
+/// Minimum conjectured security, in bits, of the configurations suitable for production, i.e.
+/// the one of `default_config`; Groth16 assets are generated only for circuits reaching it
+pub const MIN_SECURITY_BITS: usize = 100;
+
+/// Number of FRI query rounds of `test_config`
+#[cfg(any(test, feature = "test_config"))]
+const TEST_NUM_QUERY_ROUNDS: usize = 4;
+
+/// Same as `default_config`, but with fewer FRI query rounds and without the proof of work,
+/// which makes proving faster and proofs smaller. The conjectured security drops accordingly,
+/// from 100 bits to `rate_bits * num_query_rounds` = 12 bits, so proofs generated with this
+/// configuration can be forged with little effort: it is available only in tests or with the
+/// `test_config` feature, and its conjectured security is below `MIN_SECURITY_BITS`, so that
+/// the Groth16 asset generation rejects circuits built with it.
+#[cfg(any(test, feature = "test_config"))]
+pub fn test_config() -> CircuitConfig {
+    let mut config = default_config();
+    config.fri_config.num_query_rounds = TEST_NUM_QUERY_ROUNDS;
+    config.fri_config.proof_of_work_bits = 0;
+    config.security_bits = config.fri_config.rate_bits * TEST_NUM_QUERY_ROUNDS;
+    config
+}
 /// Instantiate the circuits employed for the pre-processing stage of LPN, returning their
 /// corresponding parameters
 pub fn build_circuits_params<const MAX_DEPTH: usize>() -> PublicParameters<MAX_DEPTH> {
@@ -190,14 +336,25 @@ pub fn generate_proof<const MAX_DEPTH: usize>(
         }
     }
 }
+/// Merge two block DB proofs covering consecutive ranges of blocks, i.e., such that the last
+/// root and block number of `earlier` are the ones the `later` proof starts from, into a single
+/// block DB proof spanning both ranges, which can be employed in place of `later` by query circuits
+pub fn merge_block_db_proofs<const MAX_DEPTH: usize>(
+    params: &PublicParameters<MAX_DEPTH>,
+    earlier: &[u8],
+    later: &[u8],
+) -> Result<Vec<u8>> {
+    params.block_db.merge_proofs(earlier, later)
+}
 /// Get the information about the block DB circuit that needs to be exchanged with
 /// set of parameters for query circuits
 pub fn block_db_circuit_info<const MAX_DEPTH: usize>(
     params: &PublicParameters<MAX_DEPTH>,
-) -> Result<Vec<u8>> {
+) -> Result<Vec<u8>, ApiError> {
     let block_db_info = BlockDBCircuitInfo::<MAX_DEPTH> {
         circuit_set: params.block_db.get_block_db_circuit_set().clone(),
         verifier_data: params.block_db.get_block_db_vk().clone(),
+        merge_verifier_data: params.block_db.get_block_db_merge_vk().clone(),
     };
     block_db_info.serialize()
 }
@@ -211,14 +368,84 @@ pub struct ProofWithVK {
 }
 
 impl ProofWithVK {
-    pub fn serialize(&self) -> Result<Vec<u8>> {
-        let buff = bincode::serialize(&self)?;
-        Ok(buff)
+    /// Build a `ProofWithVK` from a proof and the verifier data of the circuit which generated
+    /// it; this is the inverse of `into_parts`
+    pub fn from_parts(
+        proof: ProofWithPublicInputs<F, C, D>,
+        vk: VerifierOnlyCircuitData<C, D>,
+    ) -> Self {
+        Self { proof, vk }
+    }
+
+    /// Split `self` into the proof and the verifier data of the circuit which generated it;
+    /// this is the inverse of `from_parts`
+    pub fn into_parts(
+        self,
+    ) -> (
+        ProofWithPublicInputs<F, C, D>,
+        VerifierOnlyCircuitData<C, D>,
+    ) {
+        (self.proof, self.vk)
+    }
+
+    /// Serialize `self`, prepending to the encoded proof and verifier data a header made of
+    /// magic bytes, the version of the format and the CRC32 checksum of the encoded data
+    pub fn serialize(&self) -> Result<Vec<u8>, ApiError> {
+        let payload = bincode::serialize(&self).map_err(ApiError::Serialization)?;
+        Ok(PROOF_WITH_VK_MAGIC
+            .into_iter()
+            .chain(once(PROOF_WITH_VK_VERSION))
+            .chain(crc32(&payload).to_le_bytes())
+            .chain(payload)
+            .collect())
+    }
+
+    /// Deserialize bytes produced by `serialize`; `ApiError::Corrupted` is returned if their
+    /// checksum doesn't match, while bytes without the header must be read with
+    /// `deserialize_legacy`
+    pub fn deserialize(buff: &[u8]) -> Result<Self, ApiError> {
+        let header = buff.get(..PROOF_WITH_VK_HEADER_LEN).ok_or_else(|| {
+            ApiError::malformed("bytes are too short for a ProofWithVK header".to_string())
+        })?;
+        let (magic, header) = header.split_at(PROOF_WITH_VK_MAGIC.len());
+        if magic != PROOF_WITH_VK_MAGIC {
+            return Err(ApiError::malformed(
+                "missing ProofWithVK header: bytes serialized without it must be read with \
+                `deserialize_legacy`"
+                    .to_string(),
+            ));
+        }
+        if header[0] != PROOF_WITH_VK_VERSION {
+            return Err(ApiError::malformed(format!(
+                "unsupported ProofWithVK format version {}",
+                header[0]
+            )));
+        }
+        let expected = u32::from_le_bytes(header[1..].try_into().unwrap());
+        let payload = &buff[PROOF_WITH_VK_HEADER_LEN..];
+        let computed = crc32(payload);
+        if computed != expected {
+            return Err(ApiError::Corrupted { expected, computed });
+        }
+        Self::deserialize_legacy(payload)
+    }
+
+    /// Deserialize bytes encoding a `ProofWithVK` without the header prepended by `serialize`,
+    /// as produced before the header was introduced
+    pub fn deserialize_legacy(buff: &[u8]) -> Result<Self, ApiError> {
+        bincode::deserialize(buff).map_err(ApiError::deserialization)
+    }
+
+    /// Fingerprint of the verifier data of the circuit which generated the proof, as computed
+    /// by `vk_fingerprint`
+    pub fn vk_fingerprint(&self) -> [u8; VK_FINGERPRINT_LEN] {
+        vk_fingerprint(&self.vk)
     }
 
-    pub fn deserialize(buff: &[u8]) -> Result<Self> {
-        let s = bincode::deserialize(buff)?;
-        Ok(s)
+    /// Fingerprint of the digest of the circuit which generated the proof, as computed by
+    /// `circuit_digest_fingerprint`
+    pub fn circuit_digest_fingerprint(&self) -> CircuitDigestFingerprint {
+        circuit_digest_fingerprint(&self.vk.circuit_digest)
     }
 
     pub(crate) fn proof(&self) -> &ProofWithPublicInputs<F, C, D> {
@@ -230,6 +457,66 @@ impl ProofWithVK {
     }
 }
 
+/// Number of bytes of the fingerprint of the verifier data of a circuit
+pub const VK_FINGERPRINT_LEN: usize = 32;
+
+/// Fingerprint of the digest of a circuit, computed by `circuit_digest_fingerprint`. It is kept
+/// apart from the fingerprints computed by `vk_fingerprint`, as they hash different data and
+/// can never be equal for the same circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CircuitDigestFingerprint(pub [u8; VK_FINGERPRINT_LEN]);
+
+/// Fingerprint of the verifier data `vk` of a circuit, i.e., the Keccak hash of the elements
+/// of its constants and sigmas cap followed by the ones of its circuit digest, each encoded as
+/// the little-endian bytes of its canonical `u64` representation. It is a cheap key to route
+/// a proof to the parameters of the circuit which generated it.
+pub fn vk_fingerprint(vk: &VerifierOnlyCircuitData<C, D>) -> [u8; VK_FINGERPRINT_LEN] {
+    let bytes = vk
+        .constants_sigmas_cap
+        .0
+        .iter()
+        .flat_map(|hash| hash.elements)
+        .chain(vk.circuit_digest.elements)
+        .flat_map(|x| x.to_canonical_u64().to_le_bytes())
+        .collect::<Vec<_>>();
+    keccak256(&bytes).try_into().unwrap()
+}
+
+/// Fingerprint of the digest of a circuit, i.e., the Keccak hash of its elements encoded as in
+/// `vk_fingerprint`. As the digest commits to the constants and sigmas cap, it identifies the
+/// verifier data of the circuit as well; unlike `vk_fingerprint`, it can be computed for the
+/// circuits of a `RecursiveCircuits` set, which stores only their digests.
+pub fn circuit_digest_fingerprint(digest: &HashOut<F>) -> CircuitDigestFingerprint {
+    let bytes = digest
+        .elements
+        .iter()
+        .flat_map(|x| x.to_canonical_u64().to_le_bytes())
+        .collect::<Vec<_>>();
+    CircuitDigestFingerprint(keccak256(&bytes).try_into().unwrap())
+}
+
+/// Compare two fingerprints in constant time, i.e., without revealing through the time taken
+/// the position of the first byte where they differ
+pub fn fingerprints_eq(a: &[u8; VK_FINGERPRINT_LEN], b: &[u8; VK_FINGERPRINT_LEN]) -> bool {
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Magic bytes starting the header of a serialized `ProofWithVK`
+const PROOF_WITH_VK_MAGIC: [u8; 4] = *b"PWVK";
+/// Version of the format of a serialized `ProofWithVK`, found in the header after the magic bytes
+const PROOF_WITH_VK_VERSION: u8 = 1;
+/// Length of the header of a serialized `ProofWithVK`: magic bytes, version and CRC32 checksum
+const PROOF_WITH_VK_HEADER_LEN: usize = PROOF_WITH_VK_MAGIC.len() + 1 + 4;
+
+/// CRC32 checksum (IEEE 802.3 polynomial) of `bytes`
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| {
+            (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
 impl
     From<(
         ProofWithPublicInputs<F, C, D>,
@@ -242,24 +529,220 @@ impl
             VerifierOnlyCircuitData<C, D>,
         ),
     ) -> Self {
-        ProofWithVK { proof, vk }
+        ProofWithVK::from_parts(proof, vk)
     }
 }
 
 pub fn serialize_proof<F: SerializableRichField<D>, C: GenericConfig<D, F = F>, const D: usize>(
     proof: &ProofWithPublicInputs<F, C, D>,
-) -> Result<Vec<u8>> {
-    Ok(bincode::serialize(&proof)?)
+) -> Result<Vec<u8>, ApiError> {
+    bincode::serialize(&proof).map_err(ApiError::Serialization)
 }
 
+/// Deserialize a proof serialized with `serialize_proof`. Note that the `bincode` format
+/// employed here requires `std`, so proofs cannot be deserialized on `no_std` targets yet; such
+/// targets can only decode the public inputs of proofs verified elsewhere, with the
+/// `mrp2_public_inputs` crate.
 pub fn deserialize_proof<
     F: SerializableRichField<D>,
     C: GenericConfig<D, F = F>,
     const D: usize,
 >(
     bytes: &[u8],
-) -> Result<ProofWithPublicInputs<F, C, D>> {
-    Ok(bincode::deserialize(bytes)?)
+) -> Result<ProofWithPublicInputs<F, C, D>, ApiError> {
+    bincode::deserialize(bytes).map_err(ApiError::deserialization)
+}
+
+/// Same as `deserialize_proof`, but the proof is rejected if it doesn't expose exactly
+/// `expected_num_pis` public inputs, so that proofs generated by another circuit are caught
+/// before being verified, where they would fail with a much less descriptive error
+pub fn deserialize_proof_expecting<
+    F: SerializableRichField<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    bytes: &[u8],
+    expected_num_pis: usize,
+) -> Result<ProofWithPublicInputs<F, C, D>, ApiError> {
+    let proof: ProofWithPublicInputs<F, C, D> = deserialize_proof(bytes)?;
+    if proof.public_inputs.len() != expected_num_pis {
+        return Err(ApiError::PublicInputMismatch {
+            got: proof.public_inputs.len(),
+            expected: expected_num_pis,
+        });
+    }
+    Ok(proof)
+}
+
+/// Same as `deserialize_proof`, but the proof is incrementally read from `reader`
+/// rather than being loaded in memory as a whole beforehand
+pub fn deserialize_proof_from_reader<
+    F: SerializableRichField<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+    R: Read,
+>(
+    reader: R,
+) -> Result<ProofWithPublicInputs<F, C, D>, ApiError> {
+    bincode::deserialize_from(reader).map_err(ApiError::deserialization)
+}
+
+/// Number of bytes employed to encode each public input in the output of
+/// `serialize_public_inputs`
+pub use mrp2_public_inputs::PUBLIC_INPUT_BYTES;
+
+/// Serialize only the public inputs of `proof`, employing the same layout expected by the
+/// Solidity verifier: each public input is encoded as the little-endian bytes of its canonical
+/// `u64` representation, and public inputs are concatenated in the order they are exposed by
+/// the circuit (i.e., the i-th public input is found at byte offset `i*PUBLIC_INPUT_BYTES`)
+pub fn serialize_public_inputs<F: RichField, C: GenericConfig<D, F = F>, const D: usize>(
+    proof: &ProofWithPublicInputs<F, C, D>,
+) -> Vec<u8> {
+    proof
+        .public_inputs
+        .iter()
+        .flat_map(|f| f.to_canonical_u64().to_le_bytes())
+        .collect()
+}
+
+/// Deserialize public inputs encoded with `serialize_public_inputs`
+pub fn deserialize_public_inputs<F: RichField>(bytes: &[u8]) -> Result<Vec<F>> {
+    Ok(mrp2_public_inputs::decode_public_inputs(bytes)?)
+}
+
+/// Number of bytes of the hash computed by `proof_public_inputs_hash`
+pub const PUBLIC_INPUTS_HASH_LEN: usize = 32;
+
+/// Poseidon hash of all the public inputs of a proof serialized with `serialize_proof`,
+/// encoded as the little-endian bytes of its 4 elements. It depends only on the public
+/// inputs, so it is a stable identifier of the statement proven, which can be logged and
+/// correlated with on-chain events regardless of the proof bytes.
+pub fn proof_public_inputs_hash(
+    proof_bytes: &[u8],
+) -> Result<[u8; PUBLIC_INPUTS_HASH_LEN], ApiError> {
+    let proof = deserialize_proof::<F, C, D>(proof_bytes)?;
+    Ok(PoseidonHash::hash_no_pad(&proof.public_inputs)
+        .to_bytes()
+        .try_into()
+        .unwrap())
+}
+
+/// NFT IDs revealed by a serialized revelation proof for `L`, as returned by the `processQuery`
+/// function of the Solidity verifier, i.e. only the IDs in the revealed window, without the
+/// padding ones
+pub fn revealed_nft_ids<const L: usize>(proof_bytes: &[u8]) -> Result<Vec<u32>, ApiError> {
+    let proof = deserialize_proof_expecting::<F, C, D>(
+        proof_bytes,
+        RevelationPublicInputs::<F, L>::total_len(),
+    )?;
+    let pis = RevelationPublicInputs::<F, L>::from(proof.public_inputs.as_slice());
+    Ok(pis
+        .revealed_nft_ids()
+        .into_iter()
+        .map(|id| id.as_u32())
+        .collect())
+}
+
+/// A serialized revelation proof for `L` bundled with its public inputs, so that services
+/// passing the proof around can read the revealed values without deserializing the proof each
+/// time. The public inputs are checked against the proof whenever the envelope is built or
+/// deserialized, after which they can be trusted as much as the proof itself.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(try_from = "UncheckedProofEnvelope")]
+pub struct ProofEnvelope<const L: usize> {
+    proof: Vec<u8>,
+    public_inputs: Vec<F>,
+}
+
+/// Envelope as found in the serialized bytes, before its public inputs are checked against
+/// the proof
+#[derive(Deserialize)]
+struct UncheckedProofEnvelope {
+    proof: Vec<u8>,
+    public_inputs: Vec<F>,
+}
+
+impl<const L: usize> TryFrom<UncheckedProofEnvelope> for ProofEnvelope<L> {
+    type Error = ApiError;
+
+    fn try_from(envelope: UncheckedProofEnvelope) -> Result<Self, Self::Error> {
+        let public_inputs = revelation_proof_public_inputs::<L>(&envelope.proof)?;
+        if public_inputs != envelope.public_inputs {
+            return Err(ApiError::EnvelopeMismatch);
+        }
+        Ok(Self {
+            proof: envelope.proof,
+            public_inputs,
+        })
+    }
+}
+
+/// Public inputs of a revelation proof for `L` serialized with `serialize_proof`. Unlike
+/// `deserialize_proof`, bytes trailing the proof are rejected, as otherwise forged public inputs
+/// could be appended to a genuine proof
+fn revelation_proof_public_inputs<const L: usize>(proof_bytes: &[u8]) -> Result<Vec<F>, ApiError> {
+    use bincode::Options;
+
+    let proof: ProofWithPublicInputs<F, C, D> = bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .reject_trailing_bytes()
+        .deserialize(proof_bytes)
+        .map_err(ApiError::deserialization)?;
+    let expected = RevelationPublicInputs::<F, L>::total_len();
+    if proof.public_inputs.len() != expected {
+        return Err(ApiError::PublicInputMismatch {
+            got: proof.public_inputs.len(),
+            expected,
+        });
+    }
+    Ok(proof.public_inputs)
+}
+
+impl<const L: usize> ProofEnvelope<L> {
+    /// Bundle a revelation proof serialized with `serialize_proof` with its public inputs
+    pub fn new(proof_bytes: Vec<u8>) -> Result<Self, ApiError> {
+        Ok(Self {
+            public_inputs: revelation_proof_public_inputs::<L>(&proof_bytes)?,
+            proof: proof_bytes,
+        })
+    }
+
+    /// Serialize the proof along with its public inputs
+    pub fn serialize(&self) -> Result<Vec<u8>, ApiError> {
+        bincode::serialize(&self).map_err(ApiError::Serialization)
+    }
+
+    /// Deserialize bytes produced by `serialize`; `ApiError::EnvelopeMismatch` is returned if
+    /// the public inputs found in the bytes aren't the ones exposed by the proof
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, ApiError> {
+        // the envelope is checked apart rather than through `serde`, to keep the `ApiError`
+        let envelope: UncheckedProofEnvelope =
+            bincode::deserialize(bytes).map_err(ApiError::deserialization)?;
+        envelope.try_into()
+    }
+
+    /// Public inputs of the proof, readable without deserializing it; an error is returned if
+    /// their number isn't the one exposed by revelation proofs for `L`
+    pub fn public_inputs(&self) -> Result<RevelationPublicInputs<'_, F, L>, ApiError> {
+        let expected = RevelationPublicInputs::<F, L>::total_len();
+        if self.public_inputs.len() != expected {
+            return Err(ApiError::PublicInputMismatch {
+                got: self.public_inputs.len(),
+                expected,
+            });
+        }
+        Ok(RevelationPublicInputs::from(self.public_inputs.as_slice()))
+    }
+
+    /// The proof, serialized with `serialize_proof`
+    pub fn proof_bytes(&self) -> &[u8] {
+        &self.proof
+    }
+
+    /// Unwrap the envelope into the proof, serialized with `serialize_proof`
+    pub fn into_proof_bytes(self) -> Vec<u8> {
+        self.proof
+    }
 }
 
 impl From<ProofWithVK>
@@ -269,7 +752,7 @@ impl From<ProofWithVK>
     )
 {
     fn from(val: ProofWithVK) -> Self {
-        (val.proof, val.vk)
+        val.into_parts()
     }
 }
 
@@ -310,6 +793,7 @@ pub(crate) mod tests {
     };
 
     use super::*;
+    use crate::keccak::PACKED_HASH_LEN;
     use anyhow::Result;
     use plonky2::{
         iop::witness::PartialWitness,
@@ -369,6 +853,282 @@ pub(crate) mod tests {
 
     pub(crate) use check_panic;
 
+    #[test]
+    fn test_serialize_public_inputs() {
+        const NUM_IO: usize = 5;
+        let circuit = TestDummyCircuit::<NUM_IO>::build();
+        let public_inputs = [
+            F::ZERO,
+            F::ONE,
+            F::from_canonical_u64(u32::MAX as u64),
+            F::NEG_ONE,
+            F::from_canonical_u64(42),
+        ];
+        let proof = circuit.generate_proof(public_inputs).unwrap();
+
+        let bytes = serialize_public_inputs(&proof);
+        assert_eq!(bytes.len(), NUM_IO * PUBLIC_INPUT_BYTES);
+        // check that the i-th public input is found at the expected offset in the serialized bytes
+        proof.public_inputs.iter().enumerate().for_each(|(i, pi)| {
+            let offset = i * PUBLIC_INPUT_BYTES;
+            assert_eq!(
+                u64::from_le_bytes(
+                    bytes[offset..offset + PUBLIC_INPUT_BYTES]
+                        .try_into()
+                        .unwrap()
+                ),
+                pi.to_canonical_u64(),
+            );
+        });
+        // round-trip
+        let deserialized = deserialize_public_inputs::<F>(&bytes).unwrap();
+        assert_eq!(deserialized, proof.public_inputs);
+        assert_eq!(deserialized, public_inputs.to_vec());
+        // the public inputs must also match the ones embedded in a serialized proof
+        let proof_bytes = serialize_proof(&proof).unwrap();
+        let deserialized_proof = deserialize_proof::<F, C, D>(&proof_bytes).unwrap();
+        assert_eq!(deserialized, deserialized_proof.public_inputs);
+        circuit.circuit_data().verify(deserialized_proof).unwrap();
+
+        // truncated and non-canonical inputs must be rejected
+        assert!(deserialize_public_inputs::<F>(&bytes[..bytes.len() - 1]).is_err());
+        assert!(deserialize_public_inputs::<F>(&u64::MAX.to_le_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_proof_public_inputs_hash() {
+        let circuit = TestDummyCircuit::<3>::build();
+        let proof = circuit.generate_proof([F::ONE, F::TWO, F::ZERO]).unwrap();
+        let bytes = serialize_proof(&proof).unwrap();
+        let hash = proof_public_inputs_hash(&bytes).unwrap();
+        assert_eq!(
+            hash.to_vec(),
+            PoseidonHash::hash_no_pad(&proof.public_inputs).to_bytes()
+        );
+
+        // serializing again the same proof, even after a round-trip, yields the same hash
+        let reserialized = serialize_proof(&deserialize_proof::<F, C, D>(&bytes).unwrap()).unwrap();
+        assert_eq!(proof_public_inputs_hash(&reserialized).unwrap(), hash);
+        // as does another proof of the same statement, even if its bytes differ
+        let other_proof = circuit.generate_proof([F::ONE, F::TWO, F::ZERO]).unwrap();
+        let other_bytes = serialize_proof(&other_proof).unwrap();
+        assert_eq!(proof_public_inputs_hash(&other_bytes).unwrap(), hash);
+        // while a proof with different public inputs yields a different hash
+        let different_proof = circuit.generate_proof([F::TWO, F::ONE, F::ZERO]).unwrap();
+        let different_bytes = serialize_proof(&different_proof).unwrap();
+        assert_ne!(proof_public_inputs_hash(&different_bytes).unwrap(), hash);
+
+        assert!(proof_public_inputs_hash(&bytes[..bytes.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn test_proof_with_vk_parts() {
+        const NUM_IO: usize = 2;
+        let circuit = TestDummyCircuit::<NUM_IO>::build();
+        let proof = circuit.generate_proof([F::ONE, F::TWO]).unwrap();
+        let vk = circuit.circuit_data().verifier_only.clone();
+
+        let proof_with_vk = ProofWithVK::from_parts(proof.clone(), vk.clone());
+        let bytes = proof_with_vk.serialize().unwrap();
+        let (deserialized_proof, deserialized_vk) =
+            ProofWithVK::deserialize(&bytes).unwrap().into_parts();
+        assert_eq!(deserialized_proof, proof);
+        assert_eq!(deserialized_vk, vk);
+        circuit.circuit_data().verify(deserialized_proof).unwrap();
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_proof_with_vk_checksum() {
+        let circuit = TestDummyCircuit::<2>::build();
+        let proof = circuit.generate_proof([F::ONE, F::TWO]).unwrap();
+        let vk = circuit.circuit_data().verifier_only.clone();
+        let proof_with_vk = ProofWithVK::from_parts(proof, vk);
+        let bytes = proof_with_vk.serialize().unwrap();
+        assert_eq!(ProofWithVK::deserialize(&bytes).unwrap(), proof_with_vk);
+
+        // flipped and truncated bytes fail the checksum
+        let is_corrupted = |bytes: &[u8]| {
+            let err = ProofWithVK::deserialize(bytes).unwrap_err();
+            matches!(err, ApiError::Corrupted { .. })
+        };
+        let mut flipped = bytes.clone();
+        *flipped.last_mut().unwrap() ^= 1;
+        assert!(is_corrupted(&flipped));
+        let mut flipped_checksum = bytes.clone();
+        flipped_checksum[PROOF_WITH_VK_HEADER_LEN - 1] ^= 1;
+        assert!(is_corrupted(&flipped_checksum));
+        assert!(is_corrupted(&bytes[..bytes.len() - 1]));
+
+        // bytes without the header are read only by the legacy path
+        let legacy_bytes = bincode::serialize(&proof_with_vk).unwrap();
+        assert_eq!(
+            ProofWithVK::deserialize_legacy(&legacy_bytes).unwrap(),
+            proof_with_vk
+        );
+        let err = ProofWithVK::deserialize(&legacy_bytes).unwrap_err();
+        assert!(err.to_string().contains("deserialize_legacy"));
+        assert!(ProofWithVK::deserialize_legacy(&bytes).is_err());
+
+        // an unknown version of the format is rejected
+        let mut other_version = bytes.clone();
+        other_version[PROOF_WITH_VK_MAGIC.len()] += 1;
+        let err = ProofWithVK::deserialize(&other_version).unwrap_err();
+        assert!(matches!(err, ApiError::Deserialization(_)));
+    }
+
+    #[test]
+    fn test_deserialize_proof_expecting() {
+        const NUM_IO: usize = 3;
+        let circuit = TestDummyCircuit::<NUM_IO>::build();
+        let proof = circuit.generate_proof([F::ONE, F::TWO, F::ZERO]).unwrap();
+        let bytes = serialize_proof(&proof).unwrap();
+
+        let deserialized = deserialize_proof_expecting::<F, C, D>(&bytes, NUM_IO).unwrap();
+        assert_eq!(deserialized, proof);
+
+        // a proof exposing a different number of public inputs is rejected, naming both counts
+        let err = deserialize_proof_expecting::<F, C, D>(&bytes, NUM_IO + 1).unwrap_err();
+        assert!(matches!(
+            err,
+            ApiError::PublicInputMismatch {
+                got: NUM_IO,
+                expected,
+            } if expected == NUM_IO + 1
+        ));
+        let err = err.to_string();
+        assert!(err.contains(&NUM_IO.to_string()) && err.contains(&(NUM_IO + 1).to_string()));
+    }
+
+    #[test]
+    fn test_api_error_on_corrupted_proof() {
+        let circuit = TestDummyCircuit::<2>::build();
+        let proof = circuit.generate_proof([F::ONE, F::TWO]).unwrap();
+        let bytes = serialize_proof(&proof).unwrap();
+
+        // truncated bytes are a deserialization error
+        let err = deserialize_proof::<F, C, D>(&bytes[..bytes.len() / 2]).unwrap_err();
+        assert!(matches!(err, ApiError::Deserialization(_)));
+        let err = ProofWithVK::deserialize(&bytes[..bytes.len() / 2]).unwrap_err();
+        assert!(matches!(err, ApiError::Deserialization(_)));
+        let err =
+            deserialize_proof_from_reader::<F, C, D, _>(&bytes[..bytes.len() / 2]).unwrap_err();
+        assert!(matches!(err, ApiError::Deserialization(_)));
+
+        // while a failure of the reader is an I/O error
+        struct FailingReader;
+        impl Read for FailingReader {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::Other, "disk failure"))
+            }
+        }
+        let err = deserialize_proof_from_reader::<F, C, D, _>(FailingReader).unwrap_err();
+        assert!(matches!(err, ApiError::Io(_)));
+        // the error of the reader is kept as the source
+        let source = std::error::Error::source(&err).unwrap();
+        assert_eq!(source.to_string(), "disk failure");
+    }
+
+    #[test]
+    fn test_proof_envelope() {
+        const L: usize = 2;
+        const NUM_IO: usize = RevelationPublicInputs::<F, L>::total_len();
+        let circuit = TestDummyCircuit::<NUM_IO>::build();
+        let public_inputs = std::array::from_fn(|i| F::from_canonical_usize(i));
+        let proof = circuit.generate_proof(public_inputs).unwrap();
+        let proof_bytes = serialize_proof(&proof).unwrap();
+
+        let envelope = ProofEnvelope::<L>::new(proof_bytes.clone()).unwrap();
+        let bytes = envelope.serialize().unwrap();
+        let deserialized = ProofEnvelope::<L>::deserialize(&bytes).unwrap();
+        assert_eq!(deserialized, envelope);
+        assert_eq!(
+            deserialized.public_inputs().unwrap().inputs.as_ref(),
+            &public_inputs
+        );
+        // the envelope read through `serde` is checked as well
+        assert_eq!(
+            bincode::deserialize::<ProofEnvelope<L>>(&bytes).unwrap(),
+            envelope
+        );
+        assert_eq!(deserialized.into_proof_bytes(), proof_bytes);
+
+        // a proof for another `L`, or bytes which are not a proof, can't be wrapped
+        assert!(ProofEnvelope::<{ L + 1 }>::new(proof_bytes.clone()).is_err());
+        assert!(ProofEnvelope::<L>::new(proof_bytes[..8].to_vec()).is_err());
+
+        // public inputs of a wrong length are reported rather than read
+        let mut truncated = envelope;
+        truncated.public_inputs.pop();
+        assert!(matches!(
+            truncated.public_inputs(),
+            Err(ApiError::PublicInputMismatch { got, expected })
+                if got == NUM_IO - 1 && expected == NUM_IO
+        ));
+    }
+
+    #[test]
+    fn test_proof_envelope_tampered() {
+        const L: usize = 2;
+        const NUM_IO: usize = RevelationPublicInputs::<F, L>::total_len();
+        let circuit = TestDummyCircuit::<NUM_IO>::build();
+        let proof = circuit.generate_proof([F::ONE; NUM_IO]).unwrap();
+        let mut envelope = ProofEnvelope::<L>::new(serialize_proof(&proof).unwrap()).unwrap();
+
+        // public inputs altered after the envelope was built are caught when it is loaded
+        envelope.public_inputs[0] = F::TWO;
+        let bytes = envelope.serialize().unwrap();
+        let err = ProofEnvelope::<L>::deserialize(&bytes).unwrap_err();
+        assert!(matches!(err, ApiError::EnvelopeMismatch));
+        // also when the envelope is deserialized through `serde`
+        assert!(bincode::deserialize::<ProofEnvelope<L>>(&bytes).is_err());
+
+        // forged public inputs appended to a genuine proof are not mistaken for its own ones,
+        // even though the proof is still deserialized by `deserialize_proof`
+        let forged_pis = vec![F::TWO; NUM_IO];
+        let mut forged_proof = serialize_proof(&proof).unwrap();
+        forged_proof.extend(bincode::serialize(&forged_pis).unwrap());
+        assert_eq!(
+            deserialize_proof::<F, C, D>(&forged_proof)
+                .unwrap()
+                .public_inputs,
+            proof.public_inputs
+        );
+        assert!(ProofEnvelope::<L>::new(forged_proof.clone()).is_err());
+        let forged_envelope = bincode::serialize(&(forged_proof, forged_pis)).unwrap();
+        assert!(ProofEnvelope::<L>::deserialize(&forged_envelope).is_err());
+        assert!(bincode::deserialize::<ProofEnvelope<L>>(&forged_envelope).is_err());
+    }
+
+    #[test]
+    fn test_test_config() {
+        let config = test_config();
+        assert!(config.security_bits < MIN_SECURITY_BITS);
+        assert!(default_config().security_bits >= MIN_SECURITY_BITS);
+
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let x = builder.add_virtual_target();
+        let y = builder.square(x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+        assert_eq!(data.common.config, config);
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(7));
+        let proof = data.prove(pw).unwrap();
+        assert_eq!(proof.public_inputs, vec![F::from_canonical_u64(49)]);
+        assert_eq!(
+            proof.proof.opening_proof.query_round_proofs.len(),
+            TEST_NUM_QUERY_ROUNDS
+        );
+        data.verify(proof).unwrap();
+    }
+
     #[test]
     fn test_verify_proof_with_fixed_circuit() {
         const NUM_IO: usize = 4;
@@ -462,4 +1222,40 @@ pub(crate) mod tests {
             "successful recursive verification of proof for second circuit with first verifier"
         );
     }
+
+    // building the parameters of all the LPN circuits takes long, so the merge logic is tested
+    // in `block` against the block DB circuits only
+    #[ignore]
+    #[test]
+    fn test_merge_block_db_proofs() -> Result<()> {
+        const MAX_DEPTH: usize = 4;
+        let params = build_circuits_params::<MAX_DEPTH>();
+        let dummy = params
+            .block_db
+            .generate_dummy_proof(F::ONE, [F::ZERO; PACKED_HASH_LEN])?
+            .serialize()?;
+        assert!(merge_block_db_proofs(&params, &dummy, &dummy[1..]).is_err());
+        // dummy block db proofs can't be merged, even over consecutive ranges of blocks
+        check_panic!(
+            || merge_block_db_proofs(&params, &dummy, &dummy),
+            "dummy block db proofs were merged"
+        );
+        Ok(())
+    }
+
+    // genuine block db proofs are tested in `query2::revelation` with the block DB circuits
+    // only, as state proofs can't be faked for the LPN parameters; this test covers the public
+    // entry point, which rejects invalid inputs before proving
+    #[ignore]
+    #[test]
+    fn test_build_block_db_proof() {
+        const MAX_DEPTH: usize = 4;
+        let params = build_circuits_params::<MAX_DEPTH>();
+        let err = block::build_block_db_proof(&params, &[]).unwrap_err();
+        assert!(err.to_string().contains("at least one block"), "{err}");
+        let too_many_blocks = vec![vec![0u8; 32]; (1 << MAX_DEPTH) + 1];
+        let err = block::build_block_db_proof(&params, &too_many_blocks).unwrap_err();
+        assert!(err.to_string().contains("can't be inserted"), "{err}");
+        assert!(block::build_block_db_proof(&params, &[vec![0u8; 32]]).is_err());
+    }
 }
@@ -0,0 +1,202 @@
+//! Off-circuit analysis of how the result of a query changes between two states of the
+//! block db, for incremental clients which already hold the result for an older state
+use std::collections::BTreeSet;
+
+use anyhow::{ensure, Result};
+use plonky2::field::types::PrimeField64;
+
+use super::PublicInputs;
+use crate::{
+    api::F,
+    query2::revelation::{Query, RevelationPublicInputs},
+};
+
+/// NFT IDs added to and removed from the result of a query between two block db states
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResultDelta {
+    /// IDs revealed for the new state but not for the old one, in increasing order
+    pub added: Vec<u32>,
+    /// IDs revealed for the old state but not for the new one, in increasing order
+    pub removed: Vec<u32>,
+}
+
+impl ResultDelta {
+    /// Returns true if the result of the query is the same for both states
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Compute the NFT IDs added and removed between the results of `query` over the block db
+/// states proven by the block db proofs `old_proof` and `new_proof`, where the result for
+/// each state is the one revealed by the revelation proofs `old_result` and `new_result`.
+/// All the proofs must have already been verified; the new state must extend the old one,
+/// and each revelation proof must be bound to the block of its block db state and reveal the
+/// whole result, i.e. it can't be paginated. The block range and the block hash of the query
+/// may differ between the two states, while the mapping being queried must be the one of
+/// `query`.
+pub fn result_delta<const L: usize>(
+    old_proof: &PublicInputs<F>,
+    new_proof: &PublicInputs<F>,
+    query: &Query,
+    old_result: &RevelationPublicInputs<F, L>,
+    new_result: &RevelationPublicInputs<F, L>,
+) -> Result<ResultDelta> {
+    ensure!(
+        old_proof.init_root_data() == new_proof.init_root_data()
+            && old_proof.first_block_number_data() == new_proof.first_block_number_data()
+            && old_proof.block_number_data().to_canonical_u64()
+                <= new_proof.block_number_data().to_canonical_u64(),
+        "the new block db state doesn't extend the old one",
+    );
+    let old_ids = revealed_ids(old_proof, old_result, query, "old")?;
+    let new_ids = revealed_ids(new_proof, new_result, query, "new")?;
+    Ok(ResultDelta {
+        added: new_ids.difference(&old_ids).copied().collect(),
+        removed: old_ids.difference(&new_ids).copied().collect(),
+    })
+}
+
+/// Return the NFT IDs revealed by `proof`, checking that it is bound to the last block of
+/// the block db state `block_db`, that it answers `query` and that it reveals all the
+/// matching keys
+fn revealed_ids<const L: usize>(
+    block_db: &PublicInputs<F>,
+    proof: &RevelationPublicInputs<F, L>,
+    query: &Query,
+    state: &str,
+) -> Result<BTreeSet<u32>> {
+    ensure!(
+        proof.block_header() == block_db.block_header_data(),
+        "revelation proof for the {} state is not bound to the block db state",
+        state,
+    );
+    let answered = Query::from_public_inputs(proof)?;
+    ensure!(
+        answered.contract_address == query.contract_address
+            && answered.user_address == query.user_address
+            && answered.mapping_slot == query.mapping_slot
+            && answered.length_slot == query.length_slot,
+        "revelation proof for the {} state answers another query: {:?}",
+        state,
+        answered,
+    );
+    let window = proof.revealed_window();
+    let total_matching = proof.total_matching().to_canonical_u64() as usize;
+    ensure!(
+        window == (0..total_matching),
+        "revelation proof for the {} state reveals only the window {:?} of {} matching keys",
+        state,
+        window,
+        total_matching,
+    );
+    Ok(proof.nft_ids()[window]
+        .iter()
+        .map(|id| id.to_canonical_u64() as u32)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::{Address, U256};
+    use plonky2::field::types::Field;
+
+    use super::*;
+    use crate::{keccak::PACKED_HASH_LEN, query2::revelation::revelation_layout};
+
+    const L: usize = 4;
+
+    /// Build the public inputs of a block db proof for the state whose last block is
+    /// `block_number`, with hash `block_hash`
+    fn block_db_public_inputs(block_number: u32, block_hash: u32) -> Vec<F> {
+        PublicInputs::from_parts(
+            &[F::ONE; 4],
+            &[F::from_canonical_u32(block_number); 4],
+            F::ZERO,
+            F::from_canonical_u32(block_number),
+            &[F::from_canonical_u32(block_hash); PACKED_HASH_LEN],
+        )
+        .to_vec()
+    }
+
+    /// Build the public inputs of a revelation proof answering `query`, over the block with
+    /// hash `block_hash`, with all the given NFT IDs revealed
+    fn revelation_public_inputs(
+        query: &Query,
+        block_hash: u32,
+        ids: &[u32],
+    ) -> RevelationPublicInputs<'static, F, L> {
+        let mut inputs = vec![F::ZERO; RevelationPublicInputs::<F, L>::total_len()];
+        for (name, range) in revelation_layout(L) {
+            let values = match name {
+                "min_block_number" => vec![query.min_block_number as u64],
+                "max_block_number" => vec![query.max_block_number as u64],
+                "mapping_slot" => vec![query.mapping_slot],
+                "mapping_slot_length" => vec![query.length_slot],
+                "block_header" => vec![block_hash as u64; PACKED_HASH_LEN],
+                "nft_ids" => ids.iter().map(|id| *id as u64).collect(),
+                "total_matching" => vec![ids.len() as u64],
+                "page_limit" => vec![L as u64],
+                _ => continue,
+            };
+            inputs[range]
+                .iter_mut()
+                .zip(values)
+                .for_each(|(input, value)| *input = F::from_canonical_u64(value));
+        }
+        RevelationPublicInputs::from(inputs)
+    }
+
+    #[test]
+    fn test_result_delta() -> Result<()> {
+        let query = Query {
+            contract_address: Address::zero(),
+            user_address: Address::zero(),
+            mapping_slot: 3,
+            length_slot: 4,
+            min_block_number: 10,
+            max_block_number: 20,
+            block_hash: U256::zero(),
+        };
+        let old_inputs = block_db_public_inputs(20, 1);
+        let old_proof = PublicInputs::from(&old_inputs);
+        let old_result = revelation_public_inputs(&query, 1, &[5, 12]);
+        // the key 8 is added to the mapping in the new block db state
+        let new_inputs = block_db_public_inputs(30, 2);
+        let new_proof = PublicInputs::from(&new_inputs);
+        let new_query = Query {
+            max_block_number: 30,
+            ..query.clone()
+        };
+        let new_result = revelation_public_inputs(&new_query, 2, &[5, 8, 12]);
+
+        let delta = result_delta(&old_proof, &new_proof, &query, &old_result, &new_result)?;
+        assert_eq!(
+            delta,
+            ResultDelta {
+                added: vec![8],
+                removed: vec![],
+            }
+        );
+        assert!(result_delta(&old_proof, &old_proof, &query, &old_result, &old_result)?.is_empty());
+
+        // proofs answering another query are rejected
+        let other_query = Query {
+            mapping_slot: 5,
+            ..query.clone()
+        };
+        assert!(result_delta(
+            &old_proof,
+            &new_proof,
+            &other_query,
+            &old_result,
+            &new_result
+        )
+        .is_err());
+        // revelation proofs not bound to the block db states are rejected
+        assert!(result_delta(&old_proof, &new_proof, &query, &new_result, &old_result).is_err());
+        // an older block db state can't be the new one
+        assert!(result_delta(&new_proof, &old_proof, &query, &new_result, &old_result).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+//! Circuit merging two block db proofs covering consecutive ranges of blocks into a single
+//! proof spanning both ranges
+use anyhow::{ensure, Result};
+use plonky2::{
+    field::types::{Field, PrimeField64},
+    iop::{target::Target, witness::PartialWitness},
+    plonk::{circuit_builder::CircuitBuilder, proof::ProofWithPublicInputsTarget},
+};
+use recursion_framework::circuit_builder::CircuitLogicWires;
+use serde::{Deserialize, Serialize};
+
+use super::{PublicInputs, D, F, NUM_IO, NUM_IVC_PUBLIC_INPUTS};
+
+/// Check that the block db public inputs `earlier` and `later` cover consecutive ranges of
+/// blocks, i.e., that the later range starts from the root of the block db found at the end
+/// of the earlier range, with the block following the last one of the earlier range
+pub(crate) fn check_consecutive_ranges(earlier: &[F], later: &[F]) -> Result<()> {
+    let earlier = PublicInputs::from(earlier);
+    let later = PublicInputs::from(later);
+    ensure!(
+        earlier.root_data() == later.init_root_data(),
+        "the later block db proof doesn't start from the last root of the earlier one"
+    );
+    let last_block_number = earlier.block_number_data().to_canonical_u64();
+    let first_block_number = later.first_block_number_data().to_canonical_u64();
+    ensure!(
+        first_block_number == last_block_number + 1,
+        "the later block db proof starts from block {}, while the earlier one ends at block {}",
+        first_block_number,
+        last_block_number,
+    );
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+/// Wires for the circuit merging two block db proofs covering consecutive ranges of blocks;
+/// there are no witness values besides the verified proofs, as the merged proof exposes the
+/// initial values of the earlier proof and the last values of the later one
+pub(crate) struct BlockDbMergeWires;
+
+impl CircuitLogicWires<F, D, 2> for BlockDbMergeWires
+where
+    [(); NUM_IVC_PUBLIC_INPUTS]:,
+{
+    type CircuitBuilderParams = ();
+
+    type Inputs = ();
+
+    const NUM_PUBLIC_INPUTS: usize = NUM_IVC_PUBLIC_INPUTS;
+
+    fn circuit_logic(
+        builder: &mut CircuitBuilder<F, D>,
+        verified_proofs: [&ProofWithPublicInputsTarget<D>; 2],
+        _builder_parameters: Self::CircuitBuilderParams,
+    ) -> Self {
+        let [(earlier_pi, earlier_is_dummy), (later_pi, later_is_dummy)] =
+            verified_proofs.map(|proof| Self::public_input_targets(proof).split_at(NUM_IO));
+        // dummy proofs expose arbitrary values, so they can't be merged
+        let zero = builder.zero();
+        builder.connect(earlier_is_dummy[0], zero);
+        builder.connect(later_is_dummy[0], zero);
+
+        let earlier_pi = PublicInputs::<Target>::from(earlier_pi);
+        let later_pi = PublicInputs::<Target>::from(later_pi);
+        builder.connect_hashes(earlier_pi.root(), later_pi.init_root());
+        let next_block_number = builder.add_const(earlier_pi.block_number().0, F::ONE);
+        builder.connect(next_block_number, later_pi.first_block_number().0);
+
+        PublicInputs::<Target>::register(
+            builder,
+            &earlier_pi.init_root(),
+            &later_pi.root(),
+            earlier_pi.first_block_number(),
+            later_pi.block_number(),
+            &later_pi.original_block_header(),
+        );
+        // register public input stating that this is not a dummy proof
+        builder.register_public_input(zero);
+        Self
+    }
+
+    fn assign_input(&self, _inputs: Self::Inputs, _pw: &mut PartialWitness<F>) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::{field::types::Sample, hash::hash_types::HashOut};
+
+    use crate::query2::revelation::test_utils::merged_block_db_public_inputs;
+
+    use super::*;
+
+    fn block_db_inputs(
+        init_root: &HashOut<F>,
+        last_root: &HashOut<F>,
+        first: u32,
+        last: u32,
+    ) -> [F; NUM_IVC_PUBLIC_INPUTS] {
+        merged_block_db_public_inputs(init_root, last_root, first, last, &[0; 32])
+    }
+
+    #[test]
+    fn test_check_consecutive_ranges() {
+        let [init_root, middle_root, last_root] = [(); 3].map(|_| HashOut::<F>::rand());
+        let earlier = block_db_inputs(&init_root, &middle_root, 10, 20);
+        let later = block_db_inputs(&middle_root, &last_root, 21, 30);
+        assert!(check_consecutive_ranges(&earlier, &later).is_ok());
+        // the ranges must be merged in order
+        assert!(check_consecutive_ranges(&later, &earlier).is_err());
+        // there can be no gap between the ranges
+        let later = block_db_inputs(&middle_root, &last_root, 22, 30);
+        assert!(check_consecutive_ranges(&earlier, &later).is_err());
+        // the later range must start from the last root of the earlier one
+        let later = block_db_inputs(&init_root, &last_root, 21, 30);
+        assert!(check_consecutive_ranges(&earlier, &later).is_err());
+    }
+}
@@ -5,19 +5,28 @@
 //! - Prove the append-only property, that we keep appending blocks without
 //!   deletion and modification.
 
+mod delta;
+mod merge;
 pub mod public_inputs;
+pub use delta::{result_delta, ResultDelta};
 pub use public_inputs::PublicInputs;
 
 use crate::{
-    api::{default_config, ProofWithVK},
-    keccak::PACKED_HASH_LEN,
+    api::{default_config, serialize_proof, ApiError, ProofWithVK, PublicParameters},
+    keccak::{pack_hash_le, PACKED_HASH_LEN},
     state::{self, StateInputs},
     types::HashOutput,
-    utils::{convert_u8_to_u32_slice, hash_two_to_one},
+    utils::{convert_u32_fields_to_u8_vec, hash_two_to_one},
 };
-use anyhow::Result;
+use anyhow::{ensure, Result};
+use merge::check_consecutive_ranges;
+pub(crate) use merge::BlockDbMergeWires;
 use plonky2::{
-    field::{extension::Extendable, goldilocks_field::GoldilocksField, types::Field},
+    field::{
+        extension::Extendable,
+        goldilocks_field::GoldilocksField,
+        types::{Field, PrimeField64},
+    },
     hash::{
         hash_types::{HashOut, HashOutTarget, RichField},
         merkle_proofs::{MerkleProof, MerkleProofTarget},
@@ -49,7 +58,7 @@ use recursion_framework::{
     },
 };
 use serde::{Deserialize, Serialize};
-use std::array;
+use std::{array, ops::RangeInclusive};
 
 /// Returns the hash in bytes of the leaf of the block tree. It takes as parameters
 /// the block number, the block header in bytes and the state root in bytes.
@@ -60,7 +69,8 @@ pub fn block_leaf_hash(
     block_header: &HashOutput,
     state_root: &HashOutput,
 ) -> HashOutput {
-    let bh = convert_u8_to_u32_slice(block_header);
+    // the block header is packed as in the circuit
+    let bh = pack_hash_le(block_header);
     let sr = HashOut::from_bytes(state_root);
     let f_slice = std::iter::once(block_number)
         .chain(bh)
@@ -311,7 +321,7 @@ type F = crate::api::F;
 type C = crate::api::C;
 const D: usize = crate::api::D;
 const NUM_IO: usize = PublicInputs::<Target>::TOTAL_LEN;
-const NUM_STATE_PUBLIC_INPUTS: usize = StateInputs::<Target>::TOTAL_LEN;
+pub(crate) const NUM_STATE_PUBLIC_INPUTS: usize = StateInputs::<Target>::TOTAL_LEN;
 // number of public inputs for IVC block DB circuit; it has one additional
 // public input with respect to the public inputs of `BlockTreeCircuit`,
 // which is employed to determine whether the proof being verified is a proof
@@ -445,6 +455,7 @@ impl<const MAX_DEPTH: usize> CircuitLogicWires<F, D, 0> for DummyCircuitWires<MA
 pub(crate) struct Parameters<const MAX_DEPTH: usize> {
     dummy: CircuitWithUniversalVerifier<F, C, D, 0, DummyCircuitWires<MAX_DEPTH>>,
     ivc_circuit: CircuitWithUniversalVerifier<F, C, D, 1, BlockTreeRecursiveWires<MAX_DEPTH, D>>,
+    merge: CircuitWithUniversalVerifier<F, C, D, 2, BlockDbMergeWires>,
     set: RecursiveCircuits<F, C, D>,
 }
 
@@ -454,13 +465,18 @@ where
 {
     /// Build parameters for circuits related to the construction of the block DB tree
     pub(crate) fn build(state_circuit_set: &RecursiveCircuits<F, C, D>) -> Self {
-        const IVC_CIRCUIT_SET_SIZE: usize = 2;
+        // The circuit set is made of the dummy, IVC and merge circuits; changing its size, as
+        // done when the merge circuit was added, changes the digest of the circuit set, so that
+        // block DB proofs and query parameters generated with previous versions of these
+        // parameters must be regenerated (see README)
+        const IVC_CIRCUIT_SET_SIZE: usize = 3;
         let builder = CircuitWithUniversalVerifierBuilder::<F, D, NUM_IVC_PUBLIC_INPUTS>::new::<C>(
             default_config(),
             IVC_CIRCUIT_SET_SIZE,
         );
         let dummy = builder.build_circuit(());
         let ivc_circuit = builder.build_circuit(state_circuit_set.clone());
+        let merge = builder.build_circuit(());
 
         // It's okay to use the circuit set mechanism here since the prover can not give a dummy proof after the first
         // block insertion because the regular circuit checks if it's the first insertion or not and sets a flag accordingly.
@@ -468,6 +484,7 @@ where
         let circuits = vec![
             prepare_recursive_circuit_for_circuit_set(&dummy),
             prepare_recursive_circuit_for_circuit_set(&ivc_circuit),
+            prepare_recursive_circuit_for_circuit_set(&merge),
         ];
 
         let circuit_set = RecursiveCircuits::<F, C, D>::new(circuits);
@@ -475,6 +492,7 @@ where
         Self {
             dummy,
             ivc_circuit,
+            merge,
             set: circuit_set,
         }
     }
@@ -490,15 +508,10 @@ where
 
                 let leaf_pi =
                     StateInputs::from_slice(state::lpn::api::Parameters::public_inputs(leaf_proof));
-                let dummy_proof_inputs = DummyCircuitInputs {
-                    first_block_number: leaf_pi.block_number_data(),
-                    parent_hash: leaf_pi.prev_block_header_data().try_into().unwrap(),
-                };
-                let dummy_proof =
-                    self.set
-                        .generate_proof(&self.dummy, [], [], dummy_proof_inputs)?;
-                let previous_proof =
-                    (dummy_proof, self.dummy.circuit_data().verifier_only.clone()).into();
+                let previous_proof = self.generate_dummy_proof(
+                    leaf_pi.block_number_data(),
+                    leaf_pi.prev_block_header_data().try_into().unwrap(),
+                )?;
                 let inputs = Inputs::Subsequent(BlockTreeCircuitInputs {
                     base_inputs: input,
                     previous_proof,
@@ -514,17 +527,141 @@ where
                     [&input_vd],
                     block_tree_inputs,
                 )?;
-                ProofWithVK::from((proof, self.ivc_circuit.circuit_data().verifier_only.clone()))
-                    .serialize()
+                let proof = ProofWithVK::from((
+                    proof,
+                    self.ivc_circuit.circuit_data().verifier_only.clone(),
+                ));
+                Ok(proof.serialize()?)
             }
         }
     }
 
-    /// Verify proof generated by `generate_proof` method
+    /// Generate a proof of the dummy circuit, exposing the values expected by the IVC circuit to
+    /// insert the block `first_block_number`, whose parent hash is `parent_hash`, in an empty
+    /// block DB tree
+    pub(crate) fn generate_dummy_proof(
+        &self,
+        first_block_number: F,
+        parent_hash: [F; PACKED_HASH_LEN],
+    ) -> Result<ProofWithVK> {
+        let dummy_proof_inputs = DummyCircuitInputs {
+            first_block_number,
+            parent_hash,
+        };
+        let dummy_proof = self
+            .set
+            .generate_proof(&self.dummy, [], [], dummy_proof_inputs)?;
+        Ok((dummy_proof, self.dummy.circuit_data().verifier_only.clone()).into())
+    }
+
+    /// Merge the block db proofs `earlier` and `later`, covering consecutive ranges of blocks,
+    /// into a proof spanning both ranges, i.e., exposing the initial root and block number of
+    /// `earlier` and the last root, block number and block header of `later`
+    pub(crate) fn merge_proofs(&self, earlier: &[u8], later: &[u8]) -> Result<Vec<u8>> {
+        let (earlier_proof, earlier_vd) = ProofWithVK::deserialize(earlier)?.into();
+        let (later_proof, later_vd) = ProofWithVK::deserialize(later)?.into();
+        check_consecutive_ranges(
+            Self::block_tree_public_inputs(&earlier_proof),
+            Self::block_tree_public_inputs(&later_proof),
+        )?;
+        let proof = self.set.generate_proof(
+            &self.merge,
+            [earlier_proof, later_proof],
+            [&earlier_vd, &later_vd],
+            (),
+        )?;
+        let proof = ProofWithVK::from((proof, self.merge.circuit_data().verifier_only.clone()));
+        Ok(proof.serialize()?)
+    }
+
+    /// Insert the blocks whose LPN state proofs, generated by the circuits in `state_circuit_set`,
+    /// are `state_proofs` in an empty block DB tree, generating an IVC proof for each block. The
+    /// last IVC proof is returned serialized as expected by query circuits, i.e., without its
+    /// verifier data
+    pub(crate) fn build_block_db_proof(
+        &self,
+        state_circuit_set: &RecursiveCircuits<F, C, D>,
+        state_proofs: &[Vec<u8>],
+    ) -> Result<Vec<u8>> {
+        ensure!(
+            !state_proofs.is_empty(),
+            "at least one block must be inserted in the block DB"
+        );
+        ensure!(
+            state_proofs.len() <= 1 << MAX_DEPTH,
+            "{} blocks can't be inserted in a block DB tree of depth {}",
+            state_proofs.len(),
+            MAX_DEPTH,
+        );
+        let state_proofs = state_proofs
+            .iter()
+            .map(|proof| ProofWithVK::deserialize(proof))
+            .collect::<Result<Vec<_>, _>>()?;
+        let state_inputs = state_proofs
+            .iter()
+            .map(|proof| {
+                StateInputs::from_slice(state::lpn::api::Parameters::public_inputs(proof.proof()))
+            })
+            .collect::<Vec<_>>();
+        for (previous, next) in state_inputs.iter().zip(&state_inputs[1..]) {
+            ensure!(
+                next.block_number_data() == previous.block_number_data() + F::ONE,
+                "block {} can't be inserted right after block {}: blocks must be consecutive",
+                next.block_number_data(),
+                previous.block_number_data(),
+            );
+            ensure!(
+                next.prev_block_header_data() == previous.block_header_data(),
+                "the parent hash of block {} is not the hash of the previous block",
+                next.block_number_data(),
+            );
+        }
+        let leaf_hashes = state_inputs
+            .iter()
+            .map(|pi| {
+                let block_header = convert_u32_fields_to_u8_vec(pi.block_header_data());
+                let state_root = HashOut::<F>::from_partial(pi.root_data()).to_bytes();
+                HashOut::from_bytes(&block_leaf_hash(
+                    pi.block_number_data().to_canonical_u64() as u32,
+                    &block_header.try_into().unwrap(),
+                    &state_root.try_into().unwrap(),
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let mut previous_proof: Option<ProofWithVK> = None;
+        for (leaf_index, new_leaf_proof) in state_proofs.into_iter().enumerate() {
+            let (root, path) =
+                block_tree_root_and_path::<MAX_DEPTH>(&leaf_hashes[..=leaf_index], leaf_index);
+            let base_inputs = BlockTreeInputs {
+                block_tree: BlockTreeCircuit::new_from(leaf_index, root, path),
+                new_leaf_proof,
+                state_circuit_set: state_circuit_set.clone(),
+            };
+            let inputs = match previous_proof {
+                None => Inputs::First(base_inputs),
+                Some(previous_proof) => Inputs::Subsequent(BlockTreeCircuitInputs {
+                    base_inputs,
+                    previous_proof,
+                }),
+            };
+            previous_proof = Some(ProofWithVK::deserialize(&self.generate_proof(inputs)?)?);
+        }
+        Ok(serialize_proof(previous_proof.unwrap().proof())?)
+    }
+
+    /// Verify proof generated by `generate_proof` or `merge_proofs` methods
     pub(crate) fn verify_proof(&self, proof: &[u8]) -> Result<()> {
         let proof = ProofWithVK::deserialize(proof)?;
-        let (proof, _) = proof.into();
-        self.ivc_circuit.circuit_data().verify(proof)
+        let (proof, vd) = proof.into();
+        let circuit_data = if vd == self.merge.circuit_data().verifier_only {
+            self.merge.circuit_data()
+        } else {
+            self.ivc_circuit.circuit_data()
+        };
+        Ok(circuit_data
+            .verify(proof)
+            .map_err(ApiError::VerificationFailed)?)
     }
     /// Get the public inputs corresponding to the block tree circuit logic from a proof generated
     /// by the IVC block tree circuit
@@ -547,6 +684,10 @@ where
     pub(crate) fn get_block_db_vk(&self) -> &VerifierOnlyCircuitData<C, D> {
         &self.ivc_circuit.circuit_data().verifier_only
     }
+
+    pub(crate) fn get_block_db_merge_vk(&self) -> &VerifierOnlyCircuitData<C, D> {
+        &self.merge.circuit_data().verifier_only
+    }
 }
 
 /// This data structure contains all the inputs necessary to generate a proof for
@@ -666,14 +807,89 @@ pub fn empty_merkle_root<F: SerializableRichField<D>, const D: usize, const MAX_
     })
 }
 
+/// Compute the root of the block DB tree whose first leaves have hashes `leaf_hashes`, the
+/// other ones being empty, together with the Merkle path of the leaf at `leaf_index`
+fn block_tree_root_and_path<const MAX_DEPTH: usize>(
+    leaf_hashes: &[HashOut<F>],
+    mut leaf_index: usize,
+) -> (HashOut<F>, MerkleProof<F, PoseidonHash>) {
+    // root of an empty subtree at the current level
+    let mut empty = HashOut::<F>::from_partial(&[]);
+    let mut layer = leaf_hashes.to_vec();
+    let mut siblings = Vec::with_capacity(MAX_DEPTH);
+    for _ in 0..MAX_DEPTH {
+        siblings.push(layer.get(leaf_index ^ 1).copied().unwrap_or(empty));
+        layer = layer
+            .chunks(2)
+            .map(|pair| PoseidonHash::two_to_one(pair[0], pair.get(1).copied().unwrap_or(empty)))
+            .collect();
+        empty = PoseidonHash::two_to_one(empty, empty);
+        leaf_index >>= 1;
+    }
+    (layer[0], MerkleProof { siblings })
+}
+
+/// Build a block DB proof with the block DB circuits in `params`, inserting in an empty block DB
+/// the consecutive blocks whose LPN state proofs, generated with `params` too, are
+/// `state_proofs`, sorted by block number. This is meant for integration tests wiring real block
+/// ingestion: the proof is a genuine IVC proof, whose initial root is the empty root, and whose
+/// last root, block range and last block hash are the ones of the inserted blocks, as they
+/// can't be chosen freely. The proof is serialized as expected by query circuits, e.g. by
+/// `RevelationRecursiveInput::new`.
+pub fn build_block_db_proof<const MAX_DEPTH: usize>(
+    params: &PublicParameters<MAX_DEPTH>,
+    state_proofs: &[Vec<u8>],
+) -> Result<Vec<u8>> {
+    params.build_block_db_proof(state_proofs)
+}
+
+/// Reference to the commitments of a range of consecutive blocks in the block db, which are
+/// all covered by a single block db proof for `db_root`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockRef {
+    /// Root of the block db the blocks are committed to
+    pub db_root: HashOutput,
+    /// Numbers of the blocks, which identify the leaves of the block db storing the blocks
+    pub blocks: RangeInclusive<u32>,
+}
+
+/// Returns the references to the block commitments the revelation of a query over the
+/// blocks in `[min_block, max_block]` relies on, given the root of the block db, of depth
+/// `MAX_DEPTH`, the query is proven against. As the block db is append-only, all these blocks
+/// are committed to the same root, so the minimal set of block db proofs covering the range is
+/// the single proof for `db_root`, referenced for the whole range; `None` is returned if the
+/// range is empty. Fails if the range has more blocks than the block db could store.
+pub fn required_proofs_for_range<const MAX_DEPTH: usize>(
+    db_root: HashOutput,
+    min_block: u32,
+    max_block: u32,
+) -> Result<Option<BlockRef>> {
+    if min_block > max_block {
+        return Ok(None);
+    }
+    let num_blocks = u64::from(max_block - min_block) + 1;
+    ensure!(
+        num_blocks <= 1 << MAX_DEPTH,
+        "block range [{min_block}, {max_block}] has {num_blocks} blocks, while a block db of \
+        depth {MAX_DEPTH} stores at most {} blocks",
+        1u64 << MAX_DEPTH
+    );
+    Ok(Some(BlockRef {
+        db_root,
+        blocks: min_block..=max_block,
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs;
 
     use super::*;
     use crate::{
+        api::{deserialize_proof, tests::check_panic},
         array::Array,
         keccak::{HASH_LEN, PACKED_HASH_LEN},
+        utils::convert_u32_fields_to_u8_vec,
     };
     use mrp2_test_utils::{
         circuit::{run_circuit, UserCircuit},
@@ -1031,11 +1247,7 @@ mod tests {
         // state root
         pi.extend(&state_root.elements);
         // block header from blockchain in packed format
-        pi.extend(
-            convert_u8_to_u32_slice(&block_header)
-                .into_iter()
-                .map(F::from_canonical_u32),
-        );
+        pi.extend(pack_hash_le(&block_header).map(F::from_canonical_u32));
         // block number in u32 format
         pi.push(F::from_canonical_u32(block_number));
         // previous block hash - useless in this case but still
@@ -1238,4 +1450,121 @@ mod tests {
             )
             .unwrap();
     }
+
+    #[test]
+    fn test_block_hash_from_bytes() {
+        let block_hash: HashOutput = array::from_fn(|i| i as u8);
+        let limbs = PublicInputs::from_block_hash_bytes(&block_hash);
+        // each limb packs 4 bytes of the hash in little-endian order
+        assert_eq!(limbs[0], F::from_canonical_u32(0x03020100));
+        assert_eq!(
+            limbs[PACKED_HASH_LEN - 1],
+            F::from_canonical_u32(0x1f1e1d1c)
+        );
+        assert_eq!(convert_u32_fields_to_u8_vec(&limbs), block_hash);
+    }
+
+    /// Generate fake LPN state proofs, with the circuits in `state_circuits`, for `num_blocks`
+    /// consecutive blocks starting from `first_block`
+    fn fake_state_proofs(
+        state_circuits: &TestingRecursiveCircuits<F, C, D, NUM_STATE_PUBLIC_INPUTS>,
+        first_block: u32,
+        num_blocks: usize,
+    ) -> Result<Vec<Vec<u8>>> {
+        let block_hashes = (0..=num_blocks)
+            .map(|_| PublicInputs::from_block_hash_bytes(&thread_rng().gen()))
+            .collect::<Vec<_>>();
+        (0..num_blocks)
+            .map(|i| {
+                // [state_root, block_header, block_number, prev_block_header]
+                let state_inputs = F::rand_vec(NUM_HASH_OUT_ELTS)
+                    .into_iter()
+                    .chain(block_hashes[i + 1])
+                    .chain(std::iter::once(F::from_canonical_u32(
+                        first_block + i as u32,
+                    )))
+                    .chain(block_hashes[i])
+                    .collect::<Vec<_>>();
+                let proof = state_circuits
+                    .generate_input_proofs::<1>([state_inputs.try_into().unwrap()])?[0]
+                    .clone();
+                let vd = state_circuits.verifier_data_for_input_proofs::<1>()[0].clone();
+                Ok(ProofWithVK::from_parts(proof, vd).serialize()?)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_proofs() -> Result<()> {
+        const MAX_DEPTH: usize = 4;
+        let state_circuits =
+            TestingRecursiveCircuits::<F, C, D, NUM_STATE_PUBLIC_INPUTS>::default();
+        let params = Parameters::<MAX_DEPTH>::build(state_circuits.get_recursive_circuit_set());
+        let first_block = thread_rng().gen_range(1..10_000u32);
+        let state_proofs = fake_state_proofs(&state_circuits, first_block, 3)?;
+        let ivc_proof = |state_proofs: &[Vec<u8>]| -> Result<Vec<u8>> {
+            let proof = params
+                .build_block_db_proof(state_circuits.get_recursive_circuit_set(), state_proofs)?;
+            // the IVC proof is serialized without its verifier data for query circuits
+            let proof = deserialize_proof(&proof)?;
+            Ok(ProofWithVK::from_parts(proof, params.get_block_db_vk().clone()).serialize()?)
+        };
+
+        // IVC proofs start from an empty block db, so the proof over the last block doesn't
+        // start from the root reached by the proof over the previous ones, even if their ranges
+        // of blocks are consecutive
+        let earlier = ivc_proof(&state_proofs[..2])?;
+        let later = ivc_proof(&state_proofs[2..])?;
+        params.verify_proof(&earlier)?;
+        params.verify_proof(&later)?;
+        for (earlier, later) in [(&earlier, &later), (&later, &earlier)] {
+            let err = params.merge_proofs(earlier, later).unwrap_err().to_string();
+            assert!(
+                err.contains("doesn't start from the last root"),
+                "unexpected error: {err}"
+            );
+        }
+        assert!(params.merge_proofs(&earlier, &earlier[1..]).is_err());
+
+        // dummy proofs over consecutive ranges pass the checks done before proving, but the
+        // merge circuit doesn't accept them
+        let parent_hash = PublicInputs::from_block_hash_bytes(&thread_rng().gen());
+        let dummy = params
+            .generate_dummy_proof(F::from_canonical_u32(first_block), parent_hash)?
+            .serialize()?;
+        check_panic!(
+            || params.merge_proofs(&dummy, &dummy),
+            "dummy block db proofs were merged"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_required_proofs_for_range() -> Result<()> {
+        const MAX_DEPTH: usize = 4;
+        let db_root = random_vector::<u8>(HASH_LEN).try_into().unwrap();
+        let (min_block, max_block) = (1000, 1010);
+
+        // a single reference covers the whole range
+        let block_ref = required_proofs_for_range::<MAX_DEPTH>(db_root, min_block, max_block)?;
+        assert_eq!(
+            block_ref,
+            Some(BlockRef {
+                db_root,
+                blocks: min_block..=max_block,
+            })
+        );
+
+        assert_eq!(
+            required_proofs_for_range::<MAX_DEPTH>(db_root, 5, 5)?.map(|r| r.blocks),
+            Some(5..=5)
+        );
+        assert_eq!(required_proofs_for_range::<MAX_DEPTH>(db_root, 6, 5)?, None);
+
+        // the range can't have more blocks than the block db
+        assert!(required_proofs_for_range::<MAX_DEPTH>(db_root, 0, 15)?.is_some());
+        assert!(required_proofs_for_range::<MAX_DEPTH>(db_root, 0, 16).is_err());
+        assert!(required_proofs_for_range::<MAX_DEPTH>(db_root, 0, u32::MAX).is_err());
+        Ok(())
+    }
 }
@@ -1,6 +1,9 @@
-use crate::{keccak::OutputHash, keccak::PACKED_HASH_LEN};
+use crate::{
+    keccak::{pack_hash_le, OutputHash, PACKED_HASH_LEN},
+    types::HashOutput,
+};
 use plonky2::{
-    field::{extension::Extendable, goldilocks_field::GoldilocksField},
+    field::{extension::Extendable, goldilocks_field::GoldilocksField, types::Field},
     hash::hash_types::{HashOutTarget, RichField, NUM_HASH_OUT_ELTS},
     iop::target::Target,
     plonk::circuit_builder::CircuitBuilder,
@@ -117,4 +120,11 @@ impl PublicInputs<'_, GoldilocksField> {
         arr[Self::H_IDX..].copy_from_slice(last_block_hash);
         arr
     }
+
+    /// Pack the bytes of a block hash into the u32 limbs exposed as public input, the same way
+    /// the block header hash is packed in the circuit, i.e., each limb is built from 4 bytes
+    /// in little-endian order, starting from the first bytes of the hash (see `pack_hash_le`)
+    pub fn from_block_hash_bytes(block_hash: &HashOutput) -> [GoldilocksField; PACKED_HASH_LEN] {
+        pack_hash_le(block_hash).map(GoldilocksField::from_canonical_u32)
+    }
 }
@@ -40,11 +40,14 @@ impl<const BLOCK_DB_DEPTH: usize, const L: usize> PublicParameters<BLOCK_DB_DEPT
         let block = block::Parameters::build(&state);
         let block_db_info =
             BlockDBCircuitInfo::<BLOCK_DB_DEPTH>::deserialize(block_db_circuit_info)?;
-        let revelation = revelation::Parameters::build(
+        // merged block db proofs are accepted too
+        let revelation = revelation::ParametersBuilder::new(
             block.get_block_circuit_set(),
             block_db_info.get_block_db_circuit_set(),
             block_db_info.get_block_db_vk(),
-        );
+        )
+        .with_block_db_verifier_datas(&block_db_info.get_block_db_vks())
+        .build_for()?;
         Ok(Self {
             storage,
             state,
@@ -7,7 +7,7 @@ use plonky2::{
         goldilocks_field::GoldilocksField,
         types::Field,
     },
-    hash::hash_types::{HashOut, HashOutTarget, NUM_HASH_OUT_ELTS},
+    hash::hash_types::{HashOut, HashOutTarget},
     iop::target::Target,
     plonk::{circuit_builder::CircuitBuilder, config::GenericHashOut},
 };
@@ -25,8 +25,7 @@ use serde::{Deserialize, Serialize};
 use crate::{
     api::{default_config, ProofWithVK, C, D, F},
     types::{
-        HashOutput, PackedAddressTarget, PackedValueTarget, CURVE_TARGET_LEN, PACKED_ADDRESS_LEN,
-        PACKED_VALUE_LEN,
+        HashOutput, PackedAddressTarget, PackedValueTarget, PACKED_ADDRESS_LEN, PACKED_VALUE_LEN,
     },
     utils::{convert_point_to_curve_target, convert_slice_to_curve_point},
 };
@@ -112,7 +111,7 @@ impl Parameters {
     }
 
     pub fn generate_proof(&self, input: CircuitInput) -> Result<Vec<u8>> {
-        match input {
+        let proof = match input {
             CircuitInput::FullNode((left_proof, right_proof)) => {
                 let (left_proof, left_vd) = left_proof.into();
                 let (right_proof, right_vd) = right_proof.into();
@@ -144,8 +143,8 @@ impl Parameters {
                         .clone(),
                 ))
             }
-        }
-        .serialize()
+        };
+        Ok(proof.serialize()?)
     }
 
     pub(crate) fn verify_proof(&self, proof: &[u8]) -> Result<()> {
@@ -170,65 +169,9 @@ impl Parameters {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-#[repr(u8)]
-pub enum Inputs {
-    /// B - block number of the latest block aggregated
-    BlockNumber,
-    /// R - aggregated range
-    Range,
-    /// C - Merkle hash of the subtree, or poseidon hash of the leaf
-    Root,
-    /// A - SMC address in compact packed u32
-    SmartContractAddress,
-    /// X - onwer's address - treated as generic 32byte value, packed in u32
-    UserAddress,
-    /// M - mapping slot
-    MappingSlot,
-    /// S - storage slot length
-    StorageSlotLength,
-    /// D - aggregated digest
-    Digest,
-}
-const NUM_ELEMENTS: usize = 8;
-impl Inputs {
-    const SIZES: [usize; NUM_ELEMENTS] = [
-        1,
-        1,
-        NUM_HASH_OUT_ELTS,
-        PackedAddressTarget::LEN,
-        PACKED_VALUE_LEN,
-        1,
-        1,
-        CURVE_TARGET_LEN,
-    ];
-
-    const fn total_len() -> usize {
-        Self::SIZES[0]
-            + Self::SIZES[1]
-            + Self::SIZES[2]
-            + Self::SIZES[3]
-            + Self::SIZES[4]
-            + Self::SIZES[5]
-            + Self::SIZES[6]
-            + Self::SIZES[7]
-    }
-
-    pub const fn len(&self) -> usize {
-        let me = *self as u8;
-        Self::SIZES[me as usize]
-    }
-
-    fn range(&self) -> std::ops::Range<usize> {
-        let mut offset = 0;
-        let me = *self as u8;
-        for i in 0..me {
-            offset += Self::SIZES[i as usize];
-        }
-
-        offset..offset + Self::SIZES[me as usize]
-    }
-}
+/// Regions of the public inputs exposed by query2/block proofs; the layout is shared with the
+/// decoder of `mrp2_public_inputs`, which doesn't depend on the proving stack
+pub use mrp2_public_inputs::block::Region as Inputs;
 
 /// On top of the habitual T
 #[derive(Clone)]
@@ -281,7 +224,7 @@ impl<'a, T: Clone + Copy> BlockPublicInputs<'a, T> {
     }
 
     pub(crate) fn storage_slot_length_raw(&self) -> &[T] {
-        &self.inputs[Inputs::StorageSlotLength.range()]
+        &self.inputs[Inputs::MappingSlotLength.range()]
     }
 
     fn digest_raw(
@@ -295,7 +238,7 @@ impl<'a, T: Clone + Copy> BlockPublicInputs<'a, T> {
     }
 
     pub(crate) const fn total_len() -> usize {
-        Inputs::total_len()
+        mrp2_public_inputs::block::NUM_PUBLIC_INPUTS
     }
 }
 
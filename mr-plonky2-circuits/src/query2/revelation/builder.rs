@@ -0,0 +1,124 @@
+//! Builder of revelation `Parameters` with non-default settings, possibly for several `L`
+//! sharing the same query2/block and block db circuit sets
+use anyhow::Result;
+use plonky2::{
+    hash::poseidon::PoseidonHash,
+    plonk::{
+        circuit_data::{CircuitConfig, VerifierOnlyCircuitData},
+        config::Hasher,
+    },
+};
+use recursion_framework::framework::RecursiveCircuits;
+
+use crate::api::{default_config, C, D, F};
+
+use super::{
+    catch_panics, BlockRangeVisibility, CommitmentHash, Parameters, QueryCommitment, SaltPolicy,
+};
+
+/// Inputs of the revelation `Parameters` which don't depend on `L`, taken once and then
+/// employed to build the parameters for any `L` with `build_for`
+#[derive(Clone)]
+pub struct ParametersBuilder<const BLOCK_DB_DEPTH: usize> {
+    query2_block_set: RecursiveCircuits<F, C, D>,
+    block_db_circuit_set: RecursiveCircuits<F, C, D>,
+    block_db_verifier_datas: Vec<VerifierOnlyCircuitData<C, D>>,
+    commitment_hash: CommitmentHash,
+    salt_policy: SaltPolicy,
+    query_commitment: QueryCommitment,
+    block_range_visibility: BlockRangeVisibility,
+    config: CircuitConfig,
+}
+
+impl<const BLOCK_DB_DEPTH: usize> ParametersBuilder<BLOCK_DB_DEPTH>
+where
+    [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+{
+    /// Arguments are the ones of `Parameters::build`; the other settings are the default ones,
+    /// and they can be changed with the `with_*` methods
+    pub fn new(
+        query2_block_set: &RecursiveCircuits<F, C, D>,
+        block_db_circuit_set: &RecursiveCircuits<F, C, D>,
+        block_db_verifier_data: &VerifierOnlyCircuitData<C, D>,
+    ) -> Self {
+        Self {
+            query2_block_set: query2_block_set.clone(),
+            block_db_circuit_set: block_db_circuit_set.clone(),
+            block_db_verifier_datas: vec![block_db_verifier_data.clone()],
+            commitment_hash: CommitmentHash::default(),
+            salt_policy: SaltPolicy::default(),
+            query_commitment: QueryCommitment::default(),
+            block_range_visibility: BlockRangeVisibility::default(),
+            config: default_config(),
+        }
+    }
+
+    /// Accept block db proofs generated by any of the circuits with the given verifier datas,
+    /// in place of the one provided to `new`, e.g., proofs generated by either the old or the
+    /// new version of the block db circuit while it is being upgraded
+    pub fn with_block_db_verifier_datas(
+        mut self,
+        block_db_verifier_datas: &[VerifierOnlyCircuitData<C, D>],
+    ) -> Self {
+        self.block_db_verifier_datas = block_db_verifier_datas.to_vec();
+        self
+    }
+
+    /// Set the hash function employed to compute the commitments exposed by the proofs, which is
+    /// Poseidon by default
+    pub fn with_commitment_hash(mut self, commitment_hash: CommitmentHash) -> Self {
+        self.commitment_hash = commitment_hash;
+        self
+    }
+
+    /// Set whether a salt is mixed in the commitment to the revealed keys, and possibly
+    /// revealed
+    pub fn with_salt_policy(mut self, salt_policy: SaltPolicy) -> Self {
+        self.salt_policy = salt_policy;
+        self
+    }
+
+    /// Set whether the parameters of the query are exposed one by one or only through a
+    /// commitment, computed with the commitment hash
+    pub fn with_query_commitment(mut self, query_commitment: QueryCommitment) -> Self {
+        self.query_commitment = query_commitment;
+        self
+    }
+
+    /// Set whether the block range of the query is revealed or hidden behind a commitment; the
+    /// block range can't be hidden if the parameters of the query are committed to, as their
+    /// commitment is not blinded, so `build_for` fails in this case
+    pub fn with_block_range_visibility(
+        mut self,
+        block_range_visibility: BlockRangeVisibility,
+    ) -> Self {
+        self.block_range_visibility = block_range_visibility;
+        self
+    }
+
+    /// Set the configuration of the revelation circuit, which must be the one of the circuits in
+    /// the query2/block and block db circuit sets, as their proofs are verified with it; it is
+    /// `default_config` by default. Circuits built with a configuration below
+    /// `MIN_SECURITY_BITS`, like `test_config`, are fit only for tests, and Groth16 assets can't
+    /// be generated for them
+    pub fn with_config(mut self, config: CircuitConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Build the revelation parameters for `L`
+    pub fn build_for<const L: usize>(&self) -> Result<Parameters<BLOCK_DB_DEPTH, L>> {
+        catch_panics(|| {
+            Parameters::build_internal(
+                &self.query2_block_set,
+                &self.block_db_circuit_set,
+                &self.block_db_verifier_datas,
+                self.commitment_hash,
+                self.salt_policy,
+                self.query_commitment,
+                self.block_range_visibility,
+                self.config.clone(),
+            )
+        })
+    }
+}
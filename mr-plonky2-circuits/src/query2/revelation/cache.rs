@@ -0,0 +1,209 @@
+//! Cache of the outcomes of the verification of revelation proofs, employed to avoid
+//! re-verifying the same proof over and over (e.g., across retries)
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fmt,
+    sync::Arc,
+};
+
+use anyhow::Result;
+use plonky2::{field::types::PrimeField64, hash::hash_types::HashOut};
+
+use crate::{api::F, utils::keccak256};
+
+type CacheKey = Vec<u8>;
+
+/// Failed verification served from a `VerificationCache`, wrapping the error raised when the
+/// proof was first verified, which is reachable as the source of this error
+#[derive(Clone, Debug)]
+pub struct CachedVerificationError(Arc<anyhow::Error>);
+
+impl CachedVerificationError {
+    /// Error raised when the proof was first verified
+    pub fn error(&self) -> &anyhow::Error {
+        &self.0
+    }
+}
+
+impl fmt::Display for CachedVerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for CachedVerificationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&**self.0)
+    }
+}
+
+/// Bounded LRU cache of the outcomes of proof verification. Each outcome is identified by
+/// the hash of the serialized proof and of the digest of the circuit the proof is verified
+/// against, so a cache can be shared among verifiers of different circuits.
+pub struct VerificationCache {
+    capacity: usize,
+    /// Outcomes of the verifications, where the error is stored for failed ones
+    outcomes: HashMap<CacheKey, Result<(), CachedVerificationError>>,
+    /// Keys of the cached outcomes, from the least to the most recently used
+    lru: VecDeque<CacheKey>,
+    /// Number of verifications served from the cache
+    hits: usize,
+}
+
+impl VerificationCache {
+    /// Instantiate a cache storing at most `capacity` outcomes
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            outcomes: HashMap::with_capacity(capacity),
+            lru: VecDeque::with_capacity(capacity),
+            hits: 0,
+        }
+    }
+
+    /// Number of verifications served from the cache so far
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Number of outcomes currently stored in the cache
+    pub fn len(&self) -> usize {
+        self.outcomes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outcomes.is_empty()
+    }
+
+    /// Return the cached outcome of the verification of `proof` for the circuit with digest
+    /// `circuit_digest`, if any; otherwise, `verify` the proof and cache its outcome
+    pub(crate) fn verify_with<V: FnOnce() -> Result<()>>(
+        &mut self,
+        proof: &[u8],
+        circuit_digest: &HashOut<F>,
+        verify: V,
+    ) -> Result<()> {
+        let key = Self::cache_key(proof, circuit_digest);
+        if let Some(outcome) = self.outcomes.get(&key) {
+            let outcome = outcome.clone().map_err(anyhow::Error::new);
+            self.hits += 1;
+            self.touch(&key);
+            return outcome;
+        }
+        let outcome = verify().map_err(|e| CachedVerificationError(Arc::new(e)));
+        if self.capacity > 0 {
+            if self.outcomes.len() == self.capacity {
+                let evicted = self.lru.pop_front().unwrap();
+                self.outcomes.remove(&evicted);
+            }
+            self.outcomes.insert(key.clone(), outcome.clone());
+            self.lru.push_back(key);
+        }
+        outcome.map_err(anyhow::Error::new)
+    }
+
+    fn cache_key(proof: &[u8], circuit_digest: &HashOut<F>) -> CacheKey {
+        let data = proof
+            .iter()
+            .cloned()
+            .chain(
+                circuit_digest
+                    .elements
+                    .iter()
+                    .flat_map(|f| f.to_canonical_u64().to_le_bytes()),
+            )
+            .collect::<Vec<_>>();
+        keccak256(&data)
+    }
+
+    /// Mark `key` as the most recently used one
+    fn touch(&mut self, key: &CacheKey) {
+        if let Some(pos) = self.lru.iter().position(|k| k == key) {
+            let key = self.lru.remove(pos).unwrap();
+            self.lru.push_back(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::bail;
+    use plonky2::field::types::Sample;
+
+    use super::*;
+    use anyhow::anyhow;
+
+    use crate::api::ApiError;
+
+    #[test]
+    fn test_verification_cache() {
+        let mut cache = VerificationCache::new(2);
+        let digest = HashOut::<F>::rand();
+        let mut num_verifications = 0;
+        let mut verify = |proof: &[u8], cache: &mut VerificationCache| {
+            cache.verify_with(proof, &digest, || {
+                num_verifications += 1;
+                if proof[0] == 0 {
+                    bail!("invalid proof")
+                }
+                Ok(())
+            })
+        };
+
+        // second verification of the same proof is served from the cache
+        assert!(verify(&[1, 2, 3], &mut cache).is_ok());
+        assert!(verify(&[1, 2, 3], &mut cache).is_ok());
+        // failed verification is cached with the same error
+        let err = verify(&[0, 2, 3], &mut cache).unwrap_err().to_string();
+        assert_eq!(verify(&[0, 2, 3], &mut cache).unwrap_err().to_string(), err);
+        assert_eq!(num_verifications, 2);
+        assert_eq!(cache.hits(), 2);
+        assert_eq!(cache.len(), 2);
+
+        // the same proof for another circuit is not served from the cache
+        let other_digest = HashOut::<F>::rand();
+        let mut verified = false;
+        cache
+            .verify_with(&[1, 2, 3], &other_digest, || {
+                verified = true;
+                Ok(())
+            })
+            .unwrap();
+        assert!(verified);
+        assert_eq!(cache.len(), 2);
+        // the least recently used outcome, the one for [1, 2, 3], has been evicted
+        let mut verified = false;
+        cache
+            .verify_with(&[1, 2, 3], &digest, || {
+                verified = true;
+                Ok(())
+            })
+            .unwrap();
+        assert!(verified);
+        assert_eq!(cache.hits(), 2);
+    }
+
+    #[test]
+    fn test_verification_cache_error_source() {
+        let mut cache = VerificationCache::new(1);
+        let digest = HashOut::<F>::rand();
+        for _ in 0..2 {
+            let err = cache
+                .verify_with(&[0], &digest, || {
+                    Err(ApiError::VerificationFailed(anyhow!("invalid proof")).into())
+                })
+                .unwrap_err();
+            // the typed error raised by the verification is preserved, also when the outcome
+            // is served from the cache
+            let source = err
+                .chain()
+                .find_map(|e| e.downcast_ref::<ApiError>())
+                .unwrap();
+            assert!(
+                matches!(source, ApiError::VerificationFailed(err) if err.to_string() == "invalid proof")
+            );
+        }
+        assert_eq!(cache.hits(), 1);
+    }
+}
@@ -1,28 +1,51 @@
-use std::array::from_fn as create_array;
+use std::{array::from_fn as create_array, iter::once};
 
+use anyhow::{bail, ensure, Result};
 use itertools::Itertools;
 use plonky2::{
-    field::{goldilocks_field::GoldilocksField, types::Field},
-    hash::hash_types::HashOutTarget,
+    field::{
+        goldilocks_field::GoldilocksField,
+        types::{Field, PrimeField64},
+    },
+    hash::{
+        hash_types::{HashOut, HashOutTarget, NUM_HASH_OUT_ELTS},
+        hashing::PlonkyPermutation,
+        poseidon::{PoseidonHash, PoseidonPermutation},
+    },
     iop::{
-        target::Target,
+        target::{BoolTarget, Target},
         witness::{PartialWitness, WitnessWrite},
     },
-    plonk::circuit_builder::CircuitBuilder,
+    plonk::{circuit_builder::CircuitBuilder, config::Hasher},
+};
+use plonky2_crypto::{
+    biguint::BigUintTarget,
+    hash::{
+        keccak256::{CircuitBuilderHashKeccak, KECCAK256_R},
+        HashInputTarget,
+    },
+    u32::arithmetic_u32::U32Target,
 };
-use plonky2_ecgfp5::gadgets::curve::CircuitBuilderEcGFp5;
+use plonky2_ecgfp5::gadgets::curve::{CircuitBuilderEcGFp5, CurveTarget};
 
+use ethers::types::Address;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     block::{empty_merkle_root, public_inputs::PublicInputs as BlockDBPublicInputs},
     group_hashing::CircuitBuilderGroupHashing,
+    keccak::{compute_padding_size, compute_size_with_padding, HASH_LEN, PACKED_HASH_LEN},
     query2::block::BlockPublicInputs as BlockQueryPublicInputs,
-    types::{PackedMappingKeyTarget, PACKED_MAPPING_KEY_LEN},
-    utils::{greater_than_or_equal_to, less_than, less_than_or_equal_to},
+    types::{PackedAddressTarget, PackedMappingKeyTarget, PACKED_MAPPING_KEY_LEN},
+    utils::{
+        convert_u8_slice_to_u32_fields, convert_u8_to_u32_slice, greater_than_or_equal_to,
+        keccak256, less_than, less_than_or_equal_to, Packer,
+    },
 };
 
-use super::RevelationPublicInputs;
+use super::{
+    pack_mapping_keys, pad_packed_keys, sort_packed_keys, RevelationError, RevelationPublicInputs,
+};
 
 #[derive(Serialize, Deserialize)]
 pub(crate) struct RevelationWires<const L: usize> {
@@ -32,6 +55,14 @@ pub(crate) struct RevelationWires<const L: usize> {
     pub num_entries: Target,
     pub min_block_number: Target,
     pub max_block_number: Target,
+    pub page_offset: Target,
+    pub page_limit: Target,
+    /// Salt mixed in the result commitment, only for circuits built with a salt
+    pub salt: Option<Target>,
+    /// Blinding factor of the range commitment, only for circuits hiding the block range
+    pub range_blinding: Option<Target>,
+    /// Address of the client allowed to consume the proof
+    pub client_address: PackedAddressTarget,
 }
 
 #[derive(Clone, Debug)]
@@ -40,14 +71,119 @@ pub struct RevelationCircuit<const L: usize> {
     pub(crate) num_entries: u8,
     pub(crate) query_min_block_number: usize,
     pub(crate) query_max_block_number: usize,
+    /// Window `(offset, limit)` of the sorted keys to be revealed; all the keys
+    /// are revealed if no window is specified
+    pub(crate) page: Option<(u8, u8)>,
+    /// Salt mixed in the result commitment, so that the commitments to the same keys
+    /// computed by different queries can't be correlated; it must be provided if and only
+    /// if the circuit is built with a salt
+    pub(crate) salt: Option<GoldilocksField>,
+    /// Blinding factor of the commitment to the block range, so that the range can't be
+    /// recovered by brute force from the commitment; it must be provided if and only if the
+    /// circuit is built to hide the block range
+    pub(crate) range_blinding: Option<GoldilocksField>,
+    /// Address of the client allowed to consume the proof, which is only exposed in the
+    /// public inputs; it is the zero address if the proof is not bound to any client
+    pub(crate) client_address: Address,
 }
 impl<const L: usize> RevelationCircuit<L> {
+    /// Values assigned to the revelation circuit logic for the given mapping keys and query
+    /// range, the same way as `RevelationRecursiveInput::new` does; they are employed to
+    /// generate proofs with a `RevelationProver`
+    pub fn new(
+        mapping_keys: Vec<Vec<u8>>,
+        query_min_block: usize,
+        query_max_block: usize,
+    ) -> Result<Self> {
+        let (inputs, _) = Self::from_packed_keys(
+            &pack_mapping_keys(&mapping_keys)?,
+            query_min_block,
+            query_max_block,
+        )?;
+        Ok(inputs)
+    }
+
+    /// Same as `new`, but with mapping keys already packed; the number of duplicated keys
+    /// dropped is returned as well
+    pub(super) fn from_packed_keys(
+        packed_keys: &[[u32; PACKED_MAPPING_KEY_LEN]],
+        query_min_block: usize,
+        query_max_block: usize,
+    ) -> Result<(Self, usize)> {
+        ensure!(
+            query_min_block <= query_max_block,
+            "query block range is inverted: min block {} is greater than max block {}",
+            query_min_block,
+            query_max_block
+        );
+        let sorted_keys = sort_packed_keys(packed_keys);
+        // the number of entries is the number of distinct keys placed in the witness
+        let num_entries = sorted_keys.len();
+        let num_duplicated_keys = packed_keys.len() - num_entries;
+        if num_entries > L {
+            return Err(RevelationError::TooManyEntries {
+                got: num_entries,
+                max: L,
+            }
+            .into());
+        }
+        let inputs = RevelationCircuit {
+            packed_keys: pad_packed_keys(&sorted_keys),
+            num_entries: num_entries as u8,
+            query_min_block_number: query_min_block,
+            query_max_block_number: query_max_block,
+            page: None,
+            salt: None,
+            range_blinding: None,
+            client_address: Address::zero(),
+        };
+        Ok((inputs, num_duplicated_keys))
+    }
+
     pub fn build<const MAX_DEPTH: usize>(
         b: &mut CircuitBuilder<GoldilocksField, 2>,
         db_proof: BlockDBPublicInputs<Target>,
         root_proof: BlockQueryPublicInputs<Target>,
+        commitment_hash: CommitmentHash,
+        salt_policy: SaltPolicy,
+        query_commitment: QueryCommitment,
+        block_range_visibility: BlockRangeVisibility,
     ) -> RevelationWires<L> {
+        Self::build_multi::<MAX_DEPTH>(
+            b,
+            db_proof,
+            std::slice::from_ref(&root_proof),
+            commitment_hash,
+            salt_policy,
+            query_commitment,
+            block_range_visibility,
+        )
+        .expect("a single query2/block proof is always aggregated")
+    }
+
+    /// Same as `build`, but the revealed keys are the ones aggregated by all the `root_proofs`,
+    /// which must answer the same query over consecutive ranges of blocks, sorted in increasing
+    /// order; the circuit is the same as the one built by `build` for a single proof. An error
+    /// is returned if no proof is provided
+    pub fn build_multi<const MAX_DEPTH: usize>(
+        b: &mut CircuitBuilder<GoldilocksField, 2>,
+        db_proof: BlockDBPublicInputs<Target>,
+        root_proofs: &[BlockQueryPublicInputs<Target>],
+        commitment_hash: CommitmentHash,
+        salt_policy: SaltPolicy,
+        query_commitment: QueryCommitment,
+        block_range_visibility: BlockRangeVisibility,
+    ) -> Result<RevelationWires<L>> {
+        ensure!(
+            !root_proofs.is_empty(),
+            "at least one query2/block proof must be aggregated"
+        );
         let t = b._true();
+        // the parameters of the query are taken from the first proof, as they are checked to
+        // be the same in all the proofs
+        let root_proof = &root_proofs[0];
+        let (query_block_number, query_range, query_digest) =
+            aggregate_query_proofs(b, root_proofs);
         // Create the empty root constant matching the given MAX_DEPTH of the Poseidon storage tree
         let empty_root = HashOutTarget::from_vec(
             empty_merkle_root::<GoldilocksField, 2, MAX_DEPTH>()
@@ -71,36 +207,105 @@ impl<const L: usize> RevelationCircuit<L> {
         // number of entries (i.e. we don't enforce the LIMIT/OFFSET SQL ops yet), it doesn't
         // introduce any additional assumption in the circuit.
         let num_entries = b.add_virtual_target();
+        // The number of entries can't exceed the number of slots, so that the padding slots,
+        // i.e. the ones beyond num_entries, are exactly the ones revealed as 0
+        b.range_check(num_entries, 8);
+        let max_entries = b.constant(GoldilocksField::from_canonical_usize(L));
+        let num_entries_in_range = less_than_or_equal_to(b, num_entries, max_entries, 8);
+        b.connect(num_entries_in_range.target, t.target);
 
         let min_block_number = b.add_virtual_target();
         let max_block_number = b.add_virtual_target();
+        // The window of entries being revealed, i.e. the entries with index in
+        // [page_offset, page_offset + page_limit), while the other ones are replaced by 0
+        let page_offset = b.add_virtual_target();
+        let page_limit = b.add_virtual_target();
+        b.range_check(page_offset, 8);
+        b.range_check(page_limit, 8);
+        // the client address is only exposed, its limbs are checked to be u32 so that it can
+        // be unpacked unambiguously
+        let client_address = PackedAddressTarget::new(b);
+        client_address
+            .arr
+            .iter()
+            .for_each(|limb| b.range_check(limb.0, 32));
+        let page_end = b.add(page_offset, page_limit);
+        let mut revealed_ids = Vec::with_capacity(L);
 
         let p0 = b.curve_zero();
+        let zero = b.zero();
         let mut digests = Vec::with_capacity(L);
+        // the result commitment is computed over the number of entries and the included keys,
+        // replacing the keys not included with zeros
+        let mut commitment_inputs = vec![num_entries];
+        // the keys commitment is the Poseidon hash of the included keys only: each packed key
+        // fills exactly the rate of the sponge, so the permutation absorbing a key is kept
+        // only if the key is included, which yields the same hash as `hash_n_to_hash_no_pad`
+        // over the included keys
+        let mut keys_state = PoseidonPermutation::new(std::iter::repeat(zero));
         for i in 0..L {
-            let p = b.map_to_curve_point(&packed_ids[i].to_targets().arr);
+            let packed_id = packed_ids[i].to_targets().arr;
+            let p = b.map_to_curve_point(&packed_id);
             let it = b.constant(GoldilocksField::from_canonical_usize(i));
             let should_be_included = less_than(b, it, num_entries, 8);
+            commitment_inputs.extend(
+                packed_id
+                    .iter()
+                    .map(|limb| b.select(should_be_included, *limb, zero)),
+            );
             // also check if values are unique, i.e. we expect values in sorted order so we just check
-            // diff is positive.
+            // diff is positive; the whole keys are compared, so that keys sharing the NFT ID limb
+            // are ordered by their higher limbs
             if i > 0 {
-                let previous = nft_ids[i - 1];
-                let curr = nft_ids[i];
-                let ordered = less_than(b, previous.0, curr.0, 32);
+                let previous = packed_ids[i - 1].to_targets().arr;
+                let ordered = packed_key_less_than(b, &previous, &packed_id);
                 let should_be_ordered = b.select(should_be_included, ordered.target, t.target);
                 b.connect(should_be_ordered, t.target);
             }
+            // padding slots must hold the empty key, so that a prover can't fill them with
+            // repetitions of the real keys
+            let is_padding = b.not(should_be_included);
+            for limb in packed_id.iter() {
+                let padding_limb = b.mul(is_padding.target, *limb);
+                b.connect(padding_limb, zero);
+            }
             digests.push(b.curve_select(should_be_included, p, p0));
+            let mut absorbed = keys_state;
+            absorbed.set_from_slice(&packed_id, 0);
+            let absorbed = b.permute::<PoseidonHash>(absorbed);
+            keys_state = PoseidonPermutation::new(
+                absorbed
+                    .as_ref()
+                    .iter()
+                    .zip(keys_state.as_ref())
+                    .map(|(new, old)| b.select(should_be_included, *new, *old))
+                    .collect_vec(),
+            );
+            let after_offset = greater_than_or_equal_to(b, it, page_offset, 9);
+            let before_end = less_than(b, it, page_end, 9);
+            let in_page = b.and(after_offset, before_end);
+            let should_be_revealed = b.and(in_page, should_be_included);
+            revealed_ids.push(U32Target(b.select(should_be_revealed, nft_ids[i].0, zero)));
         }
+        let revealed_ids: [U32Target; L] = revealed_ids.try_into().unwrap();
+        let keys_commitment =
+            HashOutTarget::from_vec(keys_state.squeeze()[..NUM_HASH_OUT_ELTS].to_vec());
         let d = b.add_curve_point(&digests);
+        let salt = (salt_policy != SaltPolicy::Unsalted).then(|| b.add_virtual_target());
+        let result_commitment = commitment_hash.build_commitment(b, commitment_inputs, salt);
+        // the salt is revealed only if the policy allows it, otherwise zero is exposed
+        let exposed_salt = match (salt_policy, salt) {
+            (SaltPolicy::Exposed, Some(salt)) => salt,
+            _ => zero,
+        };
 
         // Assert the digest computed corresponds to all the nft ids aggregated up to now
-        b.connect_curve_points(d, root_proof.digest());
+        b.connect_curve_points(d, query_digest);
         // Assert the roots of the query and the block db are the same
         b.connect_hashes(root_proof.root(), db_proof.root());
         b.connect_hashes(db_proof.init_root(), empty_root);
 
-        let min_bound = b.sub(root_proof.block_number(), root_proof.range());
+        let min_bound = b.sub(query_block_number, query_range);
 
         // Comment from tests:
         // query_min >= min_block during aggregation
@@ -117,9 +322,15 @@ impl<const L: usize> RevelationCircuit<L> {
         let t = b._true();
         // TODO: check the bit count, 32 ought to be enough?
         let correct_min = greater_than_or_equal_to(b, min_bound_plus_1, min_block_number, 32);
-        let correct_max = less_than_or_equal_to(b, root_proof.block_number(), max_block_number, 32);
+        let correct_max = less_than_or_equal_to(b, query_block_number, max_block_number, 32);
         b.connect(correct_min.target, t.target);
         b.connect(correct_max.target, t.target);
+        // Assert the queried range is contained in the range of blocks of the block db
+        let in_db_min =
+            greater_than_or_equal_to(b, min_block_number, db_proof.first_block_number().0, 32);
+        let in_db_max = less_than_or_equal_to(b, max_block_number, db_proof.block_number().0, 32);
+        b.connect(in_db_min.target, t.target);
+        b.connect(in_db_max.target, t.target);
 
         // transform the generic mapping value into a packed user address
         // 32 bytes -> 8 u32, 20 bytes -> 5 u32
@@ -129,26 +340,166 @@ impl<const L: usize> RevelationCircuit<L> {
             .user_address()
             .take_last::<GoldilocksField, 2, 5>();
 
+        let smart_contract_address = root_proof.smart_contract_address();
+        let mapping_slot = root_proof.mapping_slot();
+        let mapping_slot_length = root_proof.mapping_slot_length();
+        // when the parameters of the query are committed to, only the commitment is exposed,
+        // while the parameters are left out of the public inputs
+        let exposed_query_commitment = match query_commitment {
+            QueryCommitment::Disabled => [zero; RESULT_COMMITMENT_LEN],
+            QueryCommitment::Enabled => commitment_hash.build_query_commitment(
+                b,
+                min_block_number,
+                max_block_number,
+                &smart_contract_address,
+                &user_address_packed,
+                mapping_slot,
+                mapping_slot_length,
+            ),
+        };
+        // when the block range is hidden, the block numbers of the query are replaced by
+        // zeros and only a blinded commitment to the range is exposed, while they are still
+        // checked against the query2/block proof, which is bound to the block db
+        let range_blinding = (block_range_visibility == BlockRangeVisibility::Committed)
+            .then(|| b.add_virtual_target());
+        let range_commitment = match range_blinding {
+            Some(blinding) => b.hash_n_to_hash_no_pad::<PoseidonHash>(vec![
+                min_block_number,
+                max_block_number,
+                blinding,
+            ]),
+            None => HashOutTarget::from_vec(vec![zero; NUM_HASH_OUT_ELTS]),
+        };
+        let expose_block = |x: Target| match block_range_visibility {
+            BlockRangeVisibility::Public => x,
+            BlockRangeVisibility::Committed => zero,
+        };
+
         RevelationPublicInputs::<Target, L>::register(
             b,
-            root_proof.block_number(),
-            root_proof.range(),
-            min_block_number,
-            max_block_number,
-            &root_proof.smart_contract_address(),
+            expose_block(query_block_number),
+            expose_block(query_range),
+            expose_block(min_block_number),
+            expose_block(max_block_number),
+            &smart_contract_address,
             &user_address_packed,
-            root_proof.mapping_slot(),
-            root_proof.mapping_slot_length(),
-            &nft_ids,
+            mapping_slot,
+            mapping_slot_length,
+            &revealed_ids,
             db_proof.original_block_header(),
+            &result_commitment,
+            num_entries,
+            page_offset,
+            page_limit,
+            commitment_hash,
+            exposed_salt,
+            &exposed_query_commitment,
+            query_commitment,
+            &range_commitment,
+            &db_proof.root(),
+            &client_address,
+            &keys_commitment,
         );
 
-        RevelationWires {
+        Ok(RevelationWires {
             raw_keys: packed_ids,
             num_entries,
             min_block_number,
             max_block_number,
-        }
+            page_offset,
+            page_limit,
+            salt,
+            range_blinding,
+            client_address,
+        })
+    }
+
+    /// Compute the commitment to the revealed keys exposed as public input by the circuit,
+    /// i.e., the Poseidon hash of the number of entries and of the sorted packed keys, where
+    /// the keys beyond the number of entries are replaced by zeros, followed by the salt
+    /// if any
+    pub fn result_commitment(&self) -> HashOut<GoldilocksField> {
+        let inputs = once(self.num_entries as u32)
+            .chain(self.included_packed_keys())
+            .map(GoldilocksField::from_canonical_u32)
+            .chain(self.salt)
+            .collect_vec();
+        PoseidonHash::hash_no_pad(&inputs)
+    }
+
+    /// Compute the commitment to the set of keys matching the query exposed as public input by
+    /// the circuit, i.e., the Poseidon hash of the sorted packed keys, excluding the padding
+    /// ones; it is zero if no key matches the query
+    pub fn keys_commitment(&self) -> HashOut<GoldilocksField> {
+        packed_keys_commitment(&self.packed_keys[..self.num_entries as usize])
+    }
+
+    /// Compute the Keccak commitment to the revealed keys exposed as public input by the
+    /// circuit, i.e., `keccak256(uint32(num_entries) || key_0 || ... || key_{L-1})` where each
+    /// key is encoded in 32 big-endian bytes and the keys beyond the number of entries are
+    /// replaced by zeros, which is cheap to recompute on-chain. The salt, if any, is appended
+    /// to the preimage encoded in 8 little-endian bytes.
+    pub fn keccak_result_commitment(&self) -> [u8; HASH_LEN] {
+        keccak256(&self.keccak_commitment_preimage())
+            .try_into()
+            .unwrap()
+    }
+
+    /// Compute the result commitment, as exposed in the public inputs, for the given hash
+    pub fn expected_result_commitment(
+        &self,
+        commitment_hash: CommitmentHash,
+    ) -> [GoldilocksField; RESULT_COMMITMENT_LEN] {
+        let elements = match commitment_hash {
+            // a Poseidon hash fills only the first elements, the remaining ones are zero
+            CommitmentHash::Poseidon => self
+                .result_commitment()
+                .elements
+                .into_iter()
+                .chain(std::iter::repeat(GoldilocksField::ZERO))
+                .take(RESULT_COMMITMENT_LEN)
+                .collect_vec(),
+            CommitmentHash::Keccak => {
+                convert_u8_slice_to_u32_fields(&self.keccak_result_commitment())
+            }
+        };
+        elements.try_into().unwrap()
+    }
+
+    /// Compute the commitment to the block range exposed as public input by the circuit when
+    /// the range is hidden, i.e., the Poseidon hash of the min and max block numbers of the
+    /// query and of the blinding factor
+    pub fn expected_range_commitment(&self) -> HashOut<GoldilocksField> {
+        let inputs = [self.query_min_block_number, self.query_max_block_number]
+            .into_iter()
+            .map(GoldilocksField::from_canonical_usize)
+            .chain(once(self.range_blinding.unwrap_or(GoldilocksField::ZERO)))
+            .collect_vec();
+        PoseidonHash::hash_no_pad(&inputs)
+    }
+
+    /// Bytes hashed with Keccak to compute the result commitment
+    fn keccak_commitment_preimage(&self) -> Vec<u8> {
+        once(self.num_entries as u32)
+            .map(u32::to_be_bytes)
+            .chain(self.included_packed_keys().map(u32::to_le_bytes))
+            .flatten()
+            .chain(
+                self.salt
+                    .into_iter()
+                    .flat_map(|salt| salt.to_canonical_u64().to_le_bytes()),
+            )
+            .collect()
+    }
+
+    /// Limbs of the packed keys, where the keys beyond the number of entries are replaced
+    /// by zeros
+    fn included_packed_keys(&self) -> impl Iterator<Item = u32> + '_ {
+        self.packed_keys.iter().enumerate().flat_map(|(i, key)| {
+            let is_included = i < self.num_entries as usize;
+            key.iter()
+                .map(move |limb| if is_included { *limb } else { 0 })
+        })
     }
 
     pub fn assign(&self, pw: &mut PartialWitness<GoldilocksField>, wires: &RevelationWires<L>) {
@@ -169,5 +520,320 @@ impl<const L: usize> RevelationCircuit<L> {
             wires.max_block_number,
             GoldilocksField::from_canonical_usize(self.query_max_block_number),
         );
+        let (page_offset, page_limit) = self.page.unwrap_or((0, L as u8));
+        pw.set_target(
+            wires.page_offset,
+            GoldilocksField::from_canonical_u8(page_offset),
+        );
+        pw.set_target(
+            wires.page_limit,
+            GoldilocksField::from_canonical_u8(page_limit),
+        );
+        if let Some(salt) = wires.salt {
+            pw.set_target(salt, self.salt.unwrap_or(GoldilocksField::ZERO));
+        }
+        wires.client_address.assign_from_data(
+            pw,
+            &self
+                .client_address
+                .as_fixed_bytes()
+                .pack()
+                .try_into()
+                .unwrap(),
+        );
+        if let Some(range_blinding) = wires.range_blinding {
+            pw.set_target(
+                range_blinding,
+                self.range_blinding.unwrap_or(GoldilocksField::ZERO),
+            );
+        }
+    }
+}
+
+/// Compute the commitment to the set of keys exposed by the circuit, given the sorted and
+/// distinct packed keys matching the query, i.e., the Poseidon hash of the keys
+pub(crate) fn packed_keys_commitment(
+    sorted_keys: &[[u32; PACKED_MAPPING_KEY_LEN]],
+) -> HashOut<GoldilocksField> {
+    let inputs = sorted_keys
+        .iter()
+        .flatten()
+        .map(|limb| GoldilocksField::from_canonical_u32(*limb))
+        .collect_vec();
+    PoseidonHash::hash_no_pad(&inputs)
+}
+
+/// Aggregate the query2/block proofs `root_proofs`, which must answer the same query over the
+/// same block db, returning the block number, the range and the digest of a single proof
+/// covering all of them. The proofs must cover consecutive ranges of blocks sorted in increasing
+/// order, so that no key is aggregated twice and no block is skipped; no constraint is added for
+/// a single proof.
+fn aggregate_query_proofs(
+    b: &mut CircuitBuilder<GoldilocksField, 2>,
+    root_proofs: &[BlockQueryPublicInputs<Target>],
+) -> (Target, Target, CurveTarget) {
+    let first = &root_proofs[0];
+    let last = root_proofs.last().unwrap();
+    if root_proofs.len() == 1 {
+        return (first.block_number(), first.range(), first.digest());
+    }
+    for (previous, proof) in root_proofs.iter().tuple_windows() {
+        b.connect_hashes(proof.root(), first.root());
+        proof
+            .smart_contract_address()
+            .enforce_equal(b, &first.smart_contract_address());
+        proof.user_address().enforce_equal(b, &first.user_address());
+        b.connect(proof.mapping_slot(), first.mapping_slot());
+        b.connect(proof.mapping_slot_length(), first.mapping_slot_length());
+        // the blocks of a proof are the ones in (block_number - range, block_number], so the
+        // previous proof must end right before the first block of the next one
+        let before_first_block = b.sub(proof.block_number(), proof.range());
+        b.connect(previous.block_number(), before_first_block);
+    }
+    // the aggregated proof spans from the first block of the first proof up to the last block
+    // of the last proof
+    let before_first_block = b.sub(first.block_number(), first.range());
+    let range = b.sub(last.block_number(), before_first_block);
+    let digest = b.add_curve_point(&root_proofs.iter().map(|p| p.digest()).collect_vec());
+    (last.block_number(), range, digest)
+}
+
+/// Number of public inputs employed to expose the result commitment, i.e. enough to expose
+/// a Keccak hash as packed u32 limbs
+pub const RESULT_COMMITMENT_LEN: usize = PACKED_HASH_LEN;
+
+/// Hash function employed to compute the commitment to the keys revealed by the circuit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CommitmentHash {
+    /// Poseidon hash, cheap to compute in circuit
+    #[default]
+    Poseidon,
+    /// Keccak hash, cheap to recompute on-chain
+    Keccak,
+}
+
+impl CommitmentHash {
+    /// Identifier of the hash function exposed in the public inputs
+    pub fn to_field(self) -> GoldilocksField {
+        GoldilocksField::from_canonical_u8(self as u8)
+    }
+
+    /// Return the hash function with the given identifier
+    pub fn from_field(id: GoldilocksField) -> Result<Self> {
+        match id.to_canonical_u64() {
+            0 => Ok(Self::Poseidon),
+            1 => Ok(Self::Keccak),
+            id => bail!("invalid result commitment hash identifier {}", id),
+        }
     }
+
+    /// Compute in circuit the commitment to `inputs`, made of the number of entries followed
+    /// by the limbs of the packed keys, and to the `salt`, if any
+    fn build_commitment(
+        self,
+        b: &mut CircuitBuilder<GoldilocksField, 2>,
+        inputs: Vec<Target>,
+        salt: Option<Target>,
+    ) -> [Target; RESULT_COMMITMENT_LEN] {
+        match self {
+            Self::Poseidon => poseidon_commitment(b, inputs.into_iter().chain(salt).collect()),
+            Self::Keccak => {
+                // The number of entries is encoded in 4 big-endian bytes, that is the u32 limb
+                // packing them in little-endian order is num_entries << 24, while the keys are
+                // already packed in little-endian limbs of big-endian bytes. The salt is encoded
+                // in 8 little-endian bytes, that is its low and high 32 bits.
+                let shift = b.constant(GoldilocksField::from_canonical_u32(1 << 24));
+                let num_entries = b.mul(inputs[0], shift);
+                let salt_limbs = salt.map(|salt| split_canonical_u64(b, salt));
+                let preimage = once(num_entries)
+                    .chain(inputs[1..].iter().copied())
+                    .chain(salt_limbs.into_iter().flatten())
+                    .collect_vec();
+                keccak_commitment(b, preimage)
+            }
+        }
+    }
+
+    /// Compute in circuit the commitment to the parameters of the query, i.e. the min and max
+    /// block numbers, the contract and user addresses and the mapping and length slots
+    fn build_query_commitment(
+        self,
+        b: &mut CircuitBuilder<GoldilocksField, 2>,
+        min_block_number: Target,
+        max_block_number: Target,
+        contract_address: &PackedAddressTarget,
+        user_address: &PackedAddressTarget,
+        mapping_slot: Target,
+        length_slot: Target,
+    ) -> [Target; RESULT_COMMITMENT_LEN] {
+        let address_limbs = |address: &PackedAddressTarget| address.arr.map(|limb| limb.0);
+        match self {
+            Self::Poseidon => {
+                let inputs = [min_block_number, max_block_number]
+                    .into_iter()
+                    .chain(address_limbs(contract_address))
+                    .chain(address_limbs(user_address))
+                    .chain([mapping_slot, length_slot])
+                    .collect();
+                poseidon_commitment(b, inputs)
+            }
+            Self::Keccak => {
+                // The block numbers and the slots are encoded in 8 little-endian bytes, that is
+                // their low and high 32 bits, while the addresses are already packed in
+                // little-endian limbs of their bytes
+                let [min_block_number, max_block_number, mapping_slot, length_slot] = [
+                    min_block_number,
+                    max_block_number,
+                    mapping_slot,
+                    length_slot,
+                ]
+                .map(|x| split_canonical_u64(b, x));
+                let preimage = min_block_number
+                    .into_iter()
+                    .chain(max_block_number)
+                    .chain(address_limbs(contract_address))
+                    .chain(address_limbs(user_address))
+                    .chain(mapping_slot)
+                    .chain(length_slot)
+                    .collect_vec();
+                keccak_commitment(b, preimage)
+            }
+        }
+    }
+}
+
+/// Poseidon hash of `inputs`, padded with zeros to fill the public inputs of a commitment
+fn poseidon_commitment(
+    b: &mut CircuitBuilder<GoldilocksField, 2>,
+    inputs: Vec<Target>,
+) -> [Target; RESULT_COMMITMENT_LEN] {
+    let zero = b.zero();
+    let hash = b.hash_n_to_hash_no_pad::<PoseidonHash>(inputs);
+    create_array(|i| hash.elements.get(i).copied().unwrap_or(zero))
+}
+
+/// Keccak hash of a preimage given as u32 limbs of little-endian bytes, packed in u32 limbs as
+/// well. As the preimage has a fixed length, the padding is a constant.
+fn keccak_commitment(
+    b: &mut CircuitBuilder<GoldilocksField, 2>,
+    preimage: Vec<Target>,
+) -> [Target; RESULT_COMMITMENT_LEN] {
+    let preimage_len = preimage.len() * 4;
+    let padding = keccak_padding(preimage_len);
+    let limbs = preimage
+        .into_iter()
+        .chain(
+            padding
+                .iter()
+                .map(|limb| b.constant(GoldilocksField::from_canonical_u32(*limb))),
+        )
+        .map(U32Target)
+        .collect_vec();
+    // all the blocks are hashed, the first one being always taken by the gadget
+    let num_blocks = compute_size_with_padding(preimage_len) / (KECCAK256_R / 8);
+    let blocks = (1..num_blocks).map(|_| b._true()).collect_vec();
+    let hash = b.hash_keccak256(&HashInputTarget {
+        input: BigUintTarget { limbs },
+        input_bits: 0,
+        blocks,
+    });
+    create_array(|i| hash.limbs[i].0)
+}
+
+/// Whether the parameters of the query are exposed in the public inputs one by one, or only
+/// through a commitment to them, which minimizes the calldata of the on-chain verification as
+/// the contract is then given the parameters to rehash them
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QueryCommitment {
+    /// Each parameter of the query is exposed in its own public inputs, while the query
+    /// commitment is zero
+    #[default]
+    Disabled,
+    /// The parameters of the query are exposed as zeros, and only their commitment, computed
+    /// with the same hash as the result commitment, is exposed
+    Enabled,
+}
+
+/// Policy on the salt mixed in the result commitment, which prevents correlating the
+/// commitments of different queries revealing the same keys
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SaltPolicy {
+    /// No salt is mixed in the result commitment
+    #[default]
+    Unsalted,
+    /// The salt is mixed in the result commitment but not revealed, so only who knows the
+    /// salt can recompute the commitment from the revealed keys
+    Hidden,
+    /// The salt is mixed in the result commitment and revealed in the public inputs
+    Exposed,
+}
+
+/// Whether the block range of the query is revealed in the public inputs, or hidden behind a
+/// commitment, so that a client can prove the result of a query over some range without
+/// revealing which blocks were queried
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlockRangeVisibility {
+    /// The min and max block numbers of the query are exposed, as well as the block number
+    /// and the range of the query2/block proof, while the range commitment is zero
+    #[default]
+    Public,
+    /// The block numbers are exposed as zeros, and only a blinded Poseidon commitment to the
+    /// min and max block numbers of the query is exposed
+    Committed,
+}
+
+/// Split `x` into its low and high 32 bits, enforcing that the decomposition is the canonical
+/// one, i.e. that `high * 2^32 + low` is smaller than the Goldilocks modulus `2^64 - 2^32 + 1`
+fn split_canonical_u64(b: &mut CircuitBuilder<GoldilocksField, 2>, x: Target) -> [Target; 2] {
+    let (low, high) = b.split_low_high(x, 32, 64);
+    // the high bits can be all ones only if the low bits are all zeros
+    let max_high = b.constant(GoldilocksField::from_canonical_u32(u32::MAX));
+    let is_max_high = b.is_equal(high, max_high);
+    let low_if_max_high = b.mul(is_max_high.target, low);
+    let zero = b.zero();
+    b.connect(low_if_max_high, zero);
+    [low, high]
+}
+
+/// Returns true if the packed key `a` is strictly smaller than the packed key `b`, comparing
+/// the u32 limbs from the first one, which is the most significant as keys are left padded,
+/// down to the last one. Limbs pack the big-endian bytes of the keys in little-endian order,
+/// so their bytes are swapped before being compared
+fn packed_key_less_than(
+    b: &mut CircuitBuilder<GoldilocksField, 2>,
+    a: &[Target],
+    other: &[Target],
+) -> BoolTarget {
+    // fold from the least significant limb: a is smaller if its limb is smaller, or if the
+    // limbs are equal and a is smaller on the less significant limbs
+    a.iter()
+        .zip_eq(other)
+        .rev()
+        .fold(b._false(), |smaller, (x, y)| {
+            let (x, y) = (swap_limb_bytes(b, *x), swap_limb_bytes(b, *y));
+            let limb_smaller = less_than(b, x, y, 32);
+            let limb_equal = b.is_equal(x, y);
+            let equal_and_smaller = b.and(limb_equal, smaller);
+            b.or(limb_smaller, equal_and_smaller)
+        })
+}
+
+/// Reverse the order of the bytes of the u32 `limb`, i.e. the circuit version of
+/// `u32::swap_bytes`
+fn swap_limb_bytes(b: &mut CircuitBuilder<GoldilocksField, 2>, limb: Target) -> Target {
+    let zero = b.zero();
+    // the first byte, i.e. the least significant one, becomes the most significant one
+    b.split_le_base::<256>(limb, 4)
+        .into_iter()
+        .fold(zero, |acc, byte| {
+            b.mul_const_add(GoldilocksField::from_canonical_u32(256), acc, byte)
+        })
+}
+
+/// Keccak padding of a preimage of `len` bytes, with `len` multiple of 4, packed in u32 limbs
+fn keccak_padding(len: usize) -> Vec<u32> {
+    let mut padding = vec![0u8; compute_padding_size(len)];
+    padding[0] |= 0x01;
+    *padding.last_mut().unwrap() |= 0x80;
+    convert_u8_to_u32_slice(&padding)
 }
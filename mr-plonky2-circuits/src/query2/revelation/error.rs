@@ -0,0 +1,64 @@
+//! Errors raised by the revelation API
+use std::{any::Any, fmt};
+
+use crate::api::CircuitDigestFingerprint;
+
+/// Errors specific to the revelation circuit, which can be retrieved from the `anyhow::Error`
+/// returned by the API through `downcast_ref`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RevelationError {
+    /// A panic was raised while building the circuit, or while generating or verifying a proof;
+    /// returned only if the `catch_panics` feature is enabled
+    Internal(String),
+    /// More distinct mapping keys were provided than the `max` NFT IDs revealed by the circuit
+    TooManyEntries {
+        /// Number of distinct mapping keys provided
+        got: usize,
+        /// Fixed parameter `L` of the circuit
+        max: usize,
+    },
+    /// The query2/block proof was generated by a circuit which doesn't belong to the set of
+    /// query2/block circuits accepted by the revelation parameters
+    UnknownQuery2Circuit {
+        /// Fingerprint of the digest of the circuit which generated the proof
+        fingerprint: CircuitDigestFingerprint,
+    },
+}
+
+impl fmt::Display for RevelationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Internal(msg) => write!(f, "internal error in revelation API: {}", msg),
+            Self::TooManyEntries { got, max } => write!(
+                f,
+                "Number of entries {} should not exceed fixed parameter L {}",
+                got, max
+            ),
+            Self::UnknownQuery2Circuit { fingerprint } => write!(
+                f,
+                "query2/block proof generated by circuit 0x{} not accepted by the revelation parameters",
+                fingerprint
+                    .0
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<String>()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RevelationError {}
+
+impl RevelationError {
+    /// Build an `Internal` error from the payload of a panic
+    pub(crate) fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let msg = if let Some(msg) = payload.downcast_ref::<&str>() {
+            msg.to_string()
+        } else if let Some(msg) = payload.downcast_ref::<String>() {
+            msg.clone()
+        } else {
+            "unknown panic".to_string()
+        };
+        Self::Internal(msg)
+    }
+}
@@ -1,44 +1,84 @@
-use anyhow::Result;
+use anyhow::{anyhow, ensure, Result};
+use ethers::types::{Address, Bytes};
 use recursion_framework::{
     framework::{
         RecursiveCircuits, RecursiveCircuitsVerifierGagdet, RecursiveCircuitsVerifierTarget,
+        WhitelistedCircuitsVerifierTarget, NUM_CIRCUIT_SET_DIGEST_PUBLIC_INPUTS,
     },
-    serialization::{deserialize, serialize},
+    serialization::{deserialize, deserialize_vec, serialize, serialize_vec, ToBytes},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize, Serializer};
+use std::{
+    array::from_fn as create_array,
+    collections::BTreeSet,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+    str::FromStr,
 };
-use serde::{Deserialize, Serialize};
-use std::{array::from_fn as create_array, collections::BTreeMap};
 
 use plonky2::{
-    hash::poseidon::PoseidonHash,
+    field::types::PrimeField64,
+    hash::{
+        hash_types::{HashOut, NUM_HASH_OUT_ELTS},
+        poseidon::PoseidonHash,
+    },
     iop::{
         target::Target,
         witness::{PartialWitness, WitnessWrite},
     },
     plonk::{
         circuit_builder::CircuitBuilder,
-        circuit_data::{CircuitData, VerifierCircuitData, VerifierOnlyCircuitData},
+        circuit_data::{
+            CircuitConfig, CircuitData, CommonCircuitData, ProverCircuitData, VerifierCircuitData,
+            VerifierOnlyCircuitData,
+        },
         config::Hasher,
-        proof::{ProofWithPublicInputs, ProofWithPublicInputsTarget},
+        proof::ProofWithPublicInputs,
     },
 };
 
 use crate::{
-    api::{default_config, deserialize_proof, serialize_proof, ProofWithVK, C, D, F},
+    api::{
+        circuit_digest_fingerprint, default_config, deserialize_proof, deserialize_proof_expecting,
+        deserialize_proof_from_reader, serialize_proof, vk_fingerprint, CircuitDigestFingerprint,
+        ProofWithVK, C, D, F, VK_FINGERPRINT_LEN,
+    },
     block::{
         Parameters as BlockDbParameters, PublicInputs as BlockDbPublicInputs, NUM_IVC_PUBLIC_INPUTS,
     },
-    eth::left_pad32,
+    eth::try_left_pad32,
     query2::block,
-    types::PACKED_MAPPING_KEY_LEN,
+    types::{MAPPING_KEY_LEN, PACKED_ADDRESS_LEN, PACKED_MAPPING_KEY_LEN},
     utils::Packer,
 };
 
-pub use self::circuit::RevelationCircuit;
-use self::circuit::RevelationWires;
+use self::circuit::{packed_keys_commitment, RevelationWires};
+pub use self::circuit::{
+    BlockRangeVisibility, CommitmentHash, QueryCommitment, RevelationCircuit, SaltPolicy,
+};
 
+mod builder;
+mod cache;
 pub mod circuit;
+mod error;
+mod multi;
+mod pool;
+mod prover;
 mod public_inputs;
-pub use self::public_inputs::RevelationPublicInputs;
+mod query;
+mod registry;
+#[cfg(any(test, feature = "testing"))]
+pub mod test_utils;
+pub use self::builder::ParametersBuilder;
+pub use self::cache::{CachedVerificationError, VerificationCache};
+pub use self::error::RevelationError;
+pub use self::multi::{MultiParameters, MultiRevelationInput};
+pub use self::pool::{PendingProof, RevelationProverPool};
+pub use self::prover::RevelationProver;
+pub use self::public_inputs::{revelation_layout, RevelationPublicInputs, RevelationSummary};
+pub use self::query::Query;
+pub use self::registry::{build_dyn_parameters, BlockDbDepth, DynParameters, ParamsRegistry};
 /// Wires containing the main logic wires of the RevelationCircuit,
 /// the verifier wires to check a crate::block proof (block db) and
 /// the verifier wires to check a proof from query2/block circuit set.
@@ -46,6 +86,11 @@ pub use self::public_inputs::RevelationPublicInputs;
 /// - `BLOCK_DB_DEPTH` the depth of the block db merkle tree, fixed since we
 ///   we use a fixed sparse merkle tree.
 /// - `L` the number of NFT IDs to reveal
+///
+/// Note that `L` is part of the identity of the revelation circuit: the parameters built for
+/// a given `L` verify only proofs generated for the same `L`, even if they reveal fewer NFT IDs.
+/// A result computed for a smaller `L` can be re-proven with these parameters through
+/// `PaddedProof`.
 #[derive(Serialize, Deserialize)]
 pub struct Parameters<const BLOCK_DB_DEPTH: usize, const L: usize> {
     /// The regular wires for the logic of RevelationCircuit
@@ -54,15 +99,339 @@ pub struct Parameters<const BLOCK_DB_DEPTH: usize, const L: usize> {
     query2_block: RecursiveCircuitsVerifierTarget<D>,
     /// The actual set of potential proofs VK that can be generated by query2/block
     query2_block_circuit_set: RecursiveCircuits<F, C, D>,
-    /// The wires to verify a single regular proof by crate::block module
-    #[serde(serialize_with = "serialize", deserialize_with = "deserialize")]
-    block_db: ProofWithPublicInputsTarget<D>,
+    /// The wires to verify a single regular proof by crate::block module, generated by any
+    /// of the accepted block db circuits
+    block_db: WhitelistedCircuitsVerifierTarget<D>,
+    /// The verifier data of the block db circuits whose proofs are accepted
+    #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
+    block_db_verifier_datas: Vec<VerifierOnlyCircuitData<C, D>>,
+    /// Number of gates of the revelation circuit, before padding to a power of two
+    num_gates: usize,
     /// The circuit data of the revelation circuit, required to generate and verify
     /// a revelation proof.
     #[serde(serialize_with = "serialize", deserialize_with = "deserialize")]
     circuit_data: CircuitData<F, C, D>,
 }
 
+/// Data required only to generate revelation proofs, obtained by splitting `Parameters`
+/// for deployments where proofs are generated and verified by different nodes
+#[derive(Serialize, Deserialize)]
+pub struct ProverArtifact<const BLOCK_DB_DEPTH: usize, const L: usize> {
+    revelation: RevelationWires<L>,
+    query2_block: RecursiveCircuitsVerifierTarget<D>,
+    query2_block_circuit_set: RecursiveCircuits<F, C, D>,
+    block_db: WhitelistedCircuitsVerifierTarget<D>,
+    #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
+    block_db_verifier_datas: Vec<VerifierOnlyCircuitData<C, D>>,
+    #[serde(serialize_with = "serialize", deserialize_with = "deserialize")]
+    prover_data: ProverCircuitData<F, C, D>,
+}
+
+/// Revelation circuit added to a circuit builder, together with the wires to assign its
+/// witness, before its circuit data is built
+struct RevelationCircuitBuilder<const L: usize> {
+    builder: CircuitBuilder<F, D>,
+    revelation: RevelationWires<L>,
+    query2_block: RecursiveCircuitsVerifierTarget<D>,
+    block_db: WhitelistedCircuitsVerifierTarget<D>,
+}
+
+/// Data required only to verify revelation proofs, obtained by splitting `Parameters` or by
+/// `Parameters::build_verifier_only`
+#[derive(Serialize, Deserialize)]
+pub struct VerifierArtifact {
+    #[serde(serialize_with = "serialize", deserialize_with = "deserialize")]
+    verifier_data: VerifierCircuitData<F, C, D>,
+}
+
+/// Layout of the revelation `Parameters` saved to disk by `Parameters::save`: the query2/block
+/// circuit set is not saved, as it is owned by the caller, but the digest of the set is saved to
+/// check that the parameters are loaded with the set they were built for. `Q` are the wires to
+/// verify the query2/block proofs, i.e. one proof for `Parameters` and `K` for `MultiParameters`
+#[derive(Serialize)]
+struct SavedParametersRef<'a, const L: usize, Q> {
+    revelation: &'a RevelationWires<L>,
+    query2_block: &'a Q,
+    query2_block_set_digest: Vec<F>,
+    block_db: &'a WhitelistedCircuitsVerifierTarget<D>,
+    #[serde(serialize_with = "serialize_vec")]
+    block_db_verifier_datas: &'a [VerifierOnlyCircuitData<C, D>],
+    num_gates: usize,
+    #[serde(serialize_with = "serialize_ref")]
+    circuit_data: &'a CircuitData<F, C, D>,
+}
+
+/// Owned counterpart of `SavedParametersRef`, employed to load the saved parameters
+#[derive(Deserialize)]
+struct SavedParameters<const L: usize, Q> {
+    revelation: RevelationWires<L>,
+    query2_block: Q,
+    query2_block_set_digest: Vec<F>,
+    block_db: WhitelistedCircuitsVerifierTarget<D>,
+    #[serde(deserialize_with = "deserialize_vec")]
+    block_db_verifier_datas: Vec<VerifierOnlyCircuitData<C, D>>,
+    num_gates: usize,
+    #[serde(deserialize_with = "deserialize")]
+    circuit_data: CircuitData<F, C, D>,
+}
+
+/// Version of the format of the revelation `Parameters` saved to disk, written before the
+/// serialized `SavedParametersRef`; it must be bumped whenever the saved layout changes
+const SAVED_PARAMETERS_VERSION: u8 = 1;
+
+fn serialize_ref<T: ToBytes, S: Serializer>(input: &&T, serializer: S) -> Result<S::Ok, S::Error> {
+    serialize(*input, serializer)
+}
+
+/// Save `saved` to the file at `path`, which is overwritten if it already exists
+fn save_parameters<const L: usize, Q: Serialize>(
+    path: &Path,
+    saved: &SavedParametersRef<L, Q>,
+) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(&[SAVED_PARAMETERS_VERSION])?;
+    bincode::serialize_into(&mut writer, saved)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Load the parameters saved to the file at `path` by `save_parameters`, checking that they were
+/// saved for `L` and built for the circuit sets `query2_block_set` and `block_db_circuit_set`
+fn load_parameters<const L: usize, Q: DeserializeOwned>(
+    path: &Path,
+    query2_block_set: &RecursiveCircuits<F, C, D>,
+    block_db_circuit_set: &RecursiveCircuits<F, C, D>,
+) -> Result<SavedParameters<L, Q>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut version = [0u8];
+    reader.read_exact(&mut version)?;
+    ensure!(
+        version[0] == SAVED_PARAMETERS_VERSION,
+        "revelation parameters saved in {} have format version {}, while version {} is \
+        expected: they must be built and saved again",
+        path.display(),
+        version[0],
+        SAVED_PARAMETERS_VERSION,
+    );
+    let saved: SavedParameters<L, Q> = bincode::deserialize_from(reader)?;
+    let num_public_inputs = RevelationPublicInputs::<Target, L>::total_len();
+    ensure!(
+        saved.circuit_data.common.num_public_inputs == num_public_inputs
+            || saved.circuit_data.common.num_public_inputs
+                == mrp2_public_inputs::revelation::committed_total_len(L),
+        "revelation parameters saved in {} expose {} public inputs, while the parameters for \
+        L = {} expose {}",
+        path.display(),
+        saved.circuit_data.common.num_public_inputs,
+        L,
+        num_public_inputs,
+    );
+    ensure!(
+        saved.query2_block_set_digest == query2_block_set.get_circuit_set_digest().flatten(),
+        "revelation parameters saved in {} were built for another query2/block circuit set",
+        path.display(),
+    );
+    ensure!(
+        saved
+            .block_db_verifier_datas
+            .iter()
+            .all(|vd| block_db_circuit_set.contains_circuit_digest(&vd.circuit_digest)),
+        "revelation parameters saved in {} were built for another block db circuit set",
+        path.display(),
+    );
+    Ok(saved)
+}
+
+/// Deserialize a revelation proof and return its public inputs, checking that the shape of the
+/// proof matches the one of the proofs generated by the circuit with data `common`, without
+/// verifying the proof
+fn inspect_proof<const L: usize>(
+    common: &CommonCircuitData<F, D>,
+    proof: &[u8],
+) -> Result<RevelationPublicInputs<'static, F, L>> {
+    let proof = deserialize_proof::<F, C, D>(proof)?;
+    ensure!(
+        proof.public_inputs.len() == common.num_public_inputs,
+        "revelation proof has {} public inputs, expected {}",
+        proof.public_inputs.len(),
+        common.num_public_inputs,
+    );
+    let cap_height = common.config.fri_config.cap_height;
+    let openings = &proof.proof.openings;
+    ensure!(
+        proof.proof.wires_cap.height() == cap_height
+            && proof.proof.plonk_zs_partial_products_cap.height() == cap_height
+            && proof.proof.quotient_polys_cap.height() == cap_height,
+        "revelation proof has merkle caps of unexpected height"
+    );
+    ensure!(
+        openings.constants.len() == common.num_constants
+            && openings.plonk_sigmas.len() == common.config.num_routed_wires
+            && openings.wires.len() == common.config.num_wires
+            && openings.quotient_polys.len()
+                == common.config.num_challenges * common.quotient_degree_factor,
+        "revelation proof has openings of unexpected shape"
+    );
+    ensure!(
+        proof.proof.opening_proof.query_round_proofs.len()
+            == common.config.fri_config.num_query_rounds,
+        "revelation proof has an unexpected number of FRI query rounds"
+    );
+    Ok(RevelationPublicInputs::from_exposed(proof.public_inputs))
+}
+
+/// Outcome of the linkage audit between a revelation proof and the query2/block and block db
+/// proofs it is claimed to be built upon. Each flag states whether the value exposed by the
+/// revelation proof is equal to the corresponding one found in the linked proofs; it is `None`
+/// if the comparison is not applicable, as the revelation proof exposes only a commitment to
+/// the value, i.e. to the parameters of the query or to its block range.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LinkageReport {
+    /// Block number exposed by the query2/block proof
+    pub block_number: Option<bool>,
+    /// Range aggregated by the query2/block proof
+    pub range: Option<bool>,
+    /// Smart contract address of the query
+    pub smart_contract_address: Option<bool>,
+    /// User address of the query, the revelation only exposes the packed address and not the
+    /// left padded value found in the query2/block proof
+    pub user_address: Option<bool>,
+    /// Mapping slot of the query
+    pub mapping_slot: Option<bool>,
+    /// Length slot of the query
+    pub mapping_slot_length: Option<bool>,
+    /// Root of the query2/block proof equal to the root of the block db proof
+    pub root: bool,
+    /// Block header exposed by the block db proof
+    pub block_header: bool,
+}
+
+/// Values a revelation proof is expected to expose, checked by `verify_proof_against` once
+/// the proof is verified; the values set to `None` are not checked
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RevelationExpectation {
+    /// Minimum block number of the query
+    pub min_block: Option<u32>,
+    /// Maximum block number of the query
+    pub max_block: Option<u32>,
+    /// Mapping keys expected to match the query, in any order and possibly duplicated, as
+    /// provided to `RevelationRecursiveInput::new`; they are compared to the commitment to
+    /// the set of keys exposed by the proof, so all the matching keys are checked, even the
+    /// ones outside the revealed window
+    pub mapping_keys: Option<Vec<Vec<u8>>>,
+}
+
+impl RevelationExpectation {
+    /// Check that the public inputs of a revelation proof expose the expected values,
+    /// returning an error describing the first mismatch found
+    pub fn check<const L: usize>(&self, pis: &RevelationPublicInputs<F, L>) -> Result<()> {
+        if let Some(min_block) = self.min_block {
+            let proven_min = pis.min_block()?;
+            ensure!(
+                proven_min == min_block,
+                "proof answers a query starting from block {}, while block {} was expected",
+                proven_min,
+                min_block,
+            );
+        }
+        if let Some(max_block) = self.max_block {
+            let proven_max = pis.max_block()?;
+            ensure!(
+                proven_max == max_block,
+                "proof answers a query ending at block {}, while block {} was expected",
+                proven_max,
+                max_block,
+            );
+        }
+        if let Some(mapping_keys) = &self.mapping_keys {
+            let sorted_keys = sort_packed_keys(&pack_mapping_keys(mapping_keys)?);
+            ensure!(
+                pis.keys_commitment() == packed_keys_commitment(&sorted_keys),
+                "the {} keys matching the query in the proof are not the {} expected keys",
+                pis.total_matching(),
+                sorted_keys.len(),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Parameters of the FRI configuration employed by the revelation circuit, which are
+/// necessary to re-implement the verification of revelation proofs outside of plonky2
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FriConfigDescriptor {
+    /// `rate = 2^{-rate_bits}`
+    pub rate_bits: usize,
+    /// Height of Merkle tree caps
+    pub cap_height: usize,
+    /// Number of bits of the proof of work employed for grinding
+    pub proof_of_work_bits: u32,
+    /// Number of query rounds to perform
+    pub num_query_rounds: usize,
+}
+
+/// Shape of a set of revelation parameters, which can be employed to pick the parameters
+/// suitable for a query among several ones
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParametersInfo {
+    /// Maximum number of NFT IDs revealed, i.e. `L`
+    pub max_entries: usize,
+    /// Depth of the block db merkle tree, i.e. `BLOCK_DB_DEPTH`
+    pub block_db_depth: usize,
+    /// Number of gates of the revelation circuit, after padding to a power of two
+    pub num_gates: usize,
+}
+
+/// Size of the revelation circuit, employed to choose the hardware generating the proofs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitStats {
+    /// Number of gates of the circuit, before padding to a power of two, unlike the padded
+    /// `ParametersInfo::num_gates`
+    pub num_unpadded_gates: usize,
+    /// Number of wires of each gate
+    pub num_wires: usize,
+    /// Number of public inputs exposed by the proofs
+    pub num_public_inputs: usize,
+    /// Logarithm of the degree of the circuit, i.e. of the number of gates after padding
+    pub degree_bits: usize,
+}
+
+/// Estimate of the gas spent to verify on-chain the Groth16 proof wrapping a revelation proof
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasEstimate {
+    /// Fixed cost of the transaction, of the Groth16 proof calldata, of the pairing check and
+    /// of the Groth16 inputs
+    pub fixed: u64,
+    /// Cost of providing and hashing the plonky2 public inputs
+    pub public_inputs: u64,
+}
+
+impl GasEstimate {
+    /// Total estimated gas
+    pub fn total(&self) -> u64 {
+        self.fixed + self.public_inputs
+    }
+}
+
+impl LinkageReport {
+    /// Returns true if all the values checked during the audit matched; the values whose
+    /// comparison is not applicable are not taken into account
+    pub fn is_linked(&self) -> bool {
+        [
+            self.block_number,
+            self.range,
+            self.smart_contract_address,
+            self.user_address,
+            self.mapping_slot,
+            self.mapping_slot_length,
+        ]
+        .into_iter()
+        .all(|matched| matched != Some(false))
+            && self.root
+            && self.block_header
+    }
+}
+
 /// Circuit inputs for the revelation step which contains the
 /// raw witnesses and the proof to verify in circuit.
 /// The proof is any of the proofs contained in the `query2/block/` module.
@@ -74,9 +443,17 @@ pub struct RevelationRecursiveInput<const L: usize> {
     /// The actual proof generated by the block db module, each time a new block
     /// is preprocessed
     block_db_proof: ProofWithPublicInputs<F, C, D>,
+    /// The verifier data of the block db circuit which generated `block_db_proof`, required
+    /// only if the revelation parameters accept proofs from several block db circuits
+    block_db_verifier_data: Option<VerifierOnlyCircuitData<C, D>>,
+    /// Number of mapping keys provided which were dropped as duplicates of other keys
+    num_duplicated_keys: usize,
 }
 
 impl<const L: usize> RevelationRecursiveInput<L> {
+    /// Duplicated mapping keys are dropped, and the number of entries proven is the number of
+    /// distinct keys; `num_duplicated_keys` returns how many keys were dropped. Mapping keys
+    /// longer than `MAPPING_KEY_LEN` bytes are rejected
     pub fn new(
         mapping_keys: Vec<Vec<u8>>,
         query_min_block: usize,
@@ -84,63 +461,477 @@ impl<const L: usize> RevelationRecursiveInput<L> {
         query2_block_proof: Vec<u8>,
         block_db_proof: Vec<u8>,
     ) -> Result<RevelationRecursiveInput<L>> {
-        // sort mapping keys depending on the last limb, as it is the only limb currently considered
-        // in the circuit
-        let sorted_keys = mapping_keys
+        Self::from_packed_keys(
+            pack_mapping_keys(&mapping_keys)?,
+            query_min_block,
+            query_max_block,
+            query2_block_proof,
+            block_db_proof,
+        )
+    }
+
+    /// Same as `new`, but with mapping keys already left padded to `MAPPING_KEY_LEN` bytes and
+    /// packed in u32 limbs, as done by `left_pad32(key).pack()`
+    pub fn from_packed_keys(
+        packed_keys: Vec<[u32; PACKED_MAPPING_KEY_LEN]>,
+        query_min_block: usize,
+        query_max_block: usize,
+        query2_block_proof: Vec<u8>,
+        block_db_proof: Vec<u8>,
+    ) -> Result<RevelationRecursiveInput<L>> {
+        catch_panics(|| {
+            let (main_inputs, num_duplicated_keys) = RevelationCircuit::from_packed_keys(
+                &packed_keys,
+                query_min_block,
+                query_max_block,
+            )?;
+            let query2_block_proof = ProofWithVK::deserialize(&query2_block_proof)?;
+            let block_db_proof = deserialize_block_db_proof(&block_db_proof)?;
+            check_proofs_consistency(
+                &main_inputs,
+                &query2_block_proof,
+                &block_db_proof.public_inputs,
+            )?;
+            Ok(RevelationRecursiveInput {
+                logic_inputs: main_inputs,
+                query2_block_proof,
+                block_db_proof,
+                block_db_verifier_data: None,
+                num_duplicated_keys,
+            })
+        })
+    }
+
+    /// Same as `new`, but with mapping keys provided as hex strings, e.g. as returned by
+    /// JSON-RPC, each one representing at most `MAPPING_KEY_LEN` bytes; the `0x` prefix is
+    /// optional and digits can be in any case
+    pub fn from_hex_keys(
+        mapping_keys: &[&str],
+        query_min_block: usize,
+        query_max_block: usize,
+        query2_block_proof: Vec<u8>,
+        block_db_proof: Vec<u8>,
+    ) -> Result<RevelationRecursiveInput<L>> {
+        let mapping_keys = mapping_keys
             .iter()
             .map(|key| {
-                let packed = left_pad32(key).pack();
-                (*packed.last().unwrap(), packed)
+                let digits = key
+                    .strip_prefix("0x")
+                    .or_else(|| key.strip_prefix("0X"))
+                    .unwrap_or(key);
+                // checked here as the parsing below would accept a second prefix
+                ensure!(
+                    digits.chars().all(|c| c.is_ascii_hexdigit()),
+                    "invalid hex mapping key {}: only hex digits are allowed after the optional 0x prefix",
+                    key
+                );
+                // allow an odd number of digits, e.g. `0x1`
+                let digits = if digits.len() % 2 == 1 {
+                    format!("0{}", digits)
+                } else {
+                    digits.to_string()
+                };
+                let bytes = Bytes::from_str(&digits)
+                    .map_err(|e| anyhow!("invalid hex mapping key {}: {}", key, e))?;
+                ensure!(
+                    bytes.len() <= MAPPING_KEY_LEN,
+                    "mapping key {} is longer than {} bytes",
+                    key,
+                    MAPPING_KEY_LEN
+                );
+                Ok(bytes.to_vec())
             })
-            .collect::<BTreeMap<_, _>>();
-        let mut sorted_keys_iter = sorted_keys.into_iter();
-        let keys = create_array(|_i| {
-            if let Some((_, packed)) = sorted_keys_iter.next() {
-                create_array(|j| packed[j])
-            } else {
-                [0u32; PACKED_MAPPING_KEY_LEN]
-            }
-        });
-        let num_entries = mapping_keys.len();
-        assert!(
-            num_entries <= L,
-            "Number of entries {} should not exceed fixed parameter L {}",
-            num_entries,
+            .collect::<Result<Vec<_>>>()?;
+        Self::new(
+            mapping_keys,
+            query_min_block,
+            query_max_block,
+            query2_block_proof,
+            block_db_proof,
+        )
+    }
+
+    /// Reveal only the window of `limit` sorted keys starting from `offset`, rather than all
+    /// the keys matching the query; the NFT IDs outside the window are replaced by zeros in
+    /// the public inputs, which still expose the total number of matching keys
+    pub fn with_page(mut self, offset: u8, limit: u8) -> Self {
+        self.logic_inputs.page = Some((offset, limit));
+        self
+    }
+
+    /// Mix `salt` in the result commitment, so that it differs from the commitments of other
+    /// queries revealing the same keys; this is required by parameters built with a salt
+    pub fn with_salt(mut self, salt: F) -> Self {
+        self.logic_inputs.salt = Some(salt);
+        self
+    }
+
+    /// Blind the commitment to the block range with `range_blinding`; this is required by
+    /// parameters hiding the block range, and the blinding factor must be kept secret to
+    /// prevent recovering the range from the commitment
+    pub fn with_range_blinding(mut self, range_blinding: F) -> Self {
+        self.logic_inputs.range_blinding = Some(range_blinding);
+        self
+    }
+
+    /// Bind the proof to `client_address`, which is exposed in the public inputs so that the
+    /// proof can be consumed only by that client; proofs are bound to the zero address if no
+    /// client is specified
+    pub fn with_client_address(mut self, client_address: Address) -> Self {
+        self.logic_inputs.client_address = client_address;
+        self
+    }
+
+    /// Specify the verifier data of the block db circuit which generated the block db proof;
+    /// this is required by parameters accepting proofs from several block db circuits
+    pub fn with_block_db_verifier_data(
+        mut self,
+        block_db_verifier_data: VerifierOnlyCircuitData<C, D>,
+    ) -> Self {
+        self.block_db_verifier_data = Some(block_db_verifier_data);
+        self
+    }
+
+    /// Number of mapping keys provided to `new` or `from_packed_keys` which were dropped as
+    /// duplicates of other keys
+    pub fn num_duplicated_keys(&self) -> usize {
+        self.num_duplicated_keys
+    }
+}
+
+/// Deserialize a block db proof, rejecting proofs generated by another circuit before proving
+fn deserialize_block_db_proof(bytes: &[u8]) -> Result<ProofWithPublicInputs<F, C, D>> {
+    Ok(deserialize_proof_expecting(
+        bytes,
+        BLOCK_DB_NUM_IO + NUM_CIRCUIT_SET_DIGEST_PUBLIC_INPUTS,
+    )?)
+}
+
+/// Check before proving that the query of `logic_inputs` can be answered by the revelation
+/// circuit with the given query2/block proof and a block db proof with `block_db_inputs`
+fn check_proofs_consistency<const L: usize>(
+    logic_inputs: &RevelationCircuit<L>,
+    query2_block_proof: &ProofWithVK,
+    block_db_inputs: &[F],
+) -> Result<()> {
+    let query_min_block = logic_inputs.query_min_block_number;
+    let query_max_block = logic_inputs.query_max_block_number;
+    // the roots are assembled from the public inputs of each proof, so check they are
+    // encoded in the same way before proving
+    ensure!(
+        query2_block_proof.proof().public_inputs.len() >= QUERY2_BLOCK_NUM_IO,
+        "query2/block proof exposes fewer public inputs than expected"
+    );
+    let query2_pi = block::BlockPublicInputs::<F>::from(
+        &query2_block_proof.proof().public_inputs[..QUERY2_BLOCK_NUM_IO],
+    );
+    let block_db_pi = BlockDbPublicInputs::from(&block_db_inputs[..BLOCK_DB_NUM_IO]);
+    // the circuit requires the queried range to include the blocks aggregated in the
+    // query2/block proof, i.e. the blocks in (block_number - range, block_number]
+    let proof_max_block = query2_pi.block_number().to_canonical_u64();
+    let proof_min_block =
+        (proof_max_block + 1).saturating_sub(query2_pi.range().to_canonical_u64());
+    ensure!(
+        query_min_block as u64 <= proof_min_block && proof_max_block <= query_max_block as u64,
+        "query block range [{}, {}] doesn't include the blocks [{}, {}] covered by the \
+        query2/block proof",
+        query_min_block,
+        query_max_block,
+        proof_min_block,
+        proof_max_block,
+    );
+    // the circuit requires the queried range to be contained in the range of blocks
+    // of the block db
+    let db_min_block = block_db_pi.first_block_number_data().to_canonical_u64();
+    let db_max_block = block_db_pi.block_number_data().to_canonical_u64();
+    ensure!(
+        db_min_block <= query_min_block as u64 && query_max_block as u64 <= db_max_block,
+        "query block range [{}, {}] is not contained in the blocks [{}, {}] of the block db",
+        query_min_block,
+        query_max_block,
+        db_min_block,
+        db_max_block,
+    );
+    assert_roots_field_compatible(
+        &query2_pi.root(),
+        &HashOut {
+            elements: block_db_pi.root_data().try_into()?,
+        },
+    )
+}
+
+/// Left pad the mapping keys to `MAPPING_KEY_LEN` bytes and pack them in u32 limbs, failing
+/// if any key is longer than `MAPPING_KEY_LEN` bytes
+fn pack_mapping_keys(mapping_keys: &[Vec<u8>]) -> Result<Vec<[u32; PACKED_MAPPING_KEY_LEN]>> {
+    mapping_keys
+        .iter()
+        .map(|key| {
+            let packed = try_left_pad32(key)?.pack();
+            Ok(create_array(|i| packed[i]))
+        })
+        .collect()
+}
+
+/// Sort the packed mapping keys as expected by the circuit, dropping the duplicated keys
+fn sort_packed_keys(
+    packed_keys: &[[u32; PACKED_MAPPING_KEY_LEN]],
+) -> Vec<[u32; PACKED_MAPPING_KEY_LEN]> {
+    // sort mapping keys in their numeric order, as the circuit compares the whole keys: the
+    // bytes of each limb are swapped to get big-endian limbs, whose lexicographic order, from
+    // the first limb to the last one, which is the NFT ID, is the order of the big-endian keys
+    packed_keys
+        .iter()
+        .map(|key| key.map(u32::swap_bytes))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|key| key.map(u32::swap_bytes))
+        .collect()
+}
+
+/// Pad the sorted packed keys with empty keys up to `L`
+fn pad_packed_keys<const L: usize>(
+    sorted_keys: &[[u32; PACKED_MAPPING_KEY_LEN]],
+) -> [[u32; PACKED_MAPPING_KEY_LEN]; L] {
+    let mut sorted_keys_iter = sorted_keys.iter();
+    create_array(|_i| {
+        sorted_keys_iter
+            .next()
+            .copied()
+            .unwrap_or([0u32; PACKED_MAPPING_KEY_LEN])
+    })
+}
+
+/// Check that the root exposed by a query2/block proof and the root exposed by a block db
+/// proof are the same field elements, failing with an error naming the first mismatching
+/// element otherwise; as the roots are assembled by different modules, this catches a drift
+/// in their encoding before the revelation circuit fails to connect them
+pub fn assert_roots_field_compatible(
+    query2_root: &HashOut<F>,
+    block_db_root: &HashOut<F>,
+) -> Result<()> {
+    query2_root
+        .elements
+        .iter()
+        .zip(block_db_root.elements.iter())
+        .enumerate()
+        .try_for_each(|(i, (query2_element, block_db_element))| {
+            ensure!(
+                query2_element == block_db_element,
+                "query2/block root {:?} and block db root {:?} differ at element {}: {} != {}",
+                query2_root,
+                block_db_root,
+                i,
+                query2_element,
+                block_db_element,
+            );
+            Ok(())
+        })
+}
+
+/// Adapter re-proving, with the revelation circuit for `L` NFT IDs, a result computed for a
+/// revelation circuit with fewer NFT IDs, so that it can be verified by the parameters for `L`.
+/// The keys are padded with the empty keys employed by the circuit for the missing entries,
+/// so the revealed NFT IDs are the same ones found in the proof for the smaller circuit,
+/// followed by zeros.
+pub struct PaddedProof<const L: usize> {
+    input: RevelationRecursiveInput<L>,
+}
+
+impl<const L: usize> PaddedProof<L> {
+    /// Adapt the inputs for a revelation circuit with `SMALL_L <= L` NFT IDs
+    pub fn from_input<const SMALL_L: usize>(
+        input: RevelationRecursiveInput<SMALL_L>,
+    ) -> Result<Self> {
+        ensure!(
+            SMALL_L <= L,
+            "cannot pad a revelation input for {} NFT IDs into a circuit for {} NFT IDs",
+            SMALL_L,
             L
         );
-        let main_inputs = RevelationCircuit {
-            packed_keys: keys,
-            num_entries: num_entries as u8,
-            query_min_block_number: query_min_block,
-            query_max_block_number: query_max_block,
-        };
-        Ok(RevelationRecursiveInput {
-            logic_inputs: main_inputs,
-            query2_block_proof: ProofWithVK::deserialize(&query2_block_proof)?,
-            block_db_proof: deserialize_proof(&block_db_proof)?,
+        let logic_inputs = input.logic_inputs;
+        let mut packed_keys = logic_inputs.packed_keys.into_iter();
+        Ok(Self {
+            input: RevelationRecursiveInput {
+                logic_inputs: RevelationCircuit {
+                    packed_keys: create_array(|_| {
+                        packed_keys.next().unwrap_or([0u32; PACKED_MAPPING_KEY_LEN])
+                    }),
+                    num_entries: logic_inputs.num_entries,
+                    query_min_block_number: logic_inputs.query_min_block_number,
+                    query_max_block_number: logic_inputs.query_max_block_number,
+                    page: logic_inputs.page,
+                    salt: logic_inputs.salt,
+                    range_blinding: logic_inputs.range_blinding,
+                    client_address: logic_inputs.client_address,
+                },
+                query2_block_proof: input.query2_block_proof,
+                block_db_proof: input.block_db_proof,
+                block_db_verifier_data: input.block_db_verifier_data,
+                num_duplicated_keys: input.num_duplicated_keys,
+            },
         })
     }
+
+    /// Generate the revelation proof for the padded result with `params`
+    pub fn prove<const BLOCK_DB_DEPTH: usize>(
+        self,
+        params: &Parameters<BLOCK_DB_DEPTH, L>,
+    ) -> Result<Vec<u8>>
+    where
+        [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+    {
+        params.generate_proof(self.input)
+    }
 }
 
 const QUERY2_BLOCK_NUM_IO: usize = block::BlockPublicInputs::<Target>::total_len();
 const BLOCK_DB_NUM_IO: usize = NUM_IVC_PUBLIC_INPUTS;
+/// Maximum number of public inputs of a revelation proof that can be decoded by the Solidity
+/// verifier, i.e. the `MAX_PI_LEN` constant of `query2.sol`, which rejects proofs exposing more
+/// public inputs; the number of NFT IDs `L` revealed by the circuit must be chosen accordingly.
+pub const SOLIDITY_MAX_PI: usize = 128;
+/// Upper bound on the number of gates of the revelation circuit not depending on `L`, mostly
+/// employed to recursively verify the query2/block and block db proofs; it is checked against
+/// the built circuits by `test_revelation_estimated_num_gates`
+const BASE_NUM_GATES: usize = 8_500;
+/// Upper bound on the number of gates of the revelation circuit employed to process each NFT
+/// ID, checked together with `BASE_NUM_GATES`
+const NUM_GATES_PER_ID: usize = 600;
+/// Average number of bytes employed to serialize the witness generators of a gate
+const GENERATOR_BYTES_PER_GATE: usize = 256;
+
+/// Maximum relative error of `estimate_verification_gas` with respect to the gas actually used,
+/// as a fraction of the gas used
+pub const GAS_ESTIMATE_TOLERANCE: f64 = 0.25;
+/// Intrinsic gas of the transaction calling the verifier contract
+const TX_BASE_GAS: u64 = 21_000;
+/// Gas of the calldata of the Groth16 proof and of its inputs, i.e. 11 words of non-zero bytes
+const GROTH16_CALLDATA_GAS: u64 = 11 * 32 * 16;
+/// Gas of the pairing check of a Groth16 proof, i.e. 4 pairings with the EIP-1108 costs
+const GROTH16_PAIRING_GAS: u64 = 45_000 + 4 * 34_000;
+/// Gas to accumulate each of the 3 Groth16 inputs, i.e. a scalar multiplication and an addition
+/// with the EIP-1108 costs
+const GROTH16_INPUTS_GAS: u64 = 3 * (6_000 + 150);
+/// Gas for each plonky2 public input, i.e. 8 bytes of non-zero calldata and their share of the
+/// keccak hash binding them to the Groth16 inputs
+const GAS_PER_PUBLIC_INPUT: u64 = 8 * 16 + 2;
+
+/// Estimate the gas spent to verify on-chain the Groth16 proof wrapping a proof with
+/// `num_public_inputs` plonky2 public inputs; this is only a hint for pricing, checked against
+/// the gas used by the query2 verifier contract in revm up to `GAS_ESTIMATE_TOLERANCE`
+pub fn estimate_verification_gas(num_public_inputs: usize) -> GasEstimate {
+    GasEstimate {
+        fixed: TX_BASE_GAS + GROTH16_CALLDATA_GAS + GROTH16_PAIRING_GAS + GROTH16_INPUTS_GAS,
+        public_inputs: num_public_inputs as u64 * GAS_PER_PUBLIC_INPUT,
+    }
+}
+
+/// Upper bound on the number of gates of the revelation circuit for `l` NFT IDs, before padding
+/// to a power of two
+fn estimated_num_gates(l: usize) -> usize {
+    BASE_NUM_GATES + l * NUM_GATES_PER_ID
+}
+
+/// Estimate the number of bytes of serialized `Parameters` for a block db tree of depth
+/// `_block_db_depth` and `l` NFT IDs, without building them. The estimate is derived from
+/// the expected degree of the circuit, so it is meant only for capacity planning.
+/// The block db proof is verified recursively, so the depth of the block db tree does not
+/// change the shape of the circuit and hence the size of the parameters.
+pub fn estimated_parameters_size(_block_db_depth: usize, l: usize) -> usize {
+    let config = default_config();
+    let degree = estimated_num_gates(l).next_power_of_two();
+    let lde_size = degree << config.fri_config.rate_bits;
+    let field_size = std::mem::size_of::<F>();
+    // constants and sigmas polynomials, stored both in coefficient form and as the leaves of the
+    // merkle tree committing to their low degree extension, together with the tree digests
+    let num_committed_polys = config.num_constants + config.num_routed_wires;
+    let commitment = num_committed_polys * (degree + lde_size) * field_size
+        + 2 * lde_size * NUM_HASH_OUT_ELTS * field_size;
+    // sigmas values, subgroup and representatives of the copy constraints
+    let values = (config.num_routed_wires + 1 + config.num_wires) * degree * field_size;
+    commitment + values + degree * GENERATOR_BYTES_PER_GATE
+}
 
 impl<const BLOCK_DB_DEPTH: usize, const L: usize> Parameters<BLOCK_DB_DEPTH, L> {
     /// Arguments are the circuit sets used to generate the query2/block proofs
-    /// and the block db proof, and the verification key of the block db circuit
+    /// and the block db proof, and the verification key of the block db circuit;
+    /// the other settings are the default ones, `ParametersBuilder` allows to change them
     pub fn build(
         query2_block_set: &RecursiveCircuits<F, C, D>,
         block_db_circuit_set: &RecursiveCircuits<F, C, D>,
         block_db_verifier_data: &VerifierOnlyCircuitData<C, D>,
-    ) -> Self
+    ) -> Result<Self>
+    where
+        [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+    {
+        catch_panics(|| {
+            Self::build_internal(
+                query2_block_set,
+                block_db_circuit_set,
+                std::slice::from_ref(block_db_verifier_data),
+                CommitmentHash::default(),
+                SaltPolicy::default(),
+                QueryCommitment::default(),
+                BlockRangeVisibility::default(),
+                default_config(),
+            )
+        })
+    }
+    /// Same as `build`, but only the data required to verify the revelation proofs is
+    /// generated, without building the parameters to generate them; this is enough to inspect
+    /// the shape of the circuit and to verify the proofs generated with the parameters
+    /// returned by `build`
+    pub fn build_verifier_only(
+        query2_block_set: &RecursiveCircuits<F, C, D>,
+        block_db_circuit_set: &RecursiveCircuits<F, C, D>,
+        block_db_verifier_data: &VerifierOnlyCircuitData<C, D>,
+    ) -> Result<VerifierArtifact>
+    where
+        [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+    {
+        catch_panics(|| {
+            let circuit = Self::build_circuit(
+                query2_block_set,
+                block_db_circuit_set,
+                std::slice::from_ref(block_db_verifier_data),
+                CommitmentHash::default(),
+                SaltPolicy::default(),
+                QueryCommitment::default(),
+                BlockRangeVisibility::default(),
+                default_config(),
+            )?;
+            Ok(VerifierArtifact {
+                verifier_data: circuit.builder.build_verifier::<C>(),
+            })
+        })
+    }
+    /// Add the revelation circuit to a new circuit builder, without building its data
+    #[allow(clippy::too_many_arguments)]
+    fn build_circuit(
+        query2_block_set: &RecursiveCircuits<F, C, D>,
+        block_db_circuit_set: &RecursiveCircuits<F, C, D>,
+        block_db_verifier_datas: &[VerifierOnlyCircuitData<C, D>],
+        commitment_hash: CommitmentHash,
+        salt_policy: SaltPolicy,
+        query_commitment: QueryCommitment,
+        block_range_visibility: BlockRangeVisibility,
+        config: CircuitConfig,
+    ) -> Result<RevelationCircuitBuilder<L>>
     where
         [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
     {
-        let mut b = CircuitBuilder::new(default_config());
+        Self::check_num_public_inputs()?;
+        ensure!(
+            query_commitment == QueryCommitment::Disabled
+                || block_range_visibility == BlockRangeVisibility::Public,
+            "the block range can't be hidden when the parameters of the query are committed to"
+        );
+        check_block_db_verifier_datas(block_db_circuit_set, block_db_verifier_datas)?;
+        let mut b = CircuitBuilder::new(config.clone());
         // instantiate the wires to verify a query2/block proof which can be in a circuit set
         let query2_block_verifier_gadget =
             RecursiveCircuitsVerifierGagdet::<F, C, D, QUERY2_BLOCK_NUM_IO>::new(
-                default_config(),
+                config.clone(),
                 query2_block_set,
             );
         let query2_block_verifier_wires =
@@ -153,27 +944,136 @@ impl<const BLOCK_DB_DEPTH: usize, const L: usize> Parameters<BLOCK_DB_DEPTH, L>
         // instantiate the wires to verify a block db proof
         let block_db_verifier_gadget =
             RecursiveCircuitsVerifierGagdet::<F, C, D, BLOCK_DB_NUM_IO>::new(
-                default_config(),
+                config,
                 block_db_circuit_set,
             );
-        // we enforce that the db proof is generated with the IVC circuit, not the dummy one
+        // we enforce that the db proof is generated with one of the accepted IVC circuits, not
+        // the dummy one
         let block_db_wires = block_db_verifier_gadget
-            .verify_proof_fixed_circuit_in_circuit_set(&mut b, block_db_verifier_data);
+            .verify_proof_whitelisted_circuits_in_circuit_set(&mut b, block_db_verifier_datas)?;
         let block_db_pi = BlockDbPublicInputs::from(
-            BlockDbParameters::<BLOCK_DB_DEPTH>::block_tree_public_input_targets(&block_db_wires),
+            BlockDbParameters::<BLOCK_DB_DEPTH>::block_tree_public_input_targets(
+                block_db_wires.get_proof_target(),
+            ),
         );
 
-        let wires =
-            RevelationCircuit::build::<BLOCK_DB_DEPTH>(&mut b, block_db_pi, query2_block_pi);
+        let wires = RevelationCircuit::build::<BLOCK_DB_DEPTH>(
+            &mut b,
+            block_db_pi,
+            query2_block_pi,
+            commitment_hash,
+            salt_policy,
+            query_commitment,
+            block_range_visibility,
+        );
 
-        let circuit_data = b.build::<C>();
-        Self {
+        Ok(RevelationCircuitBuilder {
+            builder: b,
             revelation: wires,
             query2_block: query2_block_verifier_wires,
-            query2_block_circuit_set: query2_block_set.clone(),
             block_db: block_db_wires,
+        })
+    }
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn build_internal(
+        query2_block_set: &RecursiveCircuits<F, C, D>,
+        block_db_circuit_set: &RecursiveCircuits<F, C, D>,
+        block_db_verifier_datas: &[VerifierOnlyCircuitData<C, D>],
+        commitment_hash: CommitmentHash,
+        salt_policy: SaltPolicy,
+        query_commitment: QueryCommitment,
+        block_range_visibility: BlockRangeVisibility,
+        config: CircuitConfig,
+    ) -> Result<Self>
+    where
+        [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+    {
+        #[cfg(test)]
+        test::NUM_PARAMETERS_BUILT.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        let circuit = Self::build_circuit(
+            query2_block_set,
+            block_db_circuit_set,
+            block_db_verifier_datas,
+            commitment_hash,
+            salt_policy,
+            query_commitment,
+            block_range_visibility,
+            config,
+        )?;
+        let num_gates = circuit.builder.num_gates();
+        let circuit_data = circuit.builder.build::<C>();
+        Ok(Self {
+            revelation: circuit.revelation,
+            query2_block: circuit.query2_block,
+            query2_block_circuit_set: query2_block_set.clone(),
+            block_db: circuit.block_db,
+            block_db_verifier_datas: block_db_verifier_datas.to_vec(),
+            num_gates,
             circuit_data,
-        }
+        })
+    }
+    /// Estimate the number of bytes of these parameters once serialized, without building
+    /// them; refer to `estimated_parameters_size` for further details
+    pub fn estimated_serialized_size() -> usize {
+        estimated_parameters_size(BLOCK_DB_DEPTH, L)
+    }
+    /// Number of public inputs exposed by the revelation proofs
+    pub const fn num_public_inputs() -> usize {
+        RevelationPublicInputs::<Target, L>::total_len()
+    }
+    /// Maximum number of NFT IDs revealed by the proofs, i.e. `L`
+    pub const fn max_entries() -> usize {
+        L
+    }
+    /// Depth of the block db merkle tree whose proofs are accepted, i.e. `BLOCK_DB_DEPTH`
+    pub const fn block_db_depth() -> usize {
+        BLOCK_DB_DEPTH
+    }
+    /// Check that proofs generated with these parameters can be decoded by the Solidity verifier
+    fn check_num_public_inputs() -> Result<()> {
+        let num_public_inputs = Self::num_public_inputs();
+        ensure!(
+            num_public_inputs <= SOLIDITY_MAX_PI,
+            "revelation circuit for L = {} exposes {} public inputs, more than the {} supported by the Solidity verifier: reduce L to at most {}",
+            L,
+            num_public_inputs,
+            SOLIDITY_MAX_PI,
+            SOLIDITY_MAX_PI - RevelationPublicInputs::<Target, 0>::total_len(),
+        );
+        Ok(())
+    }
+    /// Check that `proof` exposes the public inputs of a revelation proof for `L` NFT IDs, as
+    /// proofs generated for another `L` cannot be verified by these parameters; the proofs
+    /// committing to the parameters of the query expose fewer public inputs
+    fn check_same_l(&self, proof: &ProofWithPublicInputs<F, C, D>) -> Result<()> {
+        let expected = self.circuit_data.common.num_public_inputs;
+        ensure!(
+            proof.public_inputs.len() == expected,
+            "revelation proof exposes {} public inputs, while the parameters for L = {} expect {}: \
+            proofs for a different L must be re-proven with `PaddedProof`",
+            proof.public_inputs.len(),
+            L,
+            expected,
+        );
+        Ok(())
+    }
+    /// Check that the number of public inputs of the query2/block and block db proofs verified by
+    /// these parameters is the one expected by the current code, failing with a message naming
+    /// the mismatching widths otherwise; this is meant to be called at startup, when parameters
+    /// are loaded from a serialized version possibly built with another version of the circuits
+    pub fn assert_io_widths(&self) -> Result<()> {
+        check_io_width(
+            "query2/block",
+            "BlockPublicInputs::total_len()",
+            QUERY2_BLOCK_NUM_IO,
+            self.query2_block.num_public_inputs(),
+        )?;
+        check_io_width(
+            "block db",
+            "NUM_IVC_PUBLIC_INPUTS",
+            BLOCK_DB_NUM_IO,
+            self.block_db.get_proof_target().public_inputs.len(),
+        )
     }
     fn generate_proof_internal(
         &self,
@@ -182,22 +1082,98 @@ impl<const BLOCK_DB_DEPTH: usize, const L: usize> Parameters<BLOCK_DB_DEPTH, L>
     where
         [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
     {
-        let mut pw = PartialWitness::new();
-        // assigns the block db proof, simple verifier target
-        pw.set_proof_with_pis_target(&self.block_db, &inputs.block_db_proof);
-        // assigns the query2/block proof, recursive verifier target
-        let (proof, vd) = inputs.query2_block_proof.into();
-        self.query2_block
-            .set_target(&mut pw, &self.query2_block_circuit_set, &proof, &vd)?;
-        // assigns the regular wires
-        inputs.logic_inputs.assign(&mut pw, &self.revelation);
+        let pw = assign_witness(
+            &self.revelation,
+            &self.query2_block,
+            &self.query2_block_circuit_set,
+            &self.block_db,
+            &self.block_db_verifier_datas,
+            inputs,
+        )?;
         let proof = self.circuit_data.prove(pw)?;
         Ok(proof)
     }
 
     pub fn generate_proof(&self, inputs: RevelationRecursiveInput<L>) -> Result<Vec<u8>> {
-        let proof = self.generate_proof_internal(inputs)?;
-        serialize_proof(&proof)
+        catch_panics(|| {
+            let proof = self.generate_proof_internal(inputs)?;
+            Ok(serialize_proof(&proof)?)
+        })
+    }
+    /// Same as `generate_proof`, but the public inputs of the generated proof are returned
+    /// as well, so that the revealed values can be read without deserializing the proof
+    pub fn generate_proof_with_pis(
+        &self,
+        inputs: RevelationRecursiveInput<L>,
+    ) -> Result<(Vec<u8>, RevelationPublicInputs<'static, F, L>)> {
+        catch_panics(|| {
+            let proof = self.generate_proof_internal(inputs)?;
+            let bytes = serialize_proof(&proof)?;
+            Ok((
+                bytes,
+                RevelationPublicInputs::from_exposed(proof.public_inputs),
+            ))
+        })
+    }
+    /// Generate the proofs for several independent inputs, e.g. queries answered against the
+    /// same block db proof. The proofs are returned in the order of the inputs, i.e. the i-th
+    /// proof is the one for `inputs[i]`. The block db proof is assigned only once to a witness
+    /// scaffold shared by consecutive inputs with the same block db proof, and the witnesses of
+    /// all the inputs are assigned before proving, so an invalid input fails the whole batch
+    /// without generating any proof. The proofs are generated one after the other, as proving
+    /// already employs all the available cores.
+    pub fn generate_proofs_batch(
+        &self,
+        inputs: Vec<RevelationRecursiveInput<L>>,
+    ) -> Result<Vec<Vec<u8>>> {
+        catch_panics(|| {
+            let mut scaffold = None;
+            let mut witnesses = Vec::with_capacity(inputs.len());
+            for input in inputs {
+                let block_db = (input.block_db_proof, input.block_db_verifier_data);
+                if !matches!(&scaffold, Some((assigned, _)) if *assigned == block_db) {
+                    let pw = assign_block_db_witness(
+                        &self.block_db,
+                        &self.block_db_verifier_datas,
+                        &block_db.0,
+                        block_db.1.as_ref(),
+                    )?;
+                    scaffold = Some((block_db, pw));
+                }
+                let (_, scaffold_pw) = scaffold.as_ref().expect("scaffold assigned above");
+                let mut pw = scaffold_pw.clone();
+                assign_query_witness(
+                    &mut pw,
+                    &self.revelation,
+                    std::slice::from_ref(&self.query2_block),
+                    &self.query2_block_circuit_set,
+                    input.logic_inputs,
+                    vec![input.query2_block_proof],
+                )?;
+                witnesses.push(pw);
+            }
+            witnesses
+                .into_iter()
+                .map(|pw| Ok(serialize_proof(&self.circuit_data.prove(pw)?)?))
+                .collect()
+        })
+    }
+    /// Same as `generate_proof`, but the proof is generated on the blocking thread pool of the
+    /// tokio runtime, so that the async tasks are not starved while proving; the parameters
+    /// are shared through an `Arc` to make the returned future `'static`
+    #[cfg(feature = "tokio")]
+    pub fn generate_proof_async(
+        self: std::sync::Arc<Self>,
+        inputs: RevelationRecursiveInput<L>,
+    ) -> impl std::future::Future<Output = Result<Vec<u8>>>
+    where
+        Self: Send + Sync,
+    {
+        async move {
+            tokio::task::spawn_blocking(move || self.generate_proof(inputs))
+                .await
+                .map_err(|err| anyhow!("revelation proof generation failed to complete: {}", err))?
+        }
     }
     pub fn circuit_data(&self) -> &CircuitData<F, C, D> {
         &self.circuit_data
@@ -205,37 +1181,494 @@ impl<const BLOCK_DB_DEPTH: usize, const L: usize> Parameters<BLOCK_DB_DEPTH, L>
     pub fn verifier_data(&self) -> VerifierCircuitData<F, C, D> {
         self.circuit_data.verifier_data()
     }
+    /// Fingerprint of the verifier data of the revelation circuit, the same returned by
+    /// `ProofWithVK::vk_fingerprint` for the proofs generated with these parameters
+    pub fn verifier_fingerprint(&self) -> [u8; VK_FINGERPRINT_LEN] {
+        vk_fingerprint(&self.circuit_data.verifier_only)
+    }
+    /// Fingerprints of the digests of the query2/block circuits whose proofs are accepted by
+    /// these parameters, i.e. the values returned by `ProofWithVK::circuit_digest_fingerprint`
+    /// for such proofs
+    pub fn accepted_query2_circuit_digests(&self) -> Vec<CircuitDigestFingerprint> {
+        self.query2_block_circuit_set
+            .circuit_digests()
+            .iter()
+            .map(circuit_digest_fingerprint)
+            .collect()
+    }
+    /// Save the parameters to the file at `path`, which is overwritten if it already exists; the
+    /// circuit sets are not saved, and they must be provided again to `load`
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let saved = SavedParametersRef {
+            revelation: &self.revelation,
+            query2_block: &self.query2_block,
+            query2_block_set_digest: self
+                .query2_block_circuit_set
+                .get_circuit_set_digest()
+                .flatten(),
+            block_db: &self.block_db,
+            block_db_verifier_datas: &self.block_db_verifier_datas,
+            num_gates: self.num_gates,
+            circuit_data: &self.circuit_data,
+        };
+        save_parameters(path, &saved)
+    }
+    /// Load the parameters saved to the file at `path` by `save`, attaching the circuit sets
+    /// provided to `build`; an error is returned if the parameters were saved for another `L`,
+    /// or if they were built for other circuit sets
+    pub fn load(
+        path: &Path,
+        query2_block_set: &RecursiveCircuits<F, C, D>,
+        block_db_circuit_set: &RecursiveCircuits<F, C, D>,
+    ) -> Result<Self> {
+        catch_panics(|| {
+            let saved: SavedParameters<L, RecursiveCircuitsVerifierTarget<D>> =
+                load_parameters(path, query2_block_set, block_db_circuit_set)?;
+            Ok(Self {
+                revelation: saved.revelation,
+                query2_block: saved.query2_block,
+                query2_block_circuit_set: query2_block_set.clone(),
+                block_db: saved.block_db,
+                block_db_verifier_datas: saved.block_db_verifier_datas,
+                num_gates: saved.num_gates,
+                circuit_data: saved.circuit_data,
+            })
+        })
+    }
+    /// Split the parameters into the data required to generate proofs and the much smaller
+    /// data required to verify them, which can be serialized and shipped independently
+    pub fn split(self) -> (ProverArtifact<BLOCK_DB_DEPTH, L>, VerifierArtifact) {
+        let verifier_artifact = VerifierArtifact {
+            verifier_data: self.circuit_data.verifier_data(),
+        };
+        let prover_artifact = ProverArtifact {
+            revelation: self.revelation,
+            query2_block: self.query2_block,
+            query2_block_circuit_set: self.query2_block_circuit_set,
+            block_db: self.block_db,
+            block_db_verifier_datas: self.block_db_verifier_datas,
+            prover_data: self.circuit_data.prover_data(),
+        };
+        (prover_artifact, verifier_artifact)
+    }
+    /// Return the parameters of the FRI configuration of the revelation circuit
+    pub fn fri_config_descriptor(&self) -> FriConfigDescriptor {
+        let fri_config = &self.circuit_data.common.config.fri_config;
+        FriConfigDescriptor {
+            rate_bits: fri_config.rate_bits,
+            cap_height: fri_config.cap_height,
+            proof_of_work_bits: fri_config.proof_of_work_bits,
+            num_query_rounds: fri_config.num_query_rounds,
+        }
+    }
+    /// Return the size of the revelation circuit
+    pub fn circuit_stats(&self) -> CircuitStats {
+        let common = &self.circuit_data.common;
+        CircuitStats {
+            num_unpadded_gates: self.num_gates,
+            num_wires: common.config.num_wires,
+            num_public_inputs: common.num_public_inputs,
+            degree_bits: common.fri_params.degree_bits,
+        }
+    }
+    /// Return the shape of these parameters
+    pub fn describe(&self) -> ParametersInfo {
+        ParametersInfo {
+            max_entries: Self::max_entries(),
+            block_db_depth: Self::block_db_depth(),
+            num_gates: self.circuit_data.common.degree(),
+        }
+    }
     pub fn verify_proof(&self, proof: Vec<u8>) -> Result<()> {
-        let proof = deserialize_proof(&proof)?;
-        self.circuit_data.verify(proof)
+        catch_panics(|| {
+            let proof = deserialize_proof(&proof)?;
+            self.check_same_l(&proof)?;
+            self.circuit_data.verify(proof)
+        })
+    }
+    /// Same as `verify_proof`, but the proof is incrementally deserialized from `reader`,
+    /// e.g., a file or a network stream, without loading its serialized form in memory
+    pub fn verify_proof_reader(&self, reader: impl Read) -> Result<()> {
+        catch_panics(|| {
+            let proof = deserialize_proof_from_reader(reader)?;
+            self.check_same_l(&proof)?;
+            self.circuit_data.verify(proof)
+        })
+    }
+    /// Deserialize a revelation proof and return its public inputs, checking that the shape of
+    /// the proof matches the one expected for the revelation circuit but without verifying the
+    /// proof; this method is meant for quick inspection of proofs, `verify_proof` must be called
+    /// before trusting the returned public inputs
+    pub fn inspect(&self, proof: &[u8]) -> Result<RevelationPublicInputs<'static, F, L>> {
+        catch_panics(|| inspect_proof(&self.circuit_data.common, proof))
+    }
+    /// Check whether `proof` has the number of public inputs and the shape expected for proofs
+    /// generated with these parameters, without verifying it; this is meant to cheaply route
+    /// a proof to the right parameters when several ones are available
+    pub fn accepts(&self, proof: &[u8]) -> bool {
+        self.inspect(proof).is_ok()
+    }
+    /// Same as `verify_proof`, but it also returns the public inputs of the verified proof
+    /// together with an estimate of the gas spent to verify it on-chain once wrapped in
+    /// Groth16, so that a cost hint can be attached to the verified result
+    pub fn verify_with_cost_estimate(
+        &self,
+        proof: Vec<u8>,
+    ) -> Result<(RevelationPublicInputs<'static, F, L>, GasEstimate)> {
+        catch_panics(|| {
+            let proof = deserialize_proof(&proof)?;
+            self.check_same_l(&proof)?;
+            self.circuit_data.verify(proof.clone())?;
+            let estimate = estimate_verification_gas(proof.public_inputs.len());
+            Ok((
+                RevelationPublicInputs::from_exposed(proof.public_inputs),
+                estimate,
+            ))
+        })
+    }
+    /// Same as `verify_proof`, but the outcome of the verification is taken from `cache`
+    /// if the same proof has already been verified with these parameters
+    pub fn verify_proof_cached(&self, proof: Vec<u8>, cache: &mut VerificationCache) -> Result<()> {
+        let circuit_digest = self.circuit_data.verifier_only.circuit_digest;
+        cache.verify_with(&proof, &circuit_digest, || self.verify_proof(proof.clone()))
+    }
+    /// Same as `verify_proof`, but it also checks that the verified proof exposes the values
+    /// found in `expected`
+    pub fn verify_proof_against(
+        &self,
+        proof: Vec<u8>,
+        expected: &RevelationExpectation,
+    ) -> Result<()> {
+        let proof = catch_panics(|| {
+            let proof = deserialize_proof(&proof)?;
+            self.check_same_l(&proof)?;
+            self.circuit_data.verify(proof.clone())?;
+            Ok(proof)
+        })?;
+        expected.check(&RevelationPublicInputs::<F, L>::from_exposed(
+            proof.public_inputs,
+        ))
+    }
+    /// Verify the revelation proof and check, natively, that the values it exposes are the
+    /// ones found in the provided `query2` and `block_db` proofs. The returned report tells
+    /// which values matched, and which ones can't be compared as the revelation proof exposes
+    /// only their commitment; an error is returned only if the revelation proof is invalid or
+    /// if any of the proofs doesn't expose the expected number of public inputs.
+    pub fn audit_linkage(
+        &self,
+        revelation_proof: &[u8],
+        query2: &ProofWithVK,
+        block_db: &ProofWithPublicInputs<F, C, D>,
+    ) -> Result<LinkageReport>
+    where
+        [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+    {
+        catch_panics(|| {
+            let revelation_proof = deserialize_proof(revelation_proof)?;
+            self.check_same_l(&revelation_proof)?;
+            self.circuit_data.verify(revelation_proof.clone())?;
+            // the query2/block proof exposes the circuit set digest after its public inputs
+            ensure!(
+                query2.proof().public_inputs.len() >= QUERY2_BLOCK_NUM_IO,
+                "query2/block proof exposes {} public inputs, while at least {} are expected",
+                query2.proof().public_inputs.len(),
+                QUERY2_BLOCK_NUM_IO,
+            );
+            ensure!(
+                block_db.public_inputs.len() >= BLOCK_DB_NUM_IO,
+                "block db proof exposes {} public inputs, while at least {} are expected",
+                block_db.public_inputs.len(),
+                BLOCK_DB_NUM_IO,
+            );
+            let revelation_pi =
+                RevelationPublicInputs::<F, L>::from_exposed(revelation_proof.public_inputs);
+            let query2_pi = block::BlockPublicInputs::<F>::from(
+                &query2.proof().public_inputs[..QUERY2_BLOCK_NUM_IO],
+            );
+            let block_db_pi = BlockDbPublicInputs::from(
+                BlockDbParameters::<BLOCK_DB_DEPTH>::block_tree_public_inputs(block_db),
+            );
+            let user_address = query2_pi.user_address();
+            // the parameters of the query, and the block range, may be exposed only through a
+            // commitment, in which case they are read as zeros and can't be compared
+            let query_exposed = !revelation_pi.is_query_committed();
+            let range_exposed = query_exposed && !revelation_pi.is_range_committed();
+            let compare = |exposed: bool, matched: bool| exposed.then_some(matched);
+
+            Ok(LinkageReport {
+                block_number: compare(
+                    range_exposed,
+                    revelation_pi.block_number() == query2_pi.block_number(),
+                ),
+                range: compare(range_exposed, revelation_pi.range() == query2_pi.range()),
+                smart_contract_address: compare(
+                    query_exposed,
+                    revelation_pi.smart_contract_address() == query2_pi.smart_contract_address(),
+                ),
+                user_address: compare(
+                    query_exposed,
+                    revelation_pi.user_address()
+                        == &user_address[user_address.len() - PACKED_ADDRESS_LEN..],
+                ),
+                mapping_slot: compare(
+                    query_exposed,
+                    revelation_pi.mapping_slot() == query2_pi.mapping_slot(),
+                ),
+                mapping_slot_length: compare(
+                    query_exposed,
+                    revelation_pi.mapping_slot_length() == query2_pi.mapping_slot_length(),
+                ),
+                root: query2_pi.root().elements.as_slice() == block_db_pi.root_data(),
+                block_header: revelation_pi.block_header() == block_db_pi.block_header_data(),
+            })
+        })
     }
 }
 
-#[cfg(test)]
-mod test {
-    use std::iter::once;
+/// Run `f`, converting the panics it raises into `RevelationError::Internal` errors if the
+/// `catch_panics` feature is enabled
+fn catch_panics<T, Op: FnOnce() -> Result<T>>(f: Op) -> Result<T> {
+    #[cfg(feature = "catch_panics")]
+    {
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(f))
+            .unwrap_or_else(|payload| Err(RevelationError::from_panic(payload).into()))
+    }
+    #[cfg(not(feature = "catch_panics"))]
+    {
+        f()
+    }
+}
 
-    use crate::{
-        api::{serialize_proof, ProofWithVK},
-        block::empty_merkle_root,
-        eth::left_pad,
-        keccak::PACKED_HASH_LEN,
-        query2::revelation::{RevelationRecursiveInput, QUERY2_BLOCK_NUM_IO},
-        types::MAPPING_KEY_LEN,
-        utils::{Packer, ToFields},
-    };
-    use anyhow::Result;
-    use ethers::types::Address;
-    use itertools::Itertools;
+/// Check that the block db proofs accepted by the revelation circuit can be generated by the
+/// circuits with verifier data `block_db_verifier_datas`, i.e. that they are at least one and
+/// they all belong to the block db circuit set
+fn check_block_db_verifier_datas(
+    block_db_circuit_set: &RecursiveCircuits<F, C, D>,
+    block_db_verifier_datas: &[VerifierOnlyCircuitData<C, D>],
+) -> Result<()> {
+    ensure!(
+        !block_db_verifier_datas.is_empty(),
+        "at least one block db verifier data must be accepted"
+    );
+    for block_db_verifier_data in block_db_verifier_datas {
+        ensure!(
+            block_db_circuit_set.contains_circuit_digest(&block_db_verifier_data.circuit_digest),
+            "block db verifier data with circuit digest {:?} does not belong to the block db circuit set",
+            block_db_verifier_data.circuit_digest,
+        );
+    }
+    Ok(())
+}
+
+/// Check that proofs of the `proof_kind` circuits verified by the revelation circuit expose
+/// the `expected` number of public inputs, computed by `source`
+fn check_io_width(proof_kind: &str, source: &str, expected: usize, found: usize) -> Result<()> {
+    ensure!(
+        expected == found,
+        "revelation parameters verify {} proofs with {} public inputs, while {} expects {}: \
+        the parameters were built with another version of the {} circuits and must be rebuilt",
+        proof_kind,
+        found,
+        source,
+        expected,
+        proof_kind,
+    );
+    Ok(())
+}
+
+/// Assign to the wires of the revelation circuit the witness required to generate a proof
+/// for `inputs`
+fn assign_witness<const L: usize>(
+    revelation: &RevelationWires<L>,
+    query2_block: &RecursiveCircuitsVerifierTarget<D>,
+    query2_block_circuit_set: &RecursiveCircuits<F, C, D>,
+    block_db: &WhitelistedCircuitsVerifierTarget<D>,
+    block_db_verifier_datas: &[VerifierOnlyCircuitData<C, D>],
+    inputs: RevelationRecursiveInput<L>,
+) -> Result<PartialWitness<F>>
+where
+    [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+{
+    let mut pw = assign_block_db_witness(
+        block_db,
+        block_db_verifier_datas,
+        &inputs.block_db_proof,
+        inputs.block_db_verifier_data.as_ref(),
+    )?;
+    assign_query_witness(
+        &mut pw,
+        revelation,
+        std::slice::from_ref(query2_block),
+        query2_block_circuit_set,
+        inputs.logic_inputs,
+        vec![inputs.query2_block_proof],
+    )?;
+    Ok(pw)
+}
+
+/// Build a witness with only the block db proof assigned, which can then be completed by
+/// `assign_query_witness` for any query answered against the same block db proof
+fn assign_block_db_witness(
+    block_db: &WhitelistedCircuitsVerifierTarget<D>,
+    block_db_verifier_datas: &[VerifierOnlyCircuitData<C, D>],
+    block_db_proof: &ProofWithPublicInputs<F, C, D>,
+    block_db_verifier_data: Option<&VerifierOnlyCircuitData<C, D>>,
+) -> Result<PartialWitness<F>> {
+    // the block db proof must be generated by one of the accepted circuits, which can be left
+    // unspecified only if a single circuit is accepted
+    let block_db_verifier_data = match block_db_verifier_data {
+        Some(verifier_data) => block_db_verifier_datas
+            .iter()
+            .find(|accepted| accepted.circuit_digest == verifier_data.circuit_digest)
+            .ok_or_else(|| {
+                anyhow!(
+                    "block db verifier data with circuit digest {:?} is not accepted by the revelation parameters",
+                    verifier_data.circuit_digest,
+                )
+            })?,
+        None => {
+            ensure!(
+                block_db_verifier_datas.len() == 1,
+                "the verifier data of the block db proof must be provided, as the revelation parameters accept {} block db circuits",
+                block_db_verifier_datas.len(),
+            );
+            &block_db_verifier_datas[0]
+        }
+    };
+    let mut pw = PartialWitness::new();
+    // assigns the block db proof, whitelisted verifier target
+    block_db.set_target(&mut pw, block_db_proof, block_db_verifier_data);
+    Ok(pw)
+}
+
+/// Assign to `pw` the query2/block proofs, one for each of the `query2_blocks` verifier
+/// targets, and the values of the revelation circuit logic
+fn assign_query_witness<const L: usize>(
+    pw: &mut PartialWitness<F>,
+    revelation: &RevelationWires<L>,
+    query2_blocks: &[RecursiveCircuitsVerifierTarget<D>],
+    query2_block_circuit_set: &RecursiveCircuits<F, C, D>,
+    logic_inputs: RevelationCircuit<L>,
+    query2_block_proofs: Vec<ProofWithVK>,
+) -> Result<()>
+where
+    [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+{
+    ensure!(
+        revelation.salt.is_some() == logic_inputs.salt.is_some(),
+        "a salt must be provided if and only if the revelation parameters are built with a salt"
+    );
+    ensure!(
+        revelation.range_blinding.is_some() == logic_inputs.range_blinding.is_some(),
+        "a range blinding must be provided if and only if the revelation parameters hide the block range"
+    );
+    ensure!(
+        query2_block_proofs.len() == query2_blocks.len(),
+        "the revelation parameters verify {} query2/block proofs, but {} were provided",
+        query2_blocks.len(),
+        query2_block_proofs.len(),
+    );
+    // the query2/block proofs must be generated by one of the accepted circuits; this is
+    // checked before assigning them to report a proper error instead of a failed proving
+    for query2_block_proof in query2_block_proofs.iter() {
+        let circuit_digest = &query2_block_proof.verifier_data().circuit_digest;
+        if !query2_block_circuit_set.contains_circuit_digest(circuit_digest) {
+            return Err(RevelationError::UnknownQuery2Circuit {
+                fingerprint: circuit_digest_fingerprint(circuit_digest),
+            }
+            .into());
+        }
+    }
+    // assigns the query2/block proofs, recursive verifier targets
+    for (query2_block, query2_block_proof) in query2_blocks.iter().zip(query2_block_proofs) {
+        let (proof, vd) = query2_block_proof.into_parts();
+        query2_block.set_target(pw, query2_block_circuit_set, &proof, &vd)?;
+    }
+    // assigns the regular wires
+    logic_inputs.assign(pw, revelation);
+    Ok(())
+}
+
+impl<const BLOCK_DB_DEPTH: usize, const L: usize> ProverArtifact<BLOCK_DB_DEPTH, L> {
+    pub fn generate_proof(&self, inputs: RevelationRecursiveInput<L>) -> Result<Vec<u8>>
+    where
+        [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+    {
+        catch_panics(|| {
+            let pw = assign_witness(
+                &self.revelation,
+                &self.query2_block,
+                &self.query2_block_circuit_set,
+                &self.block_db,
+                &self.block_db_verifier_datas,
+                inputs,
+            )?;
+            let proof = self.prover_data.prove(pw)?;
+            Ok(serialize_proof(&proof)?)
+        })
+    }
+}
+
+/// Verify a serialized revelation proof with the verifier data `vd` of the revelation circuit,
+/// as returned by `Parameters::verifier_data`; this allows to verify proofs in a process which
+/// holds only the verifier data, without building or loading the revelation `Parameters`
+pub fn verify_revelation_proof(vd: &VerifierCircuitData<F, C, D>, proof: &[u8]) -> Result<()> {
+    catch_panics(|| {
+        let proof = deserialize_proof(proof)?;
+        vd.verify(proof)
+    })
+}
+
+impl VerifierArtifact {
+    pub fn verifier_data(&self) -> &VerifierCircuitData<F, C, D> {
+        &self.verifier_data
+    }
+    pub fn verify_proof(&self, proof: Vec<u8>) -> Result<()> {
+        verify_revelation_proof(&self.verifier_data, &proof)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        env,
+        io::Cursor,
+        iter::once,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use crate::{
+        api::{
+            fingerprints_eq, serialize_proof, serialize_public_inputs, test_config, ProofWithVK,
+        },
+        block::{empty_merkle_root, BlockDbMergeWires, NUM_STATE_PUBLIC_INPUTS},
+        eth::left_pad,
+        query2::revelation::{
+            test_utils::Query2Values, RevelationRecursiveInput, QUERY2_BLOCK_NUM_IO,
+        },
+        types::MAPPING_KEY_LEN,
+        utils::{keccak256, Packer, ToFields},
+    };
+    use anyhow::Result;
+    use ethers::types::{Address, U256};
+    use itertools::Itertools;
     use plonky2::{
         field::{
             goldilocks_field::GoldilocksField,
             types::{Field, PrimeField64, Sample},
         },
         hash::hash_types::{HashOut, NUM_HASH_OUT_ELTS},
+        plonk::config::GenericHashOut,
+    };
+    use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+    use recursion_framework::{
+        framework::prepare_recursive_circuit_for_circuit_set,
+        framework_testing::{new_universal_circuit_builder_for_testing, TestingRecursiveCircuits},
+        serialization::FromBytes,
     };
-    use rand::{thread_rng, Rng};
-    use recursion_framework::framework_testing::TestingRecursiveCircuits;
     use serial_test::serial;
 
     use super::*;
@@ -247,6 +1680,199 @@ mod test {
         query2::block::BlockPublicInputs,
     };
 
+    const BLOCK_DB_DEPTH: usize = 2;
+
+    /// Number of revelation `Parameters` built so far, to check which entry points build them
+    pub(super) static NUM_PARAMETERS_BUILT: AtomicUsize = AtomicUsize::new(0);
+
+    /// Fake query2/block and block db circuit sets employed to generate the proofs
+    /// recursively verified by the revelation circuit
+    struct TestingCircuits {
+        query2: TestingRecursiveCircuits<F, C, D, QUERY2_BLOCK_NUM_IO>,
+        block_db: TestingRecursiveCircuits<F, C, D, BLOCK_DB_NUM_IO>,
+        /// Configuration of the circuits in both sets, which the revelation circuit must be
+        /// built with as well
+        config: CircuitConfig,
+    }
+
+    /// Set of proofs and values employed to generate a revelation proof
+    struct TestInputs {
+        mapping_keys: Vec<Vec<u8>>,
+        query_min_block: usize,
+        query_max_block: usize,
+        query2_values: Query2Values,
+        query2_proof: ProofWithVK,
+        block_db_proof: ProofWithPublicInputs<F, C, D>,
+    }
+
+    impl TestingCircuits {
+        /// Circuit sets built with `test_config` to speed up proving; the parameters for them
+        /// must be built with `build_params` or `params_builder`, which employ the same
+        /// configuration
+        fn new() -> Self {
+            Self::new_with_config(test_config())
+        }
+
+        /// Circuit sets built with `config`, e.g. `default_config` for the tests building the
+        /// parameters with `Parameters::build`
+        fn new_with_config(config: CircuitConfig) -> Self {
+            let query2_builder =
+                new_universal_circuit_builder_for_testing::<F, C, D, QUERY2_BLOCK_NUM_IO>(
+                    config.clone(),
+                    0,
+                );
+            let block_db_builder =
+                new_universal_circuit_builder_for_testing::<F, C, D, BLOCK_DB_NUM_IO>(
+                    config.clone(),
+                    0,
+                );
+            Self {
+                query2: TestingRecursiveCircuits::new(&query2_builder, vec![]),
+                block_db: TestingRecursiveCircuits::new(&block_db_builder, vec![]),
+                config,
+            }
+        }
+
+        fn params_builder(&self) -> ParametersBuilder<BLOCK_DB_DEPTH> {
+            ParametersBuilder::<BLOCK_DB_DEPTH>::new(
+                self.query2.get_recursive_circuit_set(),
+                self.block_db.get_recursive_circuit_set(),
+                self.block_db.verifier_data_for_input_proofs::<1>()[0],
+            )
+            .with_config(self.config.clone())
+        }
+
+        fn build_params<const L: usize>(&self) -> Parameters<BLOCK_DB_DEPTH, L> {
+            self.params_builder().build_for::<L>().unwrap()
+        }
+
+        /// Generate a fake block db proof and a fake query2/block proof, taking some inputs
+        /// from the block db, over the given mapping keys
+        fn generate_inputs(&self, mapping_keys: &[[u8; MAPPING_KEY_LEN]]) -> Result<TestInputs> {
+            self.generate_inputs_for_depth::<BLOCK_DB_DEPTH>(mapping_keys)
+        }
+
+        /// Same as `generate_inputs`, for a block db of depth `DEPTH`
+        fn generate_inputs_for_depth<const DEPTH: usize>(
+            &self,
+            mapping_keys: &[[u8; MAPPING_KEY_LEN]],
+        ) -> Result<TestInputs> {
+            // Generate a fake block db proof
+            let last_root = HashOut {
+                elements: F::rand_vec(NUM_HASH_OUT_ELTS).try_into().unwrap(),
+            };
+            let init_block_number = F::from_canonical_u32(thread_rng().gen::<u32>());
+            let db_range = 555;
+            let last_block_number = init_block_number + F::from_canonical_usize(db_range);
+            let block_db_inputs = test_utils::block_db_public_inputs::<DEPTH>(
+                &last_root,
+                init_block_number,
+                last_block_number,
+                &thread_rng().gen(),
+            );
+            let block_db_pi = BlockDbPublicInputs::<GoldilocksField>::from(&block_db_inputs);
+            let block_db_proof = self
+                .block_db
+                .generate_input_proofs::<1>([block_db_inputs.clone()])?[0]
+                .clone();
+
+            // Generate a fake query2/block proof, taking some inputs from the block db
+            // block range asked is just one block less than latest block in db
+            let query_max_number = block_db_pi.block_number_data() - F::ONE;
+            let query_range = F::from_canonical_usize(10);
+            let query_min_number = query_max_number - query_range + F::ONE;
+            let query2_values = Query2Values {
+                block_number: query_max_number,
+                range: query_range,
+                root: HashOut {
+                    elements: block_db_pi.root_data().try_into().unwrap(),
+                },
+                smc_address: Address::random(),
+                user_address: Address::random(),
+                mapping_slot: F::rand(),
+                length_slot: F::rand(),
+            };
+            let query2_proof = self.generate_query2_proof(&query2_values, mapping_keys)?;
+
+            Ok(TestInputs {
+                mapping_keys: mapping_keys.iter().map(|x| x.to_vec()).collect(),
+                query_min_block: query_min_number.to_canonical_u64() as usize,
+                query_max_block: query_max_number.to_canonical_u64() as usize,
+                query2_values,
+                query2_proof,
+                block_db_proof,
+            })
+        }
+
+        fn generate_query2_proof(
+            &self,
+            values: &Query2Values,
+            mapping_keys: &[[u8; MAPPING_KEY_LEN]],
+        ) -> Result<ProofWithVK> {
+            let query2_block_proof = self
+                .query2
+                .generate_input_proofs([values.public_inputs(mapping_keys)])?;
+            let query2_block_vd = self.query2.verifier_data_for_input_proofs::<1>();
+
+            Ok(ProofWithVK::from_parts(
+                query2_block_proof[0].clone(),
+                query2_block_vd[0].clone(),
+            ))
+        }
+    }
+
+    impl TestInputs {
+        fn revelation_input<const L: usize>(&self) -> Result<RevelationRecursiveInput<L>> {
+            RevelationRecursiveInput::<L>::new(
+                self.mapping_keys.clone(),
+                self.query_min_block,
+                self.query_max_block,
+                self.query2_proof.serialize()?,
+                serialize_proof(&self.block_db_proof)?,
+            )
+        }
+    }
+
+    /// Build revelation public inputs exposing only the given NFT IDs, number of matching
+    /// keys and revealed window, all the other public inputs being zero
+    fn crafted_public_inputs<const L: usize>(
+        ids: [u32; L],
+        total: u8,
+        page: (u8, u8),
+    ) -> RevelationPublicInputs<'static, F, L> {
+        let region = |region: &str| {
+            revelation_layout(L)
+                .into_iter()
+                .find(|(name, _)| *name == region)
+                .unwrap()
+                .1
+        };
+        let mut inputs = vec![F::ZERO; RevelationPublicInputs::<F, L>::total_len()];
+        inputs[region("nft_ids")]
+            .iter_mut()
+            .zip(ids)
+            .for_each(|(input, id)| *input = F::from_canonical_u32(id));
+        inputs[region("total_matching").start] = F::from_canonical_u8(total);
+        inputs[region("page_offset").start] = F::from_canonical_u8(page.0);
+        inputs[region("page_limit").start] = F::from_canonical_u8(page.1);
+        RevelationPublicInputs::<F, L>::from(inputs)
+    }
+
+    /// Generate `n` distinct random mapping keys
+    fn random_mapping_keys(n: usize) -> Vec<[u8; MAPPING_KEY_LEN]> {
+        let mut rng = thread_rng();
+        let mut ids = Vec::with_capacity(n);
+        while ids.len() < n {
+            let id = rng.gen::<u8>();
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        ids.into_iter()
+            .map(|id| left_pad::<MAPPING_KEY_LEN>(&[id]))
+            .collect()
+    }
+
     #[test]
     #[serial]
     fn test_revelation_api() -> Result<()> {
@@ -263,12 +1889,11 @@ mod test {
         let block_db_vk = block_db_testing_framework.verifier_data_for_input_proofs::<1>()[0];
         // Build the params
         const L: usize = 2;
-        const BLOCK_DB_DEPTH: usize = 2;
         let params = super::Parameters::<BLOCK_DB_DEPTH, L>::build(
             query2_block_circuit_set,
             block_db_circuit_set,
             block_db_vk,
-        );
+        )?;
 
         // Generate a fake block db proof
         let init_root = empty_merkle_root::<GoldilocksField, 2, BLOCK_DB_DEPTH>();
@@ -278,14 +1903,15 @@ mod test {
         let init_block_number = F::from_canonical_u32(thread_rng().gen::<u32>());
         let db_range = 555;
         let last_block_number = init_block_number + F::from_canonical_usize(db_range);
-        let last_block_hash = F::rand_vec(PACKED_HASH_LEN);
+        // block hash is exposed as u32 limbs
+        let last_block_hash = BlockDbPublicInputs::from_block_hash_bytes(&thread_rng().gen());
 
         let block_db_inputs: [F; BLOCK_DB_NUM_IO] = BlockDbPublicInputs::from_parts(
             &init_root.elements,
             &last_root.elements,
             init_block_number,
             last_block_number,
-            &last_block_hash.try_into().unwrap(),
+            &last_block_hash,
         )
         .into_iter()
         .chain(once(F::ONE))
@@ -308,9 +1934,8 @@ mod test {
         let user_address = Address::random();
         let mapping_slot = F::rand();
         let length_slot = F::rand();
-        let mapping_keys = (0..L)
-            .map(|_| left_pad::<MAPPING_KEY_LEN>(&[thread_rng().gen::<u8>()]))
-            .collect::<Vec<_>>();
+        // mapping keys must be distinct, as repeated keys are revealed only once
+        let mapping_keys = random_mapping_keys(L);
         let packed_field_mks = mapping_keys
             .iter()
             .map(|x| x.pack().to_fields())
@@ -362,4 +1987,2381 @@ mod test {
         params.verify_proof(proof)?;
         Ok(())
     }
+
+    #[test]
+    #[serial]
+    fn test_revelation_verify_proof_reader() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let proof = params.generate_proof(inputs.revelation_input::<L>()?)?;
+        params.verify_proof_reader(Cursor::new(&proof))?;
+        // a truncated proof is rejected
+        assert!(params
+            .verify_proof_reader(Cursor::new(&proof[..proof.len() / 2]))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_inspect() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let proof = params.generate_proof(inputs.revelation_input::<L>()?)?;
+
+        // inspection must return the same public inputs of the verified proof
+        let verified_proof = deserialize_proof::<F, C, D>(&proof)?;
+        params.circuit_data().verify(verified_proof.clone())?;
+        let pis = params.inspect(&proof)?;
+        assert_eq!(pis.inputs.as_ref(), verified_proof.public_inputs.as_slice());
+        pis.assert_strictly_increasing()?;
+        pis.verify_count_consistency()?;
+        // a proof for another circuit is rejected
+        let block_db_proof = serialize_proof(&inputs.block_db_proof)?;
+        assert!(params.inspect(&block_db_proof).is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_verify_with_cost_estimate() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let proof = params.generate_proof(inputs.revelation_input::<L>()?)?;
+        let (verified_pis, estimate) = params.verify_with_cost_estimate(proof.clone())?;
+        assert_eq!(
+            estimate,
+            estimate_verification_gas(Parameters::<BLOCK_DB_DEPTH, L>::num_public_inputs())
+        );
+        assert_eq!(verified_pis.inputs, params.inspect(&proof)?.inputs);
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_verification_cache() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let proof = params.generate_proof(inputs.revelation_input::<L>()?)?;
+
+        // second verification of the same proof is served from the cache
+        let mut cache = VerificationCache::new(4);
+        params.verify_proof_cached(proof.clone(), &mut cache)?;
+        assert_eq!(cache.hits(), 0);
+        params.verify_proof_cached(proof, &mut cache)?;
+        assert_eq!(cache.hits(), 1);
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_io_widths() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        params.assert_io_widths()?;
+
+        // the widths verified by the parameters are the ones of the public inputs layouts and of
+        // the actual query2/block and block db proofs
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let query2_block_width = params.query2_block.num_public_inputs();
+        assert_eq!(
+            query2_block_width,
+            block::BlockPublicInputs::<Target>::total_len()
+        );
+        assert_eq!(
+            query2_block_width,
+            inputs.query2_proof.proof.public_inputs.len()
+        );
+        let block_db_width = params.block_db.get_proof_target().public_inputs.len();
+        assert_eq!(block_db_width, NUM_IVC_PUBLIC_INPUTS);
+        assert_eq!(block_db_width, inputs.block_db_proof.public_inputs.len());
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_save_load() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let path = env::temp_dir().join("revelation_params.bin");
+        params.save(&path)?;
+
+        let query2_block_set = circuits.query2.get_recursive_circuit_set();
+        let block_db_set = circuits.block_db.get_recursive_circuit_set();
+        let loaded = Parameters::<BLOCK_DB_DEPTH, L>::load(&path, query2_block_set, block_db_set)?;
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let proof = loaded.generate_proof(inputs.revelation_input::<L>()?)?;
+        loaded.verify_proof(proof.clone())?;
+        params.verify_proof(proof)?;
+
+        // loading with other circuit sets, or for another L, fails
+        assert!(Parameters::<BLOCK_DB_DEPTH, L>::load(&path, block_db_set, block_db_set).is_err());
+        assert!(
+            Parameters::<BLOCK_DB_DEPTH, L>::load(&path, query2_block_set, query2_block_set)
+                .is_err()
+        );
+        assert!(
+            Parameters::<BLOCK_DB_DEPTH, 3>::load(&path, query2_block_set, block_db_set).is_err()
+        );
+        assert_eq!(loaded.circuit_stats(), params.circuit_stats());
+
+        // parameters saved with another format version are rejected
+        let mut bytes = std::fs::read(&path)?;
+        bytes[0] = SAVED_PARAMETERS_VERSION + 1;
+        std::fs::write(&path, bytes)?;
+        let err = Parameters::<BLOCK_DB_DEPTH, L>::load(&path, query2_block_set, block_db_set)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("format version"), "{err}");
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_page() -> Result<()> {
+        const L: usize = 4;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let input = inputs.revelation_input::<L>()?.with_page(1, 2);
+        // NFT IDs are the last limb of the sorted packed keys
+        let sorted_ids = input
+            .logic_inputs
+            .packed_keys
+            .map(|key| F::from_canonical_u32(key[PACKED_MAPPING_KEY_LEN - 1]));
+        let proof = params.generate_proof(input)?;
+        params.verify_proof(proof.clone())?;
+
+        // only the IDs in the window are revealed, while the total counts all the matching keys
+        let pis = params.inspect(&proof)?;
+        assert_eq!(
+            pis.nft_ids(),
+            &[F::ZERO, sorted_ids[1], sorted_ids[2], F::ZERO]
+        );
+        assert_eq!(pis.total_matching(), F::from_canonical_usize(L));
+        assert_eq!(pis.page_offset(), F::ONE);
+        assert_eq!(pis.page_limit(), F::TWO);
+        assert_eq!(pis.revealed_window(), 1..3);
+        pis.assert_strictly_increasing()?;
+        pis.verify_count_consistency()?;
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_split_parameters() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let (prover, verifier) = circuits.build_params::<L>().split();
+
+        // each artifact can be shipped on its own
+        let prover: ProverArtifact<BLOCK_DB_DEPTH, L> =
+            bincode::deserialize(&bincode::serialize(&prover)?)?;
+        let verifier: VerifierArtifact = bincode::deserialize(&bincode::serialize(&verifier)?)?;
+
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let proof = prover.generate_proof(inputs.revelation_input::<L>()?)?;
+        verifier.verify_proof(proof)
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_random_inputs() -> Result<()> {
+        const L: usize = 3;
+        for seed in 0..3 {
+            let rng = &mut StdRng::seed_from_u64(seed);
+            let (params, input) = test_utils::random_query2_inputs::<BLOCK_DB_DEPTH, L>(rng)?;
+            let proof = params.generate_proof(input)?;
+            params.verify_proof(proof)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_build_verifier_only() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new_with_config(default_config());
+        let params = circuits.build_params::<L>();
+        // the proving parameters are not built to get the verifier data
+        let num_parameters_built = NUM_PARAMETERS_BUILT.load(Ordering::SeqCst);
+        let verifier = Parameters::<BLOCK_DB_DEPTH, L>::build_verifier_only(
+            circuits.query2.get_recursive_circuit_set(),
+            circuits.block_db.get_recursive_circuit_set(),
+            circuits.block_db.verifier_data_for_input_proofs::<1>()[0],
+        )?;
+        assert_eq!(
+            NUM_PARAMETERS_BUILT.load(Ordering::SeqCst),
+            num_parameters_built
+        );
+        assert_eq!(
+            verifier.verifier_data().verifier_only,
+            params.circuit_data().verifier_only
+        );
+        assert_eq!(
+            verifier.verifier_data().common.num_public_inputs,
+            Parameters::<BLOCK_DB_DEPTH, L>::num_public_inputs()
+        );
+
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let proof = params.generate_proof(inputs.revelation_input::<L>()?)?;
+        verifier.verify_proof(proof)
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_verify_with_verifier_data() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let proof = params.generate_proof(inputs.revelation_input::<L>()?)?;
+
+        // the verifier data is reloaded on its own, without the parameters
+        let vd_bytes = ToBytes::to_bytes(&params.verifier_data());
+        drop(params);
+        let vd = <VerifierCircuitData<F, C, D> as FromBytes>::from_bytes(&vd_bytes)
+            .map_err(|_| anyhow!("failed to deserialize the verifier data"))?;
+        verify_revelation_proof(&vd, &proof)?;
+
+        let mut tampered_proof = deserialize_proof::<F, C, D>(&proof)?;
+        tampered_proof.public_inputs[0] += F::ONE;
+        assert!(verify_revelation_proof(&vd, &serialize_proof(&tampered_proof)?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_commitment_hash() -> Result<()> {
+        const L: usize = 4;
+        let circuits = TestingCircuits::new();
+        // commit to less keys than L to check that padding keys are ignored
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L - 1))?;
+
+        for commitment_hash in [CommitmentHash::Poseidon, CommitmentHash::Keccak] {
+            let params = circuits
+                .params_builder()
+                .with_commitment_hash(commitment_hash)
+                .build_for::<L>()?;
+            let input = inputs.revelation_input::<L>()?;
+            let expected_commitment = input
+                .logic_inputs
+                .expected_result_commitment(commitment_hash);
+            let proof = params.generate_proof(input)?;
+            params.verify_proof(proof.clone())?;
+
+            let pis = params.inspect(&proof)?;
+            assert_eq!(pis.commitment_hash()?, commitment_hash);
+            assert_eq!(pis.raw_result_commitment(), expected_commitment.as_slice());
+        }
+
+        // the Keccak commitment can be recomputed from the revealed keys alone
+        let input = inputs.revelation_input::<L>()?;
+        let num_entries = input.logic_inputs.num_entries;
+        let preimage = once(num_entries as u32)
+            .flat_map(u32::to_be_bytes)
+            .chain(
+                input
+                    .logic_inputs
+                    .packed_keys
+                    .iter()
+                    .take(num_entries as usize)
+                    .flat_map(|key| key.iter().flat_map(|limb| limb.to_le_bytes())),
+            )
+            .chain(std::iter::repeat(0).take((L - num_entries as usize) * MAPPING_KEY_LEN))
+            .collect_vec();
+        assert_eq!(
+            input.logic_inputs.keccak_result_commitment().to_vec(),
+            keccak256(&preimage)
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_salt() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits
+            .params_builder()
+            .with_commitment_hash(CommitmentHash::Poseidon)
+            .with_salt_policy(SaltPolicy::Exposed)
+            .build_for::<L>()?;
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        // salted parameters require a salt
+        assert!(params
+            .generate_proof(inputs.revelation_input::<L>()?)
+            .is_err());
+
+        let salts = [F::rand(), F::rand()];
+        let pis = salts
+            .iter()
+            .map(|salt| {
+                let input = inputs.revelation_input::<L>()?.with_salt(*salt);
+                let expected_commitment = input
+                    .logic_inputs
+                    .expected_result_commitment(CommitmentHash::Poseidon);
+                let proof = params.generate_proof(input)?;
+                params.verify_proof(proof.clone())?;
+                let pis = params.inspect(&proof)?;
+                assert_eq!(pis.salt(), *salt);
+                assert_eq!(pis.raw_result_commitment(), expected_commitment.as_slice());
+                Ok(pis)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        // the same keys are revealed under different commitments
+        assert_eq!(pis[0].nft_ids(), pis[1].nft_ids());
+        assert_ne!(
+            pis[0].raw_result_commitment(),
+            pis[1].raw_result_commitment()
+        );
+
+        // the Keccak commitment is salted as well
+        let keccak_commitments = salts
+            .iter()
+            .map(|salt| {
+                Ok(inputs
+                    .revelation_input::<L>()?
+                    .with_salt(*salt)
+                    .logic_inputs
+                    .keccak_result_commitment())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        assert_ne!(keccak_commitments[0], keccak_commitments[1]);
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_hidden_block_range() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits
+            .params_builder()
+            .with_block_range_visibility(BlockRangeVisibility::Committed)
+            .build_for::<L>()?;
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        // parameters hiding the block range require a blinding factor
+        assert!(params
+            .generate_proof(inputs.revelation_input::<L>()?)
+            .is_err());
+
+        let input = inputs
+            .revelation_input::<L>()?
+            .with_range_blinding(F::rand());
+        let expected_commitment = input.logic_inputs.expected_range_commitment();
+        let proof = params.generate_proof(input)?;
+        params.verify_proof(proof.clone())?;
+        let pis = params.inspect(&proof)?;
+        assert!(pis.is_range_committed());
+        assert_eq!(pis.range_commitment(), expected_commitment);
+        // neither the block numbers of the query nor the ones of the query2/block proof are
+        // found in the public inputs
+        for block_number in [
+            F::from_canonical_usize(inputs.query_min_block),
+            F::from_canonical_usize(inputs.query_max_block),
+            inputs.query2_values.block_number,
+        ] {
+            assert!(!pis.inputs.contains(&block_number));
+        }
+        assert!(pis.assert_range_eq(0, 0).is_err());
+        assert!(pis.min_block().is_err() && pis.max_block().is_err());
+        assert_eq!(pis.summary().min_block, None);
+
+        // the proof is still bound to the block db and to the range commitment
+        for region in ["block_header", "range_commitment"] {
+            let (_, range) = revelation_layout(L)
+                .into_iter()
+                .find(|(name, _)| *name == region)
+                .unwrap();
+            let mut tampered_proof = deserialize_proof::<F, C, D>(&proof)?;
+            tampered_proof.public_inputs[range.start] += F::ONE;
+            assert!(params
+                .verify_proof(serialize_proof(&tampered_proof)?)
+                .is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_expected_sorted_packed_keys() -> Result<()> {
+        const L: usize = 4;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        // fewer keys than slots, so that the padding is checked as well
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L - 1))?;
+        let proof = params.generate_proof(inputs.revelation_input::<L>()?)?;
+        params.verify_proof(proof.clone())?;
+        let pis = params.inspect(&proof)?;
+
+        let expected = test_utils::expected_sorted_packed_keys::<L>(&inputs.mapping_keys);
+        let revealed_ids = pis
+            .nft_ids()
+            .iter()
+            .map(|id| id.to_canonical_u64() as u32)
+            .collect_vec();
+        let expected_ids = expected
+            .iter()
+            .map(|key| *key.last().unwrap())
+            .collect_vec();
+        assert_eq!(revealed_ids, expected_ids);
+        assert_eq!(expected[L - 1], [0; PACKED_MAPPING_KEY_LEN]);
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_sorted_keys_sharing_last_limb() -> Result<()> {
+        const L: usize = 4;
+        // two distinct keys with the same last limb, i.e. the same NFT ID
+        let first_key = [1u8; MAPPING_KEY_LEN];
+        let mut second_key = first_key;
+        second_key[0] = 2;
+
+        // both keys are kept when sorting
+        let mapping_keys = vec![second_key.to_vec(), first_key.to_vec()];
+        let sorted_keys = sort_packed_keys(&pack_mapping_keys(&mapping_keys)?);
+        assert_eq!(sorted_keys.len(), 2);
+
+        // and both of them are revealed by the proof
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let inputs = circuits.generate_inputs(&[second_key, first_key])?;
+        let proof = params.generate_proof(inputs.revelation_input::<L>()?)?;
+        params.verify_proof(proof.clone())?;
+        let pis = params.inspect(&proof)?;
+        assert_eq!(pis.total_matching(), F::from_canonical_usize(2));
+        let id = F::from_canonical_u32(*first_key.pack().last().unwrap());
+        assert_eq!(pis.nft_ids(), &[id, id, F::ZERO, F::ZERO]);
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_keys_sorted_on_all_limbs() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        // the key with the smaller NFT ID has the greater first limb, so it must come last
+        let mut smaller_id_key = [0u8; MAPPING_KEY_LEN];
+        smaller_id_key[3] = 2;
+        smaller_id_key[MAPPING_KEY_LEN - 1] = 1;
+        let mut greater_id_key = [0u8; MAPPING_KEY_LEN];
+        greater_id_key[3] = 1;
+        greater_id_key[MAPPING_KEY_LEN - 1] = 5;
+        let inputs = circuits.generate_inputs(&[smaller_id_key, greater_id_key])?;
+
+        let proof = params.generate_proof(inputs.revelation_input::<L>()?)?;
+        params.verify_proof(proof.clone())?;
+        let pis = params.inspect(&proof)?;
+        let expected_ids = [greater_id_key, smaller_id_key]
+            .map(|key| F::from_canonical_u32(*key.pack().last().unwrap()));
+        assert_eq!(pis.nft_ids(), &expected_ids);
+        let expected = test_utils::expected_sorted_packed_keys::<L>(&inputs.mapping_keys);
+        assert_eq!(expected[0].as_slice(), greater_id_key.pack().as_slice());
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_keys_sorted_in_numeric_order() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        // the keys differ only in the first limb: the key with a non-zero byte 0 is the
+        // greater one, even though its limb packed in little-endian order is the smaller one
+        let mut greater_key = [0u8; MAPPING_KEY_LEN];
+        greater_key[0] = 1;
+        greater_key[MAPPING_KEY_LEN - 1] = 1;
+        let mut smaller_key = [0u8; MAPPING_KEY_LEN];
+        smaller_key[3] = 2;
+        smaller_key[MAPPING_KEY_LEN - 1] = 2;
+        assert!(greater_key.pack()[0] < smaller_key.pack()[0]);
+        let inputs = circuits.generate_inputs(&[greater_key, smaller_key])?;
+        let expected = test_utils::expected_sorted_packed_keys::<L>(&inputs.mapping_keys);
+        assert_eq!(expected[0].as_slice(), smaller_key.pack().as_slice());
+
+        let input = inputs.revelation_input::<L>()?;
+        assert_eq!(input.logic_inputs.packed_keys, expected);
+        let proof = params.generate_proof(input)?;
+        params.verify_proof(proof.clone())?;
+        let pis = params.inspect(&proof)?;
+        let expected_ids = [smaller_key, greater_key]
+            .map(|key| F::from_canonical_u32(*key.pack().last().unwrap()));
+        assert_eq!(pis.nft_ids(), &expected_ids);
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_invalid_block_range() -> Result<()> {
+        // an inverted range is rejected before the proofs are deserialized
+        assert!(RevelationRecursiveInput::<2>::new(vec![], 100, 50, vec![], vec![]).is_err());
+
+        let circuits = TestingCircuits::new();
+        let inputs = circuits.generate_inputs(&random_mapping_keys(2))?;
+        let new_input = |min: usize, max: usize| -> Result<RevelationRecursiveInput<2>> {
+            RevelationRecursiveInput::new(
+                inputs.mapping_keys.clone(),
+                min,
+                max,
+                inputs.query2_proof.serialize()?,
+                serialize_proof(&inputs.block_db_proof)?,
+            )
+        };
+        let (min, max) = (inputs.query_min_block, inputs.query_max_block);
+        new_input(min, max)?;
+        // a range including the blocks covered by the query2/block proof is accepted
+        new_input(min - 1, max + 1)?;
+        // ranges leaving out some of the blocks covered by the proof are rejected
+        assert!(new_input(min + 1, max).is_err());
+        assert!(new_input(min, max - 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_query_outside_block_db() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let block_db_pi =
+            BlockDbPublicInputs::from(&inputs.block_db_proof.public_inputs[..BLOCK_DB_NUM_IO]);
+        let db_min = block_db_pi.first_block_number_data().to_canonical_u64() as usize;
+        let db_max = block_db_pi.block_number_data().to_canonical_u64() as usize;
+        let new_input = |min: usize, max: usize| -> Result<RevelationRecursiveInput<L>> {
+            RevelationRecursiveInput::new(
+                inputs.mapping_keys.clone(),
+                min,
+                max,
+                inputs.query2_proof.serialize()?,
+                serialize_proof(&inputs.block_db_proof)?,
+            )
+        };
+        // the whole range of the block db can be queried
+        let proof = params.generate_proof(new_input(db_min, db_max)?)?;
+        params.verify_proof(proof)?;
+        // ranges going beyond the blocks of the block db are rejected
+        for (min, max) in [(db_min - 1, db_max), (db_min, db_max + 1)] {
+            let err = new_input(min, max).err().unwrap();
+            assert!(err.to_string().contains("block db"), "{err}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_revelation_too_many_entries() {
+        const L: usize = 2;
+        let mapping_keys = random_mapping_keys(L + 1)
+            .into_iter()
+            .map(|key| key.to_vec())
+            .collect_vec();
+        // the number of entries is checked before the proofs are deserialized
+        let err = RevelationRecursiveInput::<L>::new(mapping_keys, 0, 0, vec![], vec![])
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.downcast_ref::<RevelationError>(),
+            Some(&RevelationError::TooManyEntries { got: L + 1, max: L })
+        );
+    }
+
+    #[test]
+    fn test_revelation_oversized_key() {
+        const L: usize = 2;
+        let mapping_keys = vec![vec![1u8; MAPPING_KEY_LEN], vec![1u8; MAPPING_KEY_LEN + 1]];
+        // oversized keys are rejected before the proofs are deserialized
+        let err = RevelationRecursiveInput::<L>::new(mapping_keys, 0, 0, vec![], vec![])
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("cannot left pad"), "{err}");
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_wrong_block_db_proof() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        inputs.revelation_input::<L>()?;
+        // a proof generated by another circuit is rejected when loading the inputs
+        let err = RevelationRecursiveInput::<L>::new(
+            inputs.mapping_keys.clone(),
+            inputs.query_min_block,
+            inputs.query_max_block,
+            inputs.query2_proof.serialize()?,
+            serialize_proof(inputs.query2_proof.proof())?,
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("public inputs"), "{err}");
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_query_commitment() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let values = &inputs.query2_values;
+        // the block hash is not committed to, so it doesn't matter here
+        let query = Query {
+            contract_address: values.smc_address,
+            user_address: values.user_address,
+            mapping_slot: values.mapping_slot.to_canonical_u64(),
+            length_slot: values.length_slot.to_canonical_u64(),
+            min_block_number: inputs.query_min_block as u32,
+            max_block_number: inputs.query_max_block as u32,
+            block_hash: U256::zero(),
+        };
+        for commitment_hash in [CommitmentHash::Poseidon, CommitmentHash::Keccak] {
+            let params = circuits
+                .params_builder()
+                .with_commitment_hash(commitment_hash)
+                .with_salt_policy(SaltPolicy::Unsalted)
+                .with_query_commitment(QueryCommitment::Enabled)
+                .build_for::<L>()?;
+            let proof = params.generate_proof(inputs.revelation_input::<L>()?)?;
+            params.verify_proof(proof.clone())?;
+            // the parameters of the query are left out of the public inputs
+            assert_eq!(
+                deserialize_proof::<F, C, D>(&proof)?.public_inputs.len(),
+                mrp2_public_inputs::revelation::committed_total_len(L)
+            );
+            let pis = params.inspect(&proof)?;
+            assert!(pis.is_query_committed());
+            assert_eq!(
+                pis.query_commitment(),
+                query.commitment(commitment_hash).as_slice()
+            );
+            // so they are read as zeros
+            assert!(pis.smart_contract_address().iter().all(|x| x.is_zero()));
+            assert_eq!(pis.mapping_slot(), F::ZERO);
+            assert!(Query::from_public_inputs(&pis).is_err());
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "catch_panics")]
+    #[test]
+    #[serial]
+    fn test_revelation_catch_panics() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+
+        // a block db proof with an unexpected number of public inputs makes witness
+        // assignment panic
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let mut input = inputs.revelation_input::<L>()?;
+        input.block_db_proof.public_inputs.push(F::ZERO);
+        let err = params.generate_proof(input).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RevelationError>(),
+            Some(RevelationError::Internal(_))
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    #[serial]
+    async fn test_revelation_generate_proof_async() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = Arc::new(circuits.build_params::<L>());
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+
+        let proof = params
+            .clone()
+            .generate_proof_async(inputs.revelation_input::<L>()?)
+            .await?;
+        params.verify_proof(proof.clone())?;
+        let pis = params.inspect(&proof)?;
+        assert_eq!(
+            pis.total_matching().to_canonical_u64() as usize,
+            inputs.mapping_keys.len()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_revelation_prover_pool() -> Result<()> {
+        const L: usize = 2;
+        const NUM_QUERIES: usize = 3;
+        let circuits = TestingCircuits::new();
+        // the queue is smaller than the number of queries, so submissions wait for the workers
+        let pool = RevelationProverPool::new(circuits.build_params::<L>(), 2, 1)?;
+
+        let inputs = (0..NUM_QUERIES)
+            .map(|_| circuits.generate_inputs(&random_mapping_keys(L)))
+            .collect::<Result<Vec<_>>>()?;
+        let mut pending_proofs = Vec::with_capacity(NUM_QUERIES);
+        for input in inputs.iter() {
+            pending_proofs.push(pool.submit(input.revelation_input::<L>()?).await);
+        }
+        for (input, pending_proof) in inputs.iter().zip(pending_proofs) {
+            let proof = pending_proof.await?;
+            pool.parameters().verify_proof(proof.clone())?;
+            // check the proof has been generated for the corresponding input
+            let pis = pool.parameters().inspect(&proof)?;
+            assert_eq!(
+                pis.min_block_number(),
+                F::from_canonical_usize(input.query_min_block)
+            );
+            assert_eq!(
+                pis.max_block_number(),
+                F::from_canonical_usize(input.query_max_block)
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_audit_linkage() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+
+        let mapping_keys = random_mapping_keys(L);
+        let inputs = circuits.generate_inputs(&mapping_keys)?;
+        let proof = params.generate_proof(inputs.revelation_input::<L>()?)?;
+
+        let report = params.audit_linkage(&proof, &inputs.query2_proof, &inputs.block_db_proof)?;
+        assert!(report.is_linked(), "linkage failed: {report:?}");
+
+        // a query2/block proof for another mapping slot must not be linked to the proof
+        let mut other_values = inputs.query2_values.clone();
+        other_values.mapping_slot += F::ONE;
+        let other_query2_proof = circuits.generate_query2_proof(&other_values, &mapping_keys)?;
+        let report = params.audit_linkage(&proof, &other_query2_proof, &inputs.block_db_proof)?;
+        assert!(!report.is_linked());
+        assert_eq!(
+            report,
+            LinkageReport {
+                block_number: Some(true),
+                range: Some(true),
+                smart_contract_address: Some(true),
+                user_address: Some(true),
+                mapping_slot: Some(false),
+                mapping_slot_length: Some(true),
+                root: true,
+                block_header: true,
+            }
+        );
+
+        // proofs with too few public inputs are reported as errors rather than panicking
+        let mut short_query2_proof = inputs.query2_proof.clone();
+        short_query2_proof.proof.public_inputs.truncate(1);
+        let err = params
+            .audit_linkage(&proof, &short_query2_proof, &inputs.block_db_proof)
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("query2/block proof exposes 1 public inputs"));
+        let mut short_block_db_proof = inputs.block_db_proof.clone();
+        short_block_db_proof.public_inputs.truncate(1);
+        assert!(params
+            .audit_linkage(&proof, &inputs.query2_proof, &short_block_db_proof)
+            .is_err());
+        // a revelation proof for another L is rejected before being verified
+        let other_params = circuits.build_params::<{ L + 1 }>();
+        let other_proof = other_params.generate_proof(inputs.revelation_input::<{ L + 1 }>()?)?;
+        let err = params
+            .audit_linkage(&other_proof, &inputs.query2_proof, &inputs.block_db_proof)
+            .unwrap_err();
+        assert!(err.to_string().contains("proofs for a different L"));
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_audit_linkage_committed() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+
+        // the block range is hidden, so only the block number and the range can't be compared
+        let params = circuits
+            .params_builder()
+            .with_block_range_visibility(BlockRangeVisibility::Committed)
+            .build_for::<L>()?;
+        let proof = params.generate_proof(
+            inputs
+                .revelation_input::<L>()?
+                .with_range_blinding(F::rand()),
+        )?;
+        let report = params.audit_linkage(&proof, &inputs.query2_proof, &inputs.block_db_proof)?;
+        assert!(report.is_linked(), "linkage failed: {report:?}");
+        assert_eq!(report.block_number, None);
+        assert_eq!(report.range, None);
+        assert_eq!(report.mapping_slot, Some(true));
+
+        // the query is committed to, so none of its parameters can be compared
+        let params = circuits
+            .params_builder()
+            .with_salt_policy(SaltPolicy::Unsalted)
+            .with_query_commitment(QueryCommitment::Enabled)
+            .build_for::<L>()?;
+        let proof = params.generate_proof(inputs.revelation_input::<L>()?)?;
+        let report = params.audit_linkage(&proof, &inputs.query2_proof, &inputs.block_db_proof)?;
+        assert!(report.is_linked(), "linkage failed: {report:?}");
+        assert_eq!(
+            report,
+            LinkageReport {
+                root: true,
+                block_header: true,
+                ..Default::default()
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_describe() {
+        let params = TestingCircuits::new().build_params::<2>();
+        assert_eq!(Parameters::<BLOCK_DB_DEPTH, 2>::max_entries(), 2);
+        assert_eq!(
+            Parameters::<BLOCK_DB_DEPTH, 2>::block_db_depth(),
+            BLOCK_DB_DEPTH
+        );
+        let info = params.describe();
+        assert_eq!(
+            info,
+            ParametersInfo {
+                max_entries: 2,
+                block_db_depth: BLOCK_DB_DEPTH,
+                num_gates: 1 << params.circuit_data().common.degree_bits(),
+            }
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_circuit_stats() {
+        let circuits = TestingCircuits::new();
+        let stats = circuits.build_params::<2>().circuit_stats();
+        assert_eq!(
+            stats.num_public_inputs,
+            Parameters::<BLOCK_DB_DEPTH, 2>::num_public_inputs()
+        );
+        assert_eq!(stats.num_wires, circuits.config.num_wires);
+        assert!(stats.num_unpadded_gates <= 1 << stats.degree_bits);
+        // the circuit grows with the number of NFT IDs revealed
+        let larger_stats = circuits.build_params::<10>().circuit_stats();
+        assert!(stats.num_unpadded_gates < larger_stats.num_unpadded_gates);
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_fri_config_descriptor() {
+        let params = TestingCircuits::new().build_params::<2>();
+        let descriptor = params.fri_config_descriptor();
+        let fri_config = test_config().fri_config;
+        assert_eq!(
+            descriptor,
+            FriConfigDescriptor {
+                rate_bits: fri_config.rate_bits,
+                cap_height: fri_config.cap_height,
+                proof_of_work_bits: fri_config.proof_of_work_bits,
+                num_query_rounds: fri_config.num_query_rounds,
+            }
+        );
+        assert_eq!(
+            descriptor.cap_height,
+            params
+                .verifier_data()
+                .verifier_only
+                .constants_sigmas_cap
+                .height()
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_foreign_block_db_verifier_data() {
+        let circuits = TestingCircuits::new();
+        // verifier data of a circuit which is not in the block db circuit set
+        let foreign_vd = circuits.query2.verifier_data_for_input_proofs::<1>()[0];
+        let err = Parameters::<BLOCK_DB_DEPTH, 2>::build(
+            circuits.query2.get_recursive_circuit_set(),
+            circuits.block_db.get_recursive_circuit_set(),
+            foreign_vd,
+        )
+        .err()
+        .expect("parameters built with verifier data not in the block db circuit set");
+        assert!(err
+            .to_string()
+            .contains("does not belong to the block db circuit set"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_multiple_block_db_verifier_datas() -> Result<()> {
+        const L: usize = 2;
+        let query2 = TestingRecursiveCircuits::<F, C, D, QUERY2_BLOCK_NUM_IO>::default();
+        // the old version of the block db circuit is played by the query2/block testing
+        // circuit, while the new one is the block db testing circuit
+        let old_vd = query2.verifier_data_for_input_proofs::<1>()[0].clone();
+        let builder = new_universal_circuit_builder_for_testing::<F, C, D, BLOCK_DB_NUM_IO>(
+            default_config(),
+            1,
+        );
+        let block_db =
+            TestingRecursiveCircuits::<F, C, D, BLOCK_DB_NUM_IO>::new_from_circuit_digests(
+                &builder,
+                vec![old_vd.circuit_digest],
+            );
+        let circuits = TestingCircuits {
+            query2,
+            block_db,
+            config: default_config(),
+        };
+        let new_vd = circuits.block_db.verifier_data_for_input_proofs::<1>()[0].clone();
+        let params = ParametersBuilder::<BLOCK_DB_DEPTH>::new(
+            circuits.query2.get_recursive_circuit_set(),
+            circuits.block_db.get_recursive_circuit_set(),
+            &new_vd,
+        )
+        .with_block_db_verifier_datas(&[old_vd, new_vd.clone()])
+        .build_for::<L>()?;
+
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        // the block db circuit must be specified when several ones are accepted
+        assert!(params
+            .generate_proof(inputs.revelation_input::<L>()?)
+            .is_err());
+        let proof = params.generate_proof(
+            inputs
+                .revelation_input::<L>()?
+                .with_block_db_verifier_data(new_vd),
+        )?;
+        params.verify_proof(proof)?;
+        // a block db circuit which is not accepted is rejected
+        let mut foreign_vd = circuits.block_db.verifier_data_for_input_proofs::<1>()[0].clone();
+        foreign_vd.circuit_digest.elements[0] += F::ONE;
+        assert!(params
+            .generate_proof(
+                inputs
+                    .revelation_input::<L>()?
+                    .with_block_db_verifier_data(foreign_vd)
+            )
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_merged_block_db_proof() -> Result<()> {
+        const L: usize = 2;
+        // the block db circuit set is made of the circuit merging block db proofs and of the
+        // testing circuit generating the fake block db proofs to be merged
+        let builder = new_universal_circuit_builder_for_testing::<F, C, D, BLOCK_DB_NUM_IO>(
+            default_config(),
+            1,
+        );
+        let merge_circuit = builder.build_circuit::<C, 2, BlockDbMergeWires>(());
+        let block_db = TestingRecursiveCircuits::<F, C, D, BLOCK_DB_NUM_IO>::new(
+            &builder,
+            vec![prepare_recursive_circuit_for_circuit_set(&merge_circuit)],
+        );
+        let circuits = TestingCircuits {
+            query2: TestingRecursiveCircuits::<F, C, D, QUERY2_BLOCK_NUM_IO>::default(),
+            block_db,
+            config: default_config(),
+        };
+
+        // fake block db proofs over the consecutive ranges [100, 200] and [201, 300]
+        let init_root = empty_merkle_root::<GoldilocksField, 2, BLOCK_DB_DEPTH>();
+        let middle_root = HashOut::<F>::rand();
+        let last_root = HashOut::<F>::rand();
+        let last_block_hash = thread_rng().gen();
+        let [earlier, later] = circuits.block_db.generate_input_proofs([
+            test_utils::merged_block_db_public_inputs(
+                &init_root,
+                &middle_root,
+                100,
+                200,
+                &thread_rng().gen(),
+            ),
+            test_utils::merged_block_db_public_inputs(
+                &middle_root,
+                &last_root,
+                201,
+                300,
+                &last_block_hash,
+            ),
+        ])?;
+        let merged = circuits
+            .block_db
+            .generate_proof(&merge_circuit, [earlier, later], ())?;
+        assert_eq!(
+            &merged.public_inputs[..BLOCK_DB_NUM_IO],
+            test_utils::merged_block_db_public_inputs(
+                &init_root,
+                &last_root,
+                100,
+                300,
+                &last_block_hash
+            )
+            .as_slice()
+        );
+
+        // the merged proof is employed to reveal a query over blocks of both ranges
+        let params = Parameters::<BLOCK_DB_DEPTH, L>::build(
+            circuits.query2.get_recursive_circuit_set(),
+            circuits.block_db.get_recursive_circuit_set(),
+            &merge_circuit.circuit_data().verifier_only,
+        )?;
+        let mapping_keys = random_mapping_keys(L);
+        let query2_values = Query2Values {
+            block_number: F::from_canonical_u32(250),
+            range: F::from_canonical_u32(100),
+            root: last_root,
+            smc_address: Address::random(),
+            user_address: Address::random(),
+            mapping_slot: F::rand(),
+            length_slot: F::rand(),
+        };
+        let inputs = TestInputs {
+            mapping_keys: mapping_keys.iter().map(|x| x.to_vec()).collect(),
+            query_min_block: 151,
+            query_max_block: 250,
+            query2_proof: circuits.generate_query2_proof(&query2_values, &mapping_keys)?,
+            query2_values,
+            block_db_proof: merged,
+        };
+        let (proof, pis) = params.generate_proof_with_pis(inputs.revelation_input::<L>()?)?;
+        params.verify_proof(proof)?;
+        assert_eq!(pis.block_db_root(), last_root);
+        Ok(())
+    }
+
+    #[test]
+    fn test_revelation_strictly_increasing_ids() {
+        const L: usize = 4;
+        // sorted IDs, possibly padded with zeros
+        for (ids, total) in [
+            ([1, 5, 7, 9], 4),
+            ([0, 5, 0, 0], 2),
+            ([3, 0, 0, 0], 1),
+            ([0, 0, 0, 0], 0),
+        ] {
+            crafted_public_inputs(ids, total, (0, L as u8))
+                .assert_strictly_increasing()
+                .unwrap();
+        }
+        // IDs outside the revealed window are not checked
+        crafted_public_inputs([0, 5, 7, 0], 4, (1, 2))
+            .assert_strictly_increasing()
+            .unwrap();
+        // duplicated and unsorted IDs
+        for (ids, total) in [([1, 5, 5, 9], 4), ([3, 3, 0, 0], 2), ([1, 7, 5, 0], 3)] {
+            assert!(crafted_public_inputs(ids, total, (0, L as u8))
+                .assert_strictly_increasing()
+                .is_err());
+        }
+        assert!(crafted_public_inputs([0, 7, 5, 0], 4, (1, 2))
+            .assert_strictly_increasing()
+            .is_err());
+        // IDs are compared once decoded, i.e. with the bytes of the packed limbs swapped: the
+        // IDs 1, 256 and 65536 are increasing even though their packed limbs are decreasing
+        let ids = [1u32, 256, 65536].map(u32::swap_bytes);
+        assert!(ids[0] > ids[1] && ids[1] > ids[2]);
+        crafted_public_inputs([ids[0], ids[1], ids[2], 0], 3, (0, L as u8))
+            .assert_strictly_increasing()
+            .unwrap();
+        assert!(
+            crafted_public_inputs([ids[2], ids[1], ids[0], 0], 3, (0, L as u8))
+                .assert_strictly_increasing()
+                .is_err()
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_strictly_increasing_multi_byte_ids() -> Result<()> {
+        const L: usize = 3;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        // IDs spanning several bytes, whose packed limbs are in decreasing order
+        let ids = [1u32, 256, 65536];
+        let mapping_keys = ids.map(|id| left_pad::<MAPPING_KEY_LEN>(&id.to_be_bytes()));
+        let inputs = circuits.generate_inputs(&mapping_keys)?;
+        let proof = params.generate_proof(inputs.revelation_input::<L>()?)?;
+        params.verify_proof(proof.clone())?;
+
+        let pis = params.inspect(&proof)?;
+        assert_eq!(pis.revealed_nft_ids(), ids.map(U256::from));
+        pis.assert_strictly_increasing()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_revelation_count_consistency() {
+        const L: usize = 4;
+        // padding and IDs outside the window are zero
+        for (ids, total, page) in [
+            ([1, 5, 7, 9], 4, (0, 4)),
+            ([0, 5, 0, 0], 2, (0, 4)),
+            ([0, 0, 0, 0], 0, (0, 4)),
+            ([0, 5, 7, 0], 4, (1, 2)),
+        ] {
+            crafted_public_inputs(ids, total, page)
+                .verify_count_consistency()
+                .unwrap();
+        }
+        // non-zero padding, non-zero ID outside the window and too many matching keys
+        for (ids, total, page) in [
+            ([1, 5, 7, 9], 3, (0, 4)),
+            ([1, 5, 7, 0], 4, (1, 2)),
+            ([1, 5, 7, 9], 5, (0, 4)),
+        ] {
+            assert!(crafted_public_inputs(ids, total, page)
+                .verify_count_consistency()
+                .is_err());
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_query_reconstruction() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let proof = params.generate_proof(inputs.revelation_input::<L>()?)?;
+        params.verify_proof(proof.clone())?;
+        let query = Query::from_public_inputs(&params.inspect(&proof)?)?;
+
+        let values = &inputs.query2_values;
+        let block_db_pi = BlockDbPublicInputs::from(
+            BlockDbParameters::<BLOCK_DB_DEPTH>::block_tree_public_inputs(&inputs.block_db_proof),
+        );
+        let block_hash = block_db_pi
+            .block_header_data()
+            .iter()
+            .rev()
+            .fold(U256::zero(), |acc, limb| {
+                (acc << 32) + limb.to_canonical_u64()
+            });
+        let expected = Query {
+            contract_address: values.smc_address,
+            user_address: values.user_address,
+            mapping_slot: values.mapping_slot.to_canonical_u64(),
+            length_slot: values.length_slot.to_canonical_u64(),
+            min_block_number: inputs.query_min_block as u32,
+            max_block_number: inputs.query_max_block as u32,
+            block_hash,
+        };
+        assert_eq!(query, expected);
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_estimated_size() -> Result<()> {
+        const L: usize = 2;
+        // the estimates are computed for circuits built with `default_config`
+        let params = TestingCircuits::new_with_config(default_config()).build_params::<L>();
+        let actual = bincode::serialize(&params)?.len();
+        let estimate = Parameters::<BLOCK_DB_DEPTH, L>::estimated_serialized_size();
+        // the degree of the circuit, which determines the size of its polynomials, is exact, so
+        // the estimate is off only by the serialized size of gates and witness generators
+        assert_eq!(
+            estimated_num_gates(L).next_power_of_two(),
+            params.circuit_data().common.degree()
+        );
+        assert!(
+            estimate.abs_diff(actual) <= actual / 4,
+            "estimated size {} too far from actual size {}",
+            estimate,
+            actual,
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_estimated_num_gates() {
+        const SMALL_L: usize = 2;
+        const LARGE_L: usize = 5;
+        // the estimates are computed for circuits built with `default_config`
+        let circuits = TestingCircuits::new_with_config(default_config());
+        let small_num_gates = circuits
+            .build_params::<SMALL_L>()
+            .circuit_stats()
+            .num_unpadded_gates;
+        let large_num_gates = circuits
+            .build_params::<LARGE_L>()
+            .circuit_stats()
+            .num_unpadded_gates;
+        // the gate constants bound the actual number of gates and the gates added by each NFT
+        // ID, and they are tight enough to predict the padded degree of the circuits
+        assert!(small_num_gates <= estimated_num_gates(SMALL_L));
+        assert!(large_num_gates <= estimated_num_gates(LARGE_L));
+        assert!(large_num_gates - small_num_gates <= (LARGE_L - SMALL_L) * NUM_GATES_PER_ID);
+        assert_eq!(
+            estimated_num_gates(SMALL_L).next_power_of_two(),
+            small_num_gates.next_power_of_two()
+        );
+        assert_eq!(
+            estimated_num_gates(LARGE_L).next_power_of_two(),
+            large_num_gates.next_power_of_two()
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_padded_proof() -> Result<()> {
+        const SMALL_L: usize = 2;
+        const L: usize = 5;
+        let circuits = TestingCircuits::new();
+        let small_params = circuits.build_params::<SMALL_L>();
+        let params = circuits.build_params::<L>();
+
+        let inputs = circuits.generate_inputs(&random_mapping_keys(SMALL_L))?;
+        let small_proof = small_params.generate_proof(inputs.revelation_input::<SMALL_L>()?)?;
+        // a proof for a different L is rejected
+        assert!(params.verify_proof(small_proof.clone()).is_err());
+
+        let proof =
+            PaddedProof::<L>::from_input(inputs.revelation_input::<SMALL_L>()?)?.prove(&params)?;
+        params.verify_proof(proof.clone())?;
+        let small_pis = small_params.inspect(&small_proof)?;
+        let pis = params.inspect(&proof)?;
+        assert_eq!(&pis.nft_ids()[..SMALL_L], small_pis.nft_ids());
+        assert!(pis.nft_ids()[SMALL_L..].iter().all(|id| *id == F::ZERO));
+        assert_eq!(pis.total_matching(), small_pis.total_matching());
+        assert_eq!(pis.block_header(), small_pis.block_header());
+
+        // inputs cannot be padded into a smaller circuit
+        assert!(PaddedProof::<SMALL_L>::from_input(inputs.revelation_input::<L>()?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_accepts() -> Result<()> {
+        let circuits = TestingCircuits::new();
+        let small_params = circuits.build_params::<2>();
+        let params = circuits.build_params::<4>();
+
+        let inputs = circuits.generate_inputs(&random_mapping_keys(2))?;
+        let small_proof = small_params.generate_proof(inputs.revelation_input::<2>()?)?;
+        let proof = params.generate_proof(inputs.revelation_input::<4>()?)?;
+        // each proof is routed to the parameters it was generated with
+        assert!(small_params.accepts(&small_proof));
+        assert!(!params.accepts(&small_proof));
+        assert!(params.accepts(&proof));
+        assert!(!small_params.accepts(&proof));
+        // neither accepts a proof of another circuit or garbage
+        let block_db_proof = serialize_proof(&inputs.block_db_proof)?;
+        assert!(!small_params.accepts(&block_db_proof));
+        assert!(!params.accepts(&[0u8; 32]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_revelation_roots_field_compatible() {
+        let root = HashOut::<F>::rand();
+        assert_roots_field_compatible(&root, &root).unwrap();
+
+        let mut other_root = root;
+        other_root.elements[2] += F::ONE;
+        let err = assert_roots_field_compatible(&root, &other_root)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("differ at element 2"), "{err}");
+    }
+
+    #[test]
+    fn test_revelation_ids_with_original_index() -> Result<()> {
+        // the NFT ID exposed for a key is the last u32 limb of the packed key
+        let packed_id = |key: &[u8]| *left_pad32(key).pack().last().unwrap();
+        // keys are provided unsorted, while the proof reveals the sorted IDs
+        let input_keys = vec![vec![0x30], vec![0x01, 0x00, 0x10], vec![0x20]];
+        let ids = [&input_keys[2], &input_keys[0], &input_keys[1]].map(|key| packed_id(key));
+        let pis = crafted_public_inputs::<4>([ids[0], ids[1], ids[2], 0], 3, (0, 4));
+        assert_eq!(
+            pis.ids_with_original_index(&input_keys)?,
+            vec![
+                (2, U256::from(0x20)),
+                (0, U256::from(0x30)),
+                (1, U256::from(0x010010)),
+            ]
+        );
+
+        // only the IDs in the revealed window are returned
+        let pis = crafted_public_inputs::<4>([0, ids[1], 0, 0], 3, (1, 1));
+        assert_eq!(
+            pis.ids_with_original_index(&input_keys)?,
+            vec![(0, U256::from(0x30))]
+        );
+
+        // keys with the same NFT ID are matched to distinct revealed IDs
+        let input_keys = vec![vec![0x30], vec![0x01, 0x00, 0x00, 0x00, 0x30]];
+        let id = packed_id(&input_keys[0]);
+        let pis = crafted_public_inputs::<4>([id, id, 0, 0], 2, (0, 4));
+        assert_eq!(
+            pis.ids_with_original_index(&input_keys)?,
+            vec![(0, U256::from(0x30)), (1, U256::from(0x30))]
+        );
+
+        // keys longer than 32 bytes are rejected
+        assert!(pis.ids_with_original_index(&[vec![1; 33]]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_revelation_decode_nft_ids() {
+        // the key ending with the bytes 0x01020304 is packed in the limb 0x04030201, which is
+        // decoded back to 0x01020304 as in Solidity
+        let key = left_pad32(&[0x01, 0x02, 0x03, 0x04]);
+        let id = *key.pack().last().unwrap();
+        assert_eq!(id, 0x04030201);
+        let pis = crafted_public_inputs::<3>([id, 0, 0], 1, (0, 3));
+        assert_eq!(
+            pis.decode_nft_ids(),
+            vec![U256::from(0x01020304), U256::zero(), U256::zero()]
+        );
+    }
+
+    #[test]
+    fn test_revelation_summary_json() -> Result<()> {
+        const L: usize = 3;
+        // IDs packed as in Solidity, i.e. with swapped bytes; only the first 2 are revealed
+        let mut pis = crafted_public_inputs::<L>([0x01000000, 0x02000000, 0], 2, (0, 3));
+        let layout = revelation_layout(L);
+        let region = |name| layout.iter().find(|(n, _)| *n == name).unwrap().1.clone();
+        let inputs = pis.inputs.to_mut();
+        inputs[region("min_block_number").start] = F::from_canonical_u32(10);
+        inputs[region("max_block_number").start] = F::from_canonical_u32(20);
+        inputs[region("block_db_root")]
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, limb)| *limb = F::from_canonical_usize(i + 1));
+
+        let json = serde_json::to_value(pis.summary())?;
+        let root = format!(
+            "0x{}",
+            hex::encode([1u64, 2, 3, 4].map(u64::to_le_bytes).concat())
+        );
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "nft_ids": ["1", "2"],
+                "min_block": 10,
+                "max_block": 20,
+                "block_db_root": root,
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_decode_only_public_inputs() -> Result<()> {
+        const L: usize = 3;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L - 1))?;
+        let (proof, pis) = params.generate_proof_with_pis(inputs.revelation_input::<L>()?)?;
+
+        // light clients decode the serialized public inputs without the proving stack
+        let bytes = serialize_public_inputs(&deserialize_proof::<F, C, D>(&proof)?);
+        let decoded = mrp2_public_inputs::decode_public_inputs::<F>(&bytes)?;
+        let decoder =
+            mrp2_public_inputs::revelation::RevelationPublicInputs::<F, L>::new(&decoded)?;
+        assert_eq!(decoder.min_block()?, pis.min_block()?);
+        assert_eq!(decoder.max_block()?, pis.max_block()?);
+        assert_eq!(decoder.revealed_window(), pis.revealed_window());
+        assert_eq!(
+            decoder
+                .revealed_nft_ids()
+                .into_iter()
+                .map(U256::from)
+                .collect_vec(),
+            pis.revealed_nft_ids()
+        );
+        assert_eq!(decoder.block_db_root(), &pis.block_db_root().elements);
+        assert_eq!(decoder.keys_commitment(), &pis.keys_commitment().elements);
+        assert_eq!(
+            Address::from(decoder.client_address()),
+            pis.client_address()
+        );
+
+        // as well as the public inputs of the query2/block proof
+        let query2_inputs = &inputs.query2_proof.proof().public_inputs[..QUERY2_BLOCK_NUM_IO];
+        let query2_decoder = mrp2_public_inputs::block::BlockPublicInputs::new(query2_inputs)?;
+        assert_eq!(
+            query2_decoder.block_number(),
+            inputs.query2_values.block_number.to_canonical_u64()
+        );
+        assert_eq!(
+            query2_decoder.smart_contract_address(),
+            inputs.query2_values.smc_address.to_fixed_bytes()
+        );
+        assert_eq!(
+            query2_decoder.user_address(),
+            left_pad32(inputs.query2_values.user_address.as_fixed_bytes())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_revelation_freshness() -> Result<()> {
+        let mut pis = crafted_public_inputs([0; 2], 0, (0, 2));
+        // max block number is found after block number, range and min block number
+        pis.inputs.to_mut()[3] = F::from_canonical_u32(1000);
+
+        assert!(pis.is_fresh(1000, 0)?);
+        assert!(pis.is_fresh(1010, 10)?);
+        assert!(!pis.is_fresh(1011, 10)?);
+        assert!(!pis.is_fresh(u32::MAX, 1000)?);
+        // a query bounded by a block beyond the tip is fresh
+        assert!(pis.is_fresh(900, 0)?);
+
+        // the freshness of proofs not exposing the block range can't be established
+        let layout = revelation_layout(2);
+        let region = |name| layout.iter().find(|(n, _)| *n == name).unwrap().1.clone();
+        for commitment in ["query_commitment", "range_commitment"] {
+            let mut committed = pis.clone();
+            committed.inputs.to_mut()[region(commitment)].fill(F::ONE);
+            let err = committed.is_fresh(1000, 0).unwrap_err().to_string();
+            assert!(err.contains("not exposed"), "{err}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_revelation_range_eq() {
+        let mut pis = crafted_public_inputs([0; 2], 0, (0, 2));
+        // min and max block numbers are found after block number and range
+        pis.inputs.to_mut()[2] = F::from_canonical_u32(100);
+        pis.inputs.to_mut()[3] = F::from_canonical_u32(200);
+
+        assert!(pis.assert_range_eq(100, 200).is_ok());
+        // the proof covers a wider range than the requested one
+        let err = pis.assert_range_eq(110, 190).unwrap_err().to_string();
+        assert!(err.contains("[100, 200]"), "{err}");
+        assert!(err.contains("[110, 190] was requested"), "{err}");
+        // the proof covers a narrower range than the requested one
+        assert!(pis.assert_range_eq(90, 200).is_err());
+        assert!(pis.assert_range_eq(100, 210).is_err());
+        assert!(pis.assert_range_eq(50, 250).is_err());
+        // block numbers which don't fit in a u32 are rejected rather than truncated
+        pis.inputs.to_mut()[3] = F::from_canonical_u64(1 << 32);
+        assert!(pis.max_block().is_err());
+        assert!(pis.assert_range_eq(100, 0).is_err());
+    }
+
+    #[test]
+    fn test_revelation_io_width_mismatch() {
+        assert!(check_io_width("query2/block", "BlockPublicInputs::total_len()", 42, 42).is_ok());
+        let err = check_io_width("query2/block", "BlockPublicInputs::total_len()", 42, 40)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("query2/block proofs with 40 public inputs"));
+        assert!(err.contains("BlockPublicInputs::total_len() expects 42"));
+        assert!(err.contains("must be rebuilt"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_params_registry() -> Result<()> {
+        let circuits = TestingCircuits::new_with_config(default_config());
+        let registry = crate::params_registry!(
+            circuits.query2.get_recursive_circuit_set(),
+            circuits.block_db.get_recursive_circuit_set(),
+            circuits.block_db.verifier_data_for_input_proofs::<1>()[0];
+            BLOCK_DB_DEPTH;
+            2, 5
+        );
+        assert_eq!(registry.registered(), vec![2, 5]);
+        // parameters are built only on first use
+        assert!(!registry.is_built(2) && !registry.is_built(5));
+
+        for l in [2, 5] {
+            let params = registry.get(l)?;
+            assert_eq!(params.l(), l);
+            assert_eq!(params.describe().max_entries, l);
+            assert!(registry.is_built(l));
+            // the cached parameters are returned afterwards
+            assert!(Arc::ptr_eq(&params, &registry.get(l)?));
+        }
+        assert_eq!(
+            registry.get(5)?.num_public_inputs(),
+            Parameters::<BLOCK_DB_DEPTH, 5>::num_public_inputs()
+        );
+        assert!(registry.get(3).is_err());
+
+        let inputs = circuits.generate_inputs(&random_mapping_keys(2))?;
+        let params = registry.get(2)?;
+        let proof = params.generate_proof(
+            inputs.mapping_keys.clone(),
+            inputs.query_min_block,
+            inputs.query_max_block,
+            inputs.query2_proof.serialize()?,
+            serialize_proof(&inputs.block_db_proof)?,
+        )?;
+        params.verify_proof(proof)
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_build_dyn_parameters() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new_with_config(default_config());
+        // the depth is chosen at runtime, e.g. read from the on-chain tree
+        let depth = BlockDbDepth::try_from(16)?;
+        assert_eq!(depth, BlockDbDepth::D16);
+        assert!(BlockDbDepth::try_from(BLOCK_DB_DEPTH).is_err());
+
+        let params = build_dyn_parameters::<L>(
+            depth,
+            circuits.query2.get_recursive_circuit_set(),
+            circuits.block_db.get_recursive_circuit_set(),
+            circuits.block_db.verifier_data_for_input_proofs::<1>()[0],
+        )?;
+        assert_eq!(params.l(), L);
+        assert_eq!(params.describe().block_db_depth, depth.depth());
+        assert_eq!(
+            params.num_public_inputs(),
+            Parameters::<16, L>::num_public_inputs()
+        );
+
+        // proofs over a block db of the chosen depth are generated and verified through the
+        // boxed parameters
+        let inputs = circuits.generate_inputs_for_depth::<16>(&random_mapping_keys(L))?;
+        let proof = params.generate_proof(
+            inputs.mapping_keys.clone(),
+            inputs.query_min_block,
+            inputs.query_max_block,
+            inputs.query2_proof.serialize()?,
+            serialize_proof(&inputs.block_db_proof)?,
+        )?;
+        params.verify_proof(proof)
+    }
+
+    #[cfg(feature = "catch_panics")]
+    #[test]
+    #[serial]
+    fn test_revelation_dyn_parameters_reject_other_depth() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new_with_config(default_config());
+        let params = build_dyn_parameters::<L>(
+            BlockDbDepth::D16,
+            circuits.query2.get_recursive_circuit_set(),
+            circuits.block_db.get_recursive_circuit_set(),
+            circuits.block_db.verifier_data_for_input_proofs::<1>()[0],
+        )?;
+        // the block db proof starts from the empty tree of another depth, so proving fails
+        let inputs = circuits.generate_inputs_for_depth::<24>(&random_mapping_keys(L))?;
+        let res = params.generate_proof(
+            inputs.mapping_keys,
+            inputs.query_min_block,
+            inputs.query_max_block,
+            inputs.query2_proof.serialize()?,
+            serialize_proof(&inputs.block_db_proof)?,
+        );
+        assert!(res.is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_parameters_builder() -> Result<()> {
+        let circuits = TestingCircuits::new();
+        let builder = circuits.params_builder();
+        let small_params = builder.build_for::<2>()?;
+        let large_params = builder.build_for::<3>()?;
+
+        // the same inputs are proven with the parameters for both `L`
+        let inputs = circuits.generate_inputs(&random_mapping_keys(2))?;
+        let small_proof = small_params.generate_proof(inputs.revelation_input::<2>()?)?;
+        small_params.verify_proof(small_proof.clone())?;
+        let large_proof = large_params.generate_proof(inputs.revelation_input::<3>()?)?;
+        large_params.verify_proof(large_proof.clone())?;
+        assert_eq!(
+            small_params.inspect(&small_proof)?.nft_ids(),
+            &large_params.inspect(&large_proof)?.nft_ids()[..2]
+        );
+        // proofs are bound to the parameters of their own `L`
+        assert!(small_params.verify_proof(large_proof).is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_generate_proof_with_pis() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let (proof, pis) = params.generate_proof_with_pis(inputs.revelation_input::<L>()?)?;
+        params.verify_proof(proof.clone())?;
+        // the returned public inputs are the ones exposed by the proof
+        assert_eq!(pis.inputs, params.inspect(&proof)?.inputs);
+        pis.assert_range_eq(inputs.query_min_block as u32, inputs.query_max_block as u32)
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_block_range_accessors() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let (_, pis) = params.generate_proof_with_pis(inputs.revelation_input::<L>()?)?;
+        assert_eq!(pis.min_block()? as usize, inputs.query_min_block);
+        assert_eq!(pis.max_block()? as usize, inputs.query_max_block);
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_block_db_root() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let (_, pis) = params.generate_proof_with_pis(inputs.revelation_input::<L>()?)?;
+        // the exposed root is the last root of the block db proof
+        let block_db_pi =
+            BlockDbPublicInputs::from(&inputs.block_db_proof.public_inputs[..BLOCK_DB_NUM_IO]);
+        let last_root = HashOut::from_partial(block_db_pi.root_data());
+        assert_eq!(pis.block_db_root(), last_root);
+        assert_eq!(pis.block_db_root_bytes().to_vec(), last_root.to_bytes());
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_empty_query() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let inputs = circuits.generate_inputs(&[])?;
+        let (proof, pis) = params.generate_proof_with_pis(inputs.revelation_input::<L>()?)?;
+        assert!(crate::api::revealed_nft_ids::<L>(&proof)?.is_empty());
+        params.verify_proof(proof)?;
+        // no key matches the query, so no NFT ID is revealed, not even a zero one
+        assert_eq!(pis.total_matching(), F::ZERO);
+        assert!(pis.revealed_window().is_empty());
+        assert!(pis.revealed_nft_ids().is_empty());
+        assert!(pis.summary().nft_ids.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_client_address() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        // proofs are bound to no client by default
+        let (_, pis) = params.generate_proof_with_pis(inputs.revelation_input::<L>()?)?;
+        assert_eq!(pis.client_address(), Address::zero());
+
+        let client_address = Address::random();
+        let (proof, pis) = params.generate_proof_with_pis(
+            inputs
+                .revelation_input::<L>()?
+                .with_client_address(client_address),
+        )?;
+        assert_eq!(pis.client_address(), client_address);
+        params.verify_proof(proof)
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_keys_commitment() -> Result<()> {
+        const L: usize = 3;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        // fewer keys than slots, so that padding keys are found in the witness
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L - 1))?;
+        let (proof, pis) = params.generate_proof_with_pis(inputs.revelation_input::<L>()?)?;
+
+        // the same keys provided in another order yield the same commitment
+        let mut reversed_keys = inputs.mapping_keys.clone();
+        reversed_keys.reverse();
+        let reversed_input = RevelationRecursiveInput::<L>::new(
+            reversed_keys,
+            inputs.query_min_block,
+            inputs.query_max_block,
+            inputs.query2_proof.serialize()?,
+            serialize_proof(&inputs.block_db_proof)?,
+        )?;
+        let (_, reversed_pis) = params.generate_proof_with_pis(reversed_input)?;
+        assert_eq!(pis.keys_commitment(), reversed_pis.keys_commitment());
+
+        // the commitment is the hash of the sorted packed keys, padding excluded
+        let sorted_keys = sort_packed_keys(&pack_mapping_keys(&inputs.mapping_keys)?);
+        let expected = PoseidonHash::hash_no_pad(
+            &sorted_keys
+                .iter()
+                .flatten()
+                .map(|limb| F::from_canonical_u32(*limb))
+                .collect_vec(),
+        );
+        assert_eq!(pis.keys_commitment(), expected);
+        params.verify_proof(proof)
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_verify_proof_against() -> Result<()> {
+        const L: usize = 3;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        // only the first key is revealed, while all the keys are checked
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let proof = params.generate_proof(inputs.revelation_input::<L>()?.with_page(0, 1))?;
+
+        let mut reversed_keys = inputs.mapping_keys.clone();
+        reversed_keys.reverse();
+        let expected = RevelationExpectation {
+            min_block: Some(inputs.query_min_block as u32),
+            max_block: Some(inputs.query_max_block as u32),
+            mapping_keys: Some(reversed_keys),
+        };
+        params.verify_proof_against(proof.clone(), &expected)?;
+        // values not set are not checked
+        params.verify_proof_against(proof.clone(), &RevelationExpectation::default())?;
+
+        let mismatches = [
+            RevelationExpectation {
+                min_block: Some(inputs.query_min_block as u32 - 1),
+                ..expected.clone()
+            },
+            RevelationExpectation {
+                max_block: Some(inputs.query_max_block as u32 + 1),
+                ..expected.clone()
+            },
+            // a missing key
+            RevelationExpectation {
+                mapping_keys: Some(inputs.mapping_keys[1..].to_vec()),
+                ..expected.clone()
+            },
+            // an unexpected key
+            RevelationExpectation {
+                mapping_keys: Some(
+                    inputs.mapping_keys[1..]
+                        .iter()
+                        .cloned()
+                        .chain(random_mapping_keys(1).into_iter().map(|key| key.to_vec()))
+                        .collect(),
+                ),
+                ..expected.clone()
+            },
+        ];
+        for (expectation, message) in mismatches.iter().zip([
+            "starting from block",
+            "ending at block",
+            "are not the 2 expected keys",
+            "are not the 3 expected keys",
+        ]) {
+            let err = params
+                .verify_proof_against(proof.clone(), expectation)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains(message), "unexpected error: {err}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_real_block_db_proof() -> Result<()> {
+        const L: usize = 2;
+        const NUM_BLOCKS: usize = 3;
+        // state proofs are fake, while the block db proof is generated by the real IVC circuit;
+        // the public `block::build_block_db_proof` requires the parameters of all the LPN
+        // circuits, so the block DB parameters are employed directly
+        let state_circuits =
+            TestingRecursiveCircuits::<F, C, D, NUM_STATE_PUBLIC_INPUTS>::default();
+        let block_db_params =
+            BlockDbParameters::<BLOCK_DB_DEPTH>::build(state_circuits.get_recursive_circuit_set());
+        let first_block = thread_rng().gen_range(1..10_000u32);
+        let block_hashes = (0..=NUM_BLOCKS)
+            .map(|_| BlockDbPublicInputs::from_block_hash_bytes(&thread_rng().gen()))
+            .collect_vec();
+        let state_proofs = (0..NUM_BLOCKS)
+            .map(|i| {
+                // [state_root, block_header, block_number, prev_block_header]
+                let state_inputs = F::rand_vec(NUM_HASH_OUT_ELTS)
+                    .into_iter()
+                    .chain(block_hashes[i + 1])
+                    .chain(once(F::from_canonical_u32(first_block + i as u32)))
+                    .chain(block_hashes[i])
+                    .collect_vec();
+                let proof = state_circuits
+                    .generate_input_proofs::<1>([state_inputs.try_into().unwrap()])?[0]
+                    .clone();
+                let vd = state_circuits.verifier_data_for_input_proofs::<1>()[0].clone();
+                Ok(ProofWithVK::from_parts(proof, vd).serialize()?)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let block_db_proof = block_db_params
+            .build_block_db_proof(state_circuits.get_recursive_circuit_set(), &state_proofs)?;
+
+        // the proof verifies and spans the inserted blocks, starting from an empty block db
+        let proof = deserialize_proof::<F, C, D>(&block_db_proof)?;
+        block_db_params.verify_proof(
+            &ProofWithVK::from_parts(proof.clone(), block_db_params.get_block_db_vk().clone())
+                .serialize()?,
+        )?;
+        let block_db_pi = BlockDbPublicInputs::from(
+            BlockDbParameters::<BLOCK_DB_DEPTH>::block_tree_public_inputs(&proof),
+        );
+        let last_block = first_block + NUM_BLOCKS as u32 - 1;
+        assert_eq!(
+            block_db_pi.init_root_data(),
+            empty_merkle_root::<F, D, BLOCK_DB_DEPTH>().elements
+        );
+        assert_eq!(
+            block_db_pi.first_block_number_data(),
+            F::from_canonical_u32(first_block)
+        );
+        assert_eq!(
+            block_db_pi.block_number_data(),
+            F::from_canonical_u32(last_block)
+        );
+        assert_eq!(block_db_pi.block_header_data(), block_hashes[NUM_BLOCKS]);
+
+        // the proof is accepted by the revelation of a query over the last block
+        let circuits = TestingCircuits::new_with_config(default_config());
+        let mapping_keys = random_mapping_keys(L);
+        let query2_values = Query2Values {
+            block_number: F::from_canonical_u32(last_block),
+            range: F::ONE,
+            root: HashOut::from_partial(block_db_pi.root_data()),
+            smc_address: Address::random(),
+            user_address: Address::random(),
+            mapping_slot: F::rand(),
+            length_slot: F::rand(),
+        };
+        let query2_proof = circuits.generate_query2_proof(&query2_values, &mapping_keys)?;
+        let input = RevelationRecursiveInput::<L>::new(
+            mapping_keys.iter().map(|key| key.to_vec()).collect(),
+            last_block as usize,
+            last_block as usize,
+            query2_proof.serialize()?,
+            block_db_proof,
+        )?;
+        let params = Parameters::<BLOCK_DB_DEPTH, L>::build(
+            circuits.query2.get_recursive_circuit_set(),
+            block_db_params.get_block_db_circuit_set(),
+            block_db_params.get_block_db_vk(),
+        )?;
+        params.verify_proof(params.generate_proof(input)?)
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_prover_pinned_block_db_proof() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let prover = RevelationProver::new(&params, &serialize_proof(&inputs.block_db_proof)?)?;
+
+        // a second query, over another mapping and a sub-range of the first one, answered
+        // against the same block db proof
+        let other_keys = random_mapping_keys(L - 1);
+        let other_values = Query2Values {
+            range: F::from_canonical_usize(5),
+            mapping_slot: inputs.query2_values.mapping_slot + F::ONE,
+            ..inputs.query2_values.clone()
+        };
+        let other_query2_proof = circuits.generate_query2_proof(&other_values, &other_keys)?;
+        let other_min_block = inputs.query_max_block - 4;
+
+        for (mapping_keys, query_min_block, query2_proof) in [
+            (
+                inputs.mapping_keys.clone(),
+                inputs.query_min_block,
+                &inputs.query2_proof,
+            ),
+            (
+                other_keys.iter().map(|key| key.to_vec()).collect_vec(),
+                other_min_block,
+                &other_query2_proof,
+            ),
+        ] {
+            let num_keys = mapping_keys.len();
+            let logic_inputs =
+                RevelationCircuit::new(mapping_keys, query_min_block, inputs.query_max_block)?;
+            let proof = prover.prove(logic_inputs, query2_proof.serialize()?)?;
+            params.verify_proof(proof.clone())?;
+            let pis = params.inspect(&proof)?;
+            assert_eq!(pis.total_matching().to_canonical_u64() as usize, num_keys);
+            assert_eq!(
+                pis.min_block_number(),
+                F::from_canonical_usize(query_min_block)
+            );
+        }
+
+        // queries not matching the pinned block db proof are rejected before proving
+        let logic_inputs = RevelationCircuit::new(
+            inputs.mapping_keys.clone(),
+            inputs.query_min_block,
+            inputs.query_max_block + 2,
+        )?;
+        assert!(prover
+            .prove(logic_inputs, inputs.query2_proof.serialize()?)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_verifier_fingerprint() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let proof = params.generate_proof(inputs.revelation_input::<L>()?)?;
+
+        let fingerprint = params.verifier_fingerprint();
+        // the fingerprint is recomputed from the verifier data of the parameters saved and
+        // loaded back, which are deserialized rather than shared with `params`
+        let path = env::temp_dir().join("revelation_fingerprint_params.bin");
+        params.save(&path)?;
+        let loaded = Parameters::<BLOCK_DB_DEPTH, L>::load(
+            &path,
+            circuits.query2.get_recursive_circuit_set(),
+            circuits.block_db.get_recursive_circuit_set(),
+        )?;
+        assert_eq!(loaded.verifier_fingerprint(), fingerprint);
+        let proof_with_vk = ProofWithVK::from_parts(
+            deserialize_proof(&proof)?,
+            loaded.circuit_data().verifier_only.clone(),
+        );
+        assert!(fingerprints_eq(
+            &proof_with_vk.vk_fingerprint(),
+            &fingerprint
+        ));
+        // parameters for another `L` have another fingerprint
+        let other_params = circuits.build_params::<{ L + 1 }>();
+        assert!(!fingerprints_eq(
+            &other_params.verifier_fingerprint(),
+            &fingerprint
+        ));
+        // the fingerprint survives the serialization of the proof
+        let proof_with_vk = ProofWithVK::deserialize(&proof_with_vk.serialize()?)?;
+        assert!(fingerprints_eq(
+            &proof_with_vk.vk_fingerprint(),
+            &fingerprint
+        ));
+        // a proof generated by another circuit is routed elsewhere
+        assert!(!fingerprints_eq(
+            &inputs.query2_proof.vk_fingerprint(),
+            &fingerprint
+        ));
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_proofs_batch() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let first_inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let second_inputs = circuits.generate_inputs(&random_mapping_keys(L - 1))?;
+        // another query answered against the same block db proof as the first one
+        let mapping_keys = random_mapping_keys(L - 1);
+        let third_inputs = TestInputs {
+            mapping_keys: mapping_keys.iter().map(|key| key.to_vec()).collect(),
+            query_min_block: first_inputs.query_min_block,
+            query_max_block: first_inputs.query_max_block,
+            query2_proof: circuits
+                .generate_query2_proof(&first_inputs.query2_values, &mapping_keys)?,
+            query2_values: first_inputs.query2_values.clone(),
+            block_db_proof: first_inputs.block_db_proof.clone(),
+        };
+
+        let proofs = params.generate_proofs_batch(vec![
+            first_inputs.revelation_input::<L>()?,
+            third_inputs.revelation_input::<L>()?,
+            second_inputs.revelation_input::<L>()?,
+        ])?;
+        assert_eq!(proofs.len(), 3);
+        // proofs are returned in the order of the inputs
+        for (proof, inputs) in proofs
+            .into_iter()
+            .zip([first_inputs, third_inputs, second_inputs])
+        {
+            params.verify_proof(proof.clone())?;
+            let expected = test_utils::expected_sorted_packed_keys::<L>(&inputs.mapping_keys);
+            let pis = params.inspect(&proof)?;
+            assert_eq!(
+                pis.total_matching().to_canonical_u64() as usize,
+                inputs.mapping_keys.len()
+            );
+            assert_eq!(
+                pis.nft_ids()[0],
+                F::from_canonical_u32(*expected[0].last().unwrap())
+            );
+        }
+        assert!(params.generate_proofs_batch(vec![])?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_duplicated_keys() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let mut inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        // more keys than L are provided, but only L of them are distinct
+        let duplicated_key = inputs.mapping_keys[0].clone();
+        inputs.mapping_keys.push(duplicated_key);
+        let input = inputs.revelation_input::<L>()?;
+        assert_eq!(input.num_duplicated_keys(), 1);
+        assert_eq!(input.logic_inputs.num_entries as usize, L);
+
+        let proof = params.generate_proof(input)?;
+        params.verify_proof(proof.clone())?;
+        let pis = params.inspect(&proof)?;
+        assert_eq!(pis.total_matching().to_canonical_u64(), L as u64);
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_repeated_keys_in_witness() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        // the query2/block proof aggregates the same key twice, so that a witness repeating
+        // the key matches its digest, and only the ordering of the keys can reject it
+        let key = random_mapping_keys(1)[0];
+        let inputs = circuits.generate_inputs(&[key, key])?;
+        let mut input = inputs.revelation_input::<L>()?;
+        let packed_key = input.logic_inputs.packed_keys[0];
+        input.logic_inputs.packed_keys = [packed_key; L];
+        input.logic_inputs.num_entries = L as u8;
+        #[cfg(feature = "catch_panics")]
+        assert!(params.generate_proof(input).is_err());
+        #[cfg(not(feature = "catch_panics"))]
+        crate::api::tests::check_panic!(
+            || params.generate_proof(input),
+            "proof generated for a witness repeating a key"
+        );
+
+        // a repeated key can't be hidden in the padding slots either
+        let inputs = circuits.generate_inputs(&[key])?;
+        let mut input = inputs.revelation_input::<L>()?;
+        assert_eq!(input.logic_inputs.num_entries, 1);
+        input.logic_inputs.packed_keys[1] = packed_key;
+        #[cfg(feature = "catch_panics")]
+        assert!(params.generate_proof(input).is_err());
+        #[cfg(not(feature = "catch_panics"))]
+        crate::api::tests::check_panic!(
+            || params.generate_proof(input),
+            "proof generated for a witness repeating a key in the padding slots"
+        );
+
+        // while the honest witness, with empty padding keys, is accepted
+        let proof = params.generate_proof(inputs.revelation_input::<L>()?)?;
+        params.verify_proof(proof)
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_unknown_query2_circuit() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let accepted = params.accepted_query2_circuit_digests();
+        assert!(accepted.contains(&inputs.query2_proof.circuit_digest_fingerprint()));
+
+        // a proof generated by a circuit outside of the query2/block set, here the block db one,
+        // is rejected before proving
+        let unrelated_proof = ProofWithVK::from_parts(
+            inputs.block_db_proof.clone(),
+            circuits.block_db.verifier_data_for_input_proofs::<1>()[0].clone(),
+        );
+        let fingerprint = unrelated_proof.circuit_digest_fingerprint();
+        assert!(!accepted.contains(&fingerprint));
+        let mut input = inputs.revelation_input::<L>()?;
+        input.query2_block_proof = unrelated_proof;
+        let err = params.generate_proof(input).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<RevelationError>(),
+            Some(&RevelationError::UnknownQuery2Circuit { fingerprint })
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_from_packed_keys() -> Result<()> {
+        const L: usize = 2;
+        let circuits = TestingCircuits::new();
+        let params = circuits.build_params::<L>();
+        let inputs = circuits.generate_inputs(&random_mapping_keys(L))?;
+        let packed_keys = inputs
+            .mapping_keys
+            .iter()
+            .map(|key| left_pad32(key).pack().try_into().unwrap())
+            .collect_vec();
+        let packed_input = RevelationRecursiveInput::<L>::from_packed_keys(
+            packed_keys,
+            inputs.query_min_block,
+            inputs.query_max_block,
+            inputs.query2_proof.serialize()?,
+            serialize_proof(&inputs.block_db_proof)?,
+        )?;
+        let input = inputs.revelation_input::<L>()?;
+        assert_eq!(
+            packed_input.logic_inputs.packed_keys,
+            input.logic_inputs.packed_keys
+        );
+        assert_eq!(
+            packed_input.logic_inputs.num_entries,
+            input.logic_inputs.num_entries
+        );
+
+        // both inputs are proven with the same public inputs
+        let packed_proof = params.generate_proof(packed_input)?;
+        let proof = params.generate_proof(input)?;
+        params.verify_proof(packed_proof.clone())?;
+        assert_eq!(
+            params.inspect(&packed_proof)?.inputs,
+            params.inspect(&proof)?.inputs
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_revelation_gas_estimate() {
+        let small = estimate_verification_gas(Parameters::<BLOCK_DB_DEPTH, 2>::num_public_inputs());
+        let large = estimate_verification_gas(Parameters::<BLOCK_DB_DEPTH, 8>::num_public_inputs());
+        // the fixed cost doesn't depend on the number of public inputs
+        assert_eq!(small.fixed, large.fixed);
+        assert_eq!(large.total() - small.total(), 6 * GAS_PER_PUBLIC_INPUT);
+        assert!(estimate_verification_gas(0).total() > 0);
+    }
+
+    #[test]
+    fn test_revelation_max_public_inputs() {
+        const MAX_L: usize = SOLIDITY_MAX_PI - RevelationPublicInputs::<Target, 0>::total_len();
+        assert_eq!(
+            Parameters::<BLOCK_DB_DEPTH, MAX_L>::num_public_inputs(),
+            SOLIDITY_MAX_PI
+        );
+        assert!(Parameters::<BLOCK_DB_DEPTH, MAX_L>::check_num_public_inputs().is_ok());
+        let err = Parameters::<BLOCK_DB_DEPTH, { MAX_L + 1 }>::check_num_public_inputs()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(&format!("reduce L to at most {MAX_L}")));
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_input_from_hex_keys() -> Result<()> {
+        const L: usize = 4;
+        // mixed-case digits, with and without prefix
+        let hex_keys = [
+            "0x1",
+            "0203",
+            "0X0a0B0c",
+            "1122334455667788990011223344556677889900112233445566778899AABBCC",
+        ];
+        let mapping_keys = hex_keys
+            .iter()
+            .map(|key| {
+                let key = key.trim_start_matches("0x").trim_start_matches("0X");
+                let key = if key.len() % 2 == 1 {
+                    format!("0{key}")
+                } else {
+                    key.to_string()
+                };
+                hex::decode(key).unwrap()
+            })
+            .collect_vec();
+        let circuits = TestingCircuits::new();
+        let padded_keys = mapping_keys.iter().map(|key| left_pad32(key)).collect_vec();
+        let inputs = circuits.generate_inputs(&padded_keys)?;
+        let query2_proof = inputs.query2_proof.serialize()?;
+        let block_db_proof = serialize_proof(&inputs.block_db_proof)?;
+
+        let from_bytes = RevelationRecursiveInput::<L>::new(
+            mapping_keys,
+            inputs.query_min_block,
+            inputs.query_max_block,
+            query2_proof.clone(),
+            block_db_proof.clone(),
+        )?;
+        let from_hex = RevelationRecursiveInput::<L>::from_hex_keys(
+            &hex_keys,
+            inputs.query_min_block,
+            inputs.query_max_block,
+            query2_proof.clone(),
+            block_db_proof.clone(),
+        )?;
+        assert_eq!(
+            from_hex.logic_inputs.packed_keys,
+            from_bytes.logic_inputs.packed_keys
+        );
+        assert_eq!(
+            from_hex.logic_inputs.num_entries,
+            from_bytes.logic_inputs.num_entries
+        );
+        // the proofs generated from both inputs expose the same public inputs
+        let params = circuits.build_params::<L>();
+        let (_, from_bytes_pis) = params.generate_proof_with_pis(from_bytes)?;
+        let (proof, from_hex_pis) = params.generate_proof_with_pis(from_hex)?;
+        assert_eq!(from_hex_pis.inputs, from_bytes_pis.inputs);
+        params.verify_proof(proof)?;
+
+        // keys which are not hex or longer than 32 bytes are rejected
+        let too_long = format!("0x{}", "ab".repeat(MAPPING_KEY_LEN + 1));
+        for invalid_key in ["0xzz", "0x0x01", too_long.as_str()] {
+            assert!(RevelationRecursiveInput::<L>::from_hex_keys(
+                &[invalid_key],
+                inputs.query_min_block,
+                inputs.query_max_block,
+                query2_proof.clone(),
+                block_db_proof.clone(),
+            )
+            .is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_revelation_multiple_query2_proofs() -> Result<()> {
+        const L: usize = 2;
+        const K: usize = 2;
+        let circuits = TestingCircuits::new_with_config(default_config());
+        let params = MultiParameters::<BLOCK_DB_DEPTH, L, K>::build(
+            circuits.query2.get_recursive_circuit_set(),
+            circuits.block_db.get_recursive_circuit_set(),
+            circuits.block_db.verifier_data_for_input_proofs::<1>()[0],
+        )?;
+        let mapping_keys = random_mapping_keys(L);
+        let inputs = circuits.generate_inputs(&mapping_keys)?;
+
+        // split the blocks of the query in two halves, each one matching one of the keys
+        let half_range = inputs.query2_values.range / F::TWO;
+        let first_values = Query2Values {
+            block_number: inputs.query2_values.block_number - half_range,
+            range: half_range,
+            ..inputs.query2_values.clone()
+        };
+        let second_values = Query2Values {
+            range: half_range,
+            ..inputs.query2_values.clone()
+        };
+        let query2_proofs = [
+            circuits.generate_query2_proof(&first_values, &mapping_keys[..1])?,
+            circuits.generate_query2_proof(&second_values, &mapping_keys[1..])?,
+        ]
+        .iter()
+        .map(|proof| proof.serialize())
+        .collect::<Result<Vec<_>, _>>()?;
+        let block_db_proof = serialize_proof(&inputs.block_db_proof)?;
+        let new_input = |mapping_keys: Vec<Vec<u8>>, query2_proofs: Vec<Vec<u8>>| {
+            MultiRevelationInput::<L, K>::new(
+                mapping_keys,
+                inputs.query_min_block,
+                inputs.query_max_block,
+                query2_proofs,
+                block_db_proof.clone(),
+            )
+        };
+
+        // the combined digest of the proofs matches the keys
+        let proof = params.generate_proof(new_input(
+            inputs.mapping_keys.clone(),
+            query2_proofs.clone(),
+        )?)?;
+        params.verify_proof(proof.clone())?;
+        let pis = params.inspect(&proof)?;
+        assert_eq!(pis.total_matching().to_canonical_u64() as usize, L);
+        // the exposed range spans the blocks of both proofs
+        assert_eq!(pis.block_number(), inputs.query2_values.block_number);
+        assert_eq!(pis.range(), inputs.query2_values.range);
+
+        // the parameters can be saved and loaded back, only for the same K
+        let path = env::temp_dir().join("multi_revelation_params.bin");
+        params.save(&path)?;
+        let query2_block_set = circuits.query2.get_recursive_circuit_set();
+        let block_db_set = circuits.block_db.get_recursive_circuit_set();
+        let loaded =
+            MultiParameters::<BLOCK_DB_DEPTH, L, K>::load(&path, query2_block_set, block_db_set)?;
+        loaded.verify_proof(proof)?;
+        assert!(MultiParameters::<BLOCK_DB_DEPTH, L, 3>::load(
+            &path,
+            query2_block_set,
+            block_db_set
+        )
+        .is_err());
+
+        // the combined digest doesn't match a key found by none of the proofs
+        let mismatching_keys = vec![
+            inputs.mapping_keys[0].clone(),
+            random_mapping_keys(1)[0].to_vec(),
+        ];
+        let input = new_input(mismatching_keys, query2_proofs.clone())?;
+        #[cfg(feature = "catch_panics")]
+        assert!(params.generate_proof(input).is_err());
+        #[cfg(not(feature = "catch_panics"))]
+        crate::api::tests::check_panic!(
+            || params.generate_proof(input),
+            "proof generated for keys not matching the query2/block proofs"
+        );
+
+        // the proofs must be provided in increasing order of blocks
+        let reversed_proofs = query2_proofs.iter().rev().cloned().collect_vec();
+        assert!(new_input(inputs.mapping_keys.clone(), reversed_proofs).is_err());
+        assert!(new_input(inputs.mapping_keys.clone(), query2_proofs[..1].to_vec()).is_err());
+
+        // the proofs must cover consecutive ranges of blocks: a block skipped between the two
+        // proofs is rejected
+        let gap_values = Query2Values {
+            block_number: first_values.block_number - F::ONE,
+            range: first_values.range - F::ONE,
+            ..first_values.clone()
+        };
+        let gapped_proofs = vec![
+            circuits
+                .generate_query2_proof(&gap_values, &mapping_keys[..1])?
+                .serialize()?,
+            query2_proofs[1].clone(),
+        ];
+        let err = new_input(inputs.mapping_keys.clone(), gapped_proofs)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("consecutive ranges"), "{err}");
+        Ok(())
+    }
 }
@@ -0,0 +1,299 @@
+//! Revelation circuit answering a query whose results are aggregated from several query2/block
+//! proofs, each one covering a distinct range of blocks, against the same block db proof
+use std::path::Path;
+
+use anyhow::{ensure, Result};
+use itertools::Itertools;
+use plonky2::{
+    field::types::PrimeField64,
+    hash::poseidon::PoseidonHash,
+    iop::target::Target,
+    plonk::{
+        circuit_builder::CircuitBuilder,
+        circuit_data::{CircuitData, VerifierCircuitData, VerifierOnlyCircuitData},
+        config::Hasher,
+        proof::ProofWithPublicInputs,
+    },
+};
+use recursion_framework::{
+    framework::{
+        RecursiveCircuits, RecursiveCircuitsVerifierGagdet, RecursiveCircuitsVerifierTarget,
+        WhitelistedCircuitsVerifierTarget,
+    },
+    serialization::{deserialize, deserialize_vec, serialize, serialize_vec},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{default_config, deserialize_proof, serialize_proof, ProofWithVK, C, D, F},
+    block::{Parameters as BlockDbParameters, PublicInputs as BlockDbPublicInputs},
+    query2::block,
+};
+
+use super::{
+    assign_block_db_witness, assign_query_witness, catch_panics, check_block_db_verifier_datas,
+    check_proofs_consistency, circuit::RevelationWires, deserialize_block_db_proof, inspect_proof,
+    load_parameters, pack_mapping_keys, save_parameters, BlockRangeVisibility, CommitmentHash,
+    QueryCommitment, RevelationCircuit, RevelationPublicInputs, SaltPolicy, SavedParameters,
+    SavedParametersRef, BLOCK_DB_NUM_IO, QUERY2_BLOCK_NUM_IO,
+};
+
+/// Same as `Parameters`, but the revelation circuit verifies `K` query2/block proofs rather
+/// than one, and the revealed keys are the ones aggregated by all of them. The proofs must
+/// answer the same query over consecutive ranges of blocks, sorted in increasing order, of the
+/// same block db. The proofs expose the same public inputs as the ones generated by
+/// `Parameters`, where the block number and the range are the ones spanning all the proofs.
+#[derive(Serialize, Deserialize)]
+pub struct MultiParameters<const BLOCK_DB_DEPTH: usize, const L: usize, const K: usize> {
+    revelation: RevelationWires<L>,
+    /// The wires to verify each of the `K` proofs in the query2/block circuit set
+    query2_blocks: Vec<RecursiveCircuitsVerifierTarget<D>>,
+    query2_block_circuit_set: RecursiveCircuits<F, C, D>,
+    block_db: WhitelistedCircuitsVerifierTarget<D>,
+    #[serde(serialize_with = "serialize_vec", deserialize_with = "deserialize_vec")]
+    block_db_verifier_datas: Vec<VerifierOnlyCircuitData<C, D>>,
+    num_gates: usize,
+    #[serde(serialize_with = "serialize", deserialize_with = "deserialize")]
+    circuit_data: CircuitData<F, C, D>,
+}
+
+/// Circuit inputs for the revelation step verifying `K` query2/block proofs
+pub struct MultiRevelationInput<const L: usize, const K: usize> {
+    logic_inputs: RevelationCircuit<L>,
+    /// The top proofs generated by the query2/block module, sorted by block range
+    query2_block_proofs: Vec<ProofWithVK>,
+    block_db_proof: ProofWithPublicInputs<F, C, D>,
+}
+
+impl<const L: usize, const K: usize> MultiRevelationInput<L, K> {
+    /// Same as `RevelationRecursiveInput::new`, but the keys are the ones aggregated by the
+    /// `K` serialized `query2_block_proofs`, which must cover consecutive ranges of blocks
+    /// sorted in increasing order, all contained in the query block range
+    pub fn new(
+        mapping_keys: Vec<Vec<u8>>,
+        query_min_block: usize,
+        query_max_block: usize,
+        query2_block_proofs: Vec<Vec<u8>>,
+        block_db_proof: Vec<u8>,
+    ) -> Result<Self> {
+        ensure!(
+            query2_block_proofs.len() == K,
+            "{} query2/block proofs provided, while {} are expected",
+            query2_block_proofs.len(),
+            K,
+        );
+        catch_panics(|| {
+            let (logic_inputs, _) = RevelationCircuit::from_packed_keys(
+                &pack_mapping_keys(&mapping_keys)?,
+                query_min_block,
+                query_max_block,
+            )?;
+            let query2_block_proofs = query2_block_proofs
+                .iter()
+                .map(|proof| ProofWithVK::deserialize(proof))
+                .collect::<Result<Vec<_>, _>>()?;
+            let block_db_proof = deserialize_block_db_proof(&block_db_proof)?;
+            for query2_block_proof in &query2_block_proofs {
+                check_proofs_consistency(
+                    &logic_inputs,
+                    query2_block_proof,
+                    &block_db_proof.public_inputs,
+                )?;
+            }
+            check_consecutive_ranges(&query2_block_proofs)?;
+            Ok(Self {
+                logic_inputs,
+                query2_block_proofs,
+                block_db_proof,
+            })
+        })
+    }
+}
+
+/// Check that the query2/block proofs cover consecutive ranges of blocks sorted in increasing
+/// order, i.e. each proof starts right after the last block of the previous one, as required
+/// by the revelation circuit
+fn check_consecutive_ranges(query2_block_proofs: &[ProofWithVK]) -> Result<()> {
+    for (previous, next) in query2_block_proofs.iter().tuple_windows() {
+        let [previous, next] = [previous, next].map(|proof| {
+            block::BlockPublicInputs::<F>::from(&proof.proof().public_inputs[..QUERY2_BLOCK_NUM_IO])
+        });
+        let previous_max_block = previous.block_number().to_canonical_u64();
+        let next_min_block = (next.block_number().to_canonical_u64() + 1)
+            .saturating_sub(next.range().to_canonical_u64());
+        ensure!(
+            previous_max_block + 1 == next_min_block,
+            "query2/block proof ending at block {} is followed by a proof starting at block {}: \
+            the proofs must cover consecutive ranges of blocks in increasing order",
+            previous_max_block,
+            next_min_block,
+        );
+    }
+    Ok(())
+}
+
+impl<const BLOCK_DB_DEPTH: usize, const L: usize, const K: usize>
+    MultiParameters<BLOCK_DB_DEPTH, L, K>
+{
+    /// Same arguments as `Parameters::build`
+    pub fn build(
+        query2_block_set: &RecursiveCircuits<F, C, D>,
+        block_db_circuit_set: &RecursiveCircuits<F, C, D>,
+        block_db_verifier_data: &VerifierOnlyCircuitData<C, D>,
+    ) -> Result<Self>
+    where
+        [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+    {
+        catch_panics(|| {
+            Self::build_internal(
+                query2_block_set,
+                block_db_circuit_set,
+                std::slice::from_ref(block_db_verifier_data),
+            )
+        })
+    }
+    fn build_internal(
+        query2_block_set: &RecursiveCircuits<F, C, D>,
+        block_db_circuit_set: &RecursiveCircuits<F, C, D>,
+        block_db_verifier_datas: &[VerifierOnlyCircuitData<C, D>],
+    ) -> Result<Self>
+    where
+        [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+    {
+        check_block_db_verifier_datas(block_db_circuit_set, block_db_verifier_datas)?;
+        let mut b = CircuitBuilder::new(default_config());
+        let query2_block_verifier_gadget =
+            RecursiveCircuitsVerifierGagdet::<F, C, D, QUERY2_BLOCK_NUM_IO>::new(
+                default_config(),
+                query2_block_set,
+            );
+        let query2_blocks = (0..K)
+            .map(|_| query2_block_verifier_gadget.verify_proof_in_circuit_set(&mut b))
+            .collect_vec();
+        let query2_block_pis = query2_blocks
+            .iter()
+            .map(|wires| {
+                block::BlockPublicInputs::<Target>::from(
+                    wires.get_public_input_targets::<F, QUERY2_BLOCK_NUM_IO>(),
+                )
+            })
+            .collect_vec();
+        let block_db_verifier_gadget =
+            RecursiveCircuitsVerifierGagdet::<F, C, D, BLOCK_DB_NUM_IO>::new(
+                default_config(),
+                block_db_circuit_set,
+            );
+        let block_db_wires = block_db_verifier_gadget
+            .verify_proof_whitelisted_circuits_in_circuit_set(&mut b, block_db_verifier_datas)?;
+        let block_db_pi = BlockDbPublicInputs::from(
+            BlockDbParameters::<BLOCK_DB_DEPTH>::block_tree_public_input_targets(
+                block_db_wires.get_proof_target(),
+            ),
+        );
+
+        let wires = RevelationCircuit::build_multi::<BLOCK_DB_DEPTH>(
+            &mut b,
+            block_db_pi,
+            &query2_block_pis,
+            CommitmentHash::default(),
+            SaltPolicy::default(),
+            QueryCommitment::default(),
+            BlockRangeVisibility::default(),
+        )?;
+
+        let num_gates = b.num_gates();
+        let circuit_data = b.build::<C>();
+        Ok(Self {
+            revelation: wires,
+            query2_blocks,
+            query2_block_circuit_set: query2_block_set.clone(),
+            block_db: block_db_wires,
+            block_db_verifier_datas: block_db_verifier_datas.to_vec(),
+            num_gates,
+            circuit_data,
+        })
+    }
+    /// Same as `Parameters::save`
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let saved = SavedParametersRef {
+            revelation: &self.revelation,
+            query2_block: &self.query2_blocks,
+            query2_block_set_digest: self
+                .query2_block_circuit_set
+                .get_circuit_set_digest()
+                .flatten(),
+            block_db: &self.block_db,
+            block_db_verifier_datas: &self.block_db_verifier_datas,
+            num_gates: self.num_gates,
+            circuit_data: &self.circuit_data,
+        };
+        save_parameters(path, &saved)
+    }
+    /// Same as `Parameters::load`; an error is also returned if the parameters were saved for
+    /// another `K`
+    pub fn load(
+        path: &Path,
+        query2_block_set: &RecursiveCircuits<F, C, D>,
+        block_db_circuit_set: &RecursiveCircuits<F, C, D>,
+    ) -> Result<Self> {
+        catch_panics(|| {
+            let saved: SavedParameters<L, Vec<RecursiveCircuitsVerifierTarget<D>>> =
+                load_parameters(path, query2_block_set, block_db_circuit_set)?;
+            ensure!(
+                saved.query2_block.len() == K,
+                "revelation parameters saved in {} verify {} query2/block proofs, while {} are \
+                expected",
+                path.display(),
+                saved.query2_block.len(),
+                K,
+            );
+            Ok(Self {
+                revelation: saved.revelation,
+                query2_blocks: saved.query2_block,
+                query2_block_circuit_set: query2_block_set.clone(),
+                block_db: saved.block_db,
+                block_db_verifier_datas: saved.block_db_verifier_datas,
+                num_gates: saved.num_gates,
+                circuit_data: saved.circuit_data,
+            })
+        })
+    }
+    pub fn generate_proof(&self, inputs: MultiRevelationInput<L, K>) -> Result<Vec<u8>>
+    where
+        [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+    {
+        catch_panics(|| {
+            let mut pw = assign_block_db_witness(
+                &self.block_db,
+                &self.block_db_verifier_datas,
+                &inputs.block_db_proof,
+                None,
+            )?;
+            assign_query_witness(
+                &mut pw,
+                &self.revelation,
+                &self.query2_blocks,
+                &self.query2_block_circuit_set,
+                inputs.logic_inputs,
+                inputs.query2_block_proofs,
+            )?;
+            Ok(serialize_proof(&self.circuit_data.prove(pw)?)?)
+        })
+    }
+    pub fn verify_proof(&self, proof: Vec<u8>) -> Result<()> {
+        catch_panics(|| {
+            let proof = deserialize_proof(&proof)?;
+            self.circuit_data.verify(proof)
+        })
+    }
+    /// Same as `Parameters::inspect`
+    pub fn inspect(&self, proof: &[u8]) -> Result<RevelationPublicInputs<'static, F, L>> {
+        catch_panics(|| inspect_proof(&self.circuit_data.common, proof))
+    }
+    pub fn circuit_data(&self) -> &CircuitData<F, C, D> {
+        &self.circuit_data
+    }
+    pub fn verifier_data(&self) -> VerifierCircuitData<F, C, D> {
+        self.circuit_data.verifier_data()
+    }
+}
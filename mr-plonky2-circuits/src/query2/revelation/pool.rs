@@ -0,0 +1,165 @@
+//! Pool of workers generating revelation proofs for independent queries
+use std::{
+    future::Future,
+    panic::{catch_unwind, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    thread::{self, JoinHandle},
+};
+
+use anyhow::{anyhow, ensure, Result};
+use plonky2::{hash::poseidon::PoseidonHash, plonk::config::Hasher};
+use tokio::sync::mpsc::{channel, error::SendError, Receiver, Sender};
+
+use crate::api::F;
+
+use super::{Parameters, RevelationRecursiveInput};
+
+/// Slot where a worker stores the outcome of the proof generation for a `PendingProof`
+#[derive(Default)]
+struct ProofSlot {
+    result: Option<Result<Vec<u8>>>,
+    waker: Option<Waker>,
+}
+
+/// Future resolving to the revelation proof generated by the pool for a submitted input
+pub struct PendingProof(Arc<Mutex<ProofSlot>>);
+
+impl Future for PendingProof {
+    type Output = Result<Vec<u8>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = self.0.lock().unwrap();
+        match slot.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+struct Job<const L: usize> {
+    input: RevelationRecursiveInput<L>,
+    slot: Arc<Mutex<ProofSlot>>,
+}
+
+/// Pool of worker threads generating revelation proofs with the same `Parameters`.
+/// At most `num_workers` proofs are generated concurrently, while at most `queue_size`
+/// inputs are waiting for a worker; `submit` waits, without blocking the executor, when the
+/// queue is full, bounding the memory employed by the pool.
+pub struct RevelationProverPool<const BLOCK_DB_DEPTH: usize, const L: usize> {
+    params: Arc<Parameters<BLOCK_DB_DEPTH, L>>,
+    queue: Option<Sender<Job<L>>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl<const BLOCK_DB_DEPTH: usize, const L: usize> RevelationProverPool<BLOCK_DB_DEPTH, L>
+where
+    [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+{
+    /// Instantiate a pool of `num_workers` threads generating proofs with `params`
+    pub fn new(
+        params: Parameters<BLOCK_DB_DEPTH, L>,
+        num_workers: usize,
+        queue_size: usize,
+    ) -> Result<Self> {
+        ensure!(
+            num_workers > 0,
+            "revelation prover pool needs at least one worker"
+        );
+        ensure!(
+            queue_size > 0,
+            "revelation prover pool needs a queue of at least one input"
+        );
+        let params = Arc::new(params);
+        let (sender, receiver) = channel(queue_size);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..num_workers)
+            .map(|i| {
+                let params = params.clone();
+                let receiver = receiver.clone();
+                thread::Builder::new()
+                    .name(format!("revelation-prover-{}", i))
+                    .spawn(move || Self::run_worker(&params, &receiver))
+                    .map_err(|e| anyhow!("failed to spawn revelation prover worker: {}", e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            params,
+            queue: Some(sender),
+            workers,
+        })
+    }
+
+    /// Enqueue the generation of a revelation proof for `input`, waiting for a free slot in
+    /// the queue if it is full, and return a future that resolves to the serialized proof
+    pub async fn submit(&self, input: RevelationRecursiveInput<L>) -> PendingProof {
+        let (job, pending_proof) = Self::job(input);
+        let sent = self.queue.as_ref().unwrap().send(job).await;
+        Self::enqueued(sent, pending_proof)
+    }
+
+    /// Same as `submit`, but for callers outside of async code: the current thread is blocked
+    /// while the queue is full, so this method panics if called within an async runtime
+    pub fn submit_blocking(&self, input: RevelationRecursiveInput<L>) -> PendingProof {
+        let (job, pending_proof) = Self::job(input);
+        let sent = self.queue.as_ref().unwrap().blocking_send(job);
+        Self::enqueued(sent, pending_proof)
+    }
+
+    fn job(input: RevelationRecursiveInput<L>) -> (Job<L>, PendingProof) {
+        let slot = Arc::new(Mutex::new(ProofSlot::default()));
+        let job = Job {
+            input,
+            slot: slot.clone(),
+        };
+        (job, PendingProof(slot))
+    }
+
+    /// Report in `pending_proof` the failure to enqueue its job, if any: the queue is dropped
+    /// only with the pool, so sending can fail only if all the workers have exited, which
+    /// should never happen as panics are caught in workers
+    fn enqueued(sent: Result<(), SendError<Job<L>>>, pending_proof: PendingProof) -> PendingProof {
+        if sent.is_err() {
+            pending_proof.0.lock().unwrap().result =
+                Some(Err(anyhow!("revelation prover pool is closed")));
+        }
+        pending_proof
+    }
+
+    /// Parameters employed by the workers to generate proofs
+    pub fn parameters(&self) -> &Parameters<BLOCK_DB_DEPTH, L> {
+        &self.params
+    }
+
+    fn run_worker(params: &Parameters<BLOCK_DB_DEPTH, L>, queue: &Mutex<Receiver<Job<L>>>) {
+        loop {
+            // the lock is released as soon as a job is received
+            let job = match queue.lock().unwrap().blocking_recv() {
+                Some(job) => job,
+                // the pool has been dropped
+                None => return,
+            };
+            let result = catch_unwind(AssertUnwindSafe(|| params.generate_proof(job.input)))
+                .unwrap_or_else(|_| Err(anyhow!("revelation proof generation panicked")));
+            let mut slot = job.slot.lock().unwrap();
+            slot.result = Some(result);
+            if let Some(waker) = slot.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl<const BLOCK_DB_DEPTH: usize, const L: usize> Drop for RevelationProverPool<BLOCK_DB_DEPTH, L> {
+    fn drop(&mut self) {
+        // closing the queue makes the workers exit once the pending jobs are processed
+        self.queue.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Prover generating revelation proofs for several queries answered against the same block
+//! db proof, whose assignment is computed only once
+use anyhow::Result;
+use plonky2::{
+    hash::poseidon::PoseidonHash,
+    iop::witness::PartialWitness,
+    plonk::{circuit_data::VerifierOnlyCircuitData, config::Hasher},
+};
+
+use crate::api::{serialize_proof, ProofWithVK, C, D, F};
+
+use super::{
+    assign_block_db_witness, assign_query_witness, catch_panics, check_proofs_consistency,
+    deserialize_block_db_proof, Parameters, RevelationCircuit,
+};
+
+/// Generate revelation proofs with the same `Parameters` and a pinned block db proof. The
+/// witness with the block db proof assigned is built once in `new`, and it is then cloned
+/// for each proof, where only the query2/block proof and the circuit logic are assigned.
+pub struct RevelationProver<'a, const BLOCK_DB_DEPTH: usize, const L: usize> {
+    params: &'a Parameters<BLOCK_DB_DEPTH, L>,
+    /// Public inputs of the pinned block db proof, checked against each query before proving
+    block_db_inputs: Vec<F>,
+    /// Witness with only the pinned block db proof assigned
+    block_db_witness: PartialWitness<F>,
+}
+
+impl<'a, const BLOCK_DB_DEPTH: usize, const L: usize> RevelationProver<'a, BLOCK_DB_DEPTH, L> {
+    /// Pin the serialized `block_db_proof` to the prover; the parameters must accept a single
+    /// block db circuit, otherwise `new_with_block_db_verifier_data` must be employed
+    pub fn new(params: &'a Parameters<BLOCK_DB_DEPTH, L>, block_db_proof: &[u8]) -> Result<Self> {
+        Self::new_internal(params, block_db_proof, None)
+    }
+
+    /// Same as `new`, but for a block db proof generated by the circuit with verifier data
+    /// `block_db_verifier_data`, among the ones accepted by the parameters
+    pub fn new_with_block_db_verifier_data(
+        params: &'a Parameters<BLOCK_DB_DEPTH, L>,
+        block_db_proof: &[u8],
+        block_db_verifier_data: &VerifierOnlyCircuitData<C, D>,
+    ) -> Result<Self> {
+        Self::new_internal(params, block_db_proof, Some(block_db_verifier_data))
+    }
+
+    fn new_internal(
+        params: &'a Parameters<BLOCK_DB_DEPTH, L>,
+        block_db_proof: &[u8],
+        block_db_verifier_data: Option<&VerifierOnlyCircuitData<C, D>>,
+    ) -> Result<Self> {
+        catch_panics(|| {
+            let block_db_proof = deserialize_block_db_proof(block_db_proof)?;
+            let block_db_witness = assign_block_db_witness(
+                &params.block_db,
+                &params.block_db_verifier_datas,
+                &block_db_proof,
+                block_db_verifier_data,
+            )?;
+            Ok(Self {
+                params,
+                block_db_inputs: block_db_proof.public_inputs,
+                block_db_witness,
+            })
+        })
+    }
+
+    /// Generate a revelation proof for the query of `logic_inputs`, answered by the serialized
+    /// `query2_block_proof` against the pinned block db proof
+    pub fn prove(
+        &self,
+        logic_inputs: RevelationCircuit<L>,
+        query2_block_proof: Vec<u8>,
+    ) -> Result<Vec<u8>>
+    where
+        [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+    {
+        catch_panics(|| {
+            let query2_block_proof = ProofWithVK::deserialize(&query2_block_proof)?;
+            check_proofs_consistency(&logic_inputs, &query2_block_proof, &self.block_db_inputs)?;
+            let mut pw = self.block_db_witness.clone();
+            assign_query_witness(
+                &mut pw,
+                &self.params.revelation,
+                std::slice::from_ref(&self.params.query2_block),
+                &self.params.query2_block_circuit_set,
+                logic_inputs,
+                vec![query2_block_proof],
+            )?;
+            Ok(serialize_proof(&self.params.circuit_data.prove(pw)?)?)
+        })
+    }
+}
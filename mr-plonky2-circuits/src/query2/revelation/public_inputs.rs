@@ -1,120 +1,139 @@
-use std::array::from_fn as create_array;
+use std::{array::from_fn as create_array, borrow::Cow};
 
+use anyhow::{anyhow, ensure, Result};
+use ethers::types::{Address, Bytes, U256};
+use mrp2_public_inputs::revelation::{self, Region};
 use plonky2::{
-    field::goldilocks_field::GoldilocksField, iop::target::Target,
-    plonk::circuit_builder::CircuitBuilder,
+    field::{
+        goldilocks_field::GoldilocksField,
+        types::{Field, PrimeField64},
+    },
+    hash::hash_types::{HashOut, HashOutTarget, NUM_HASH_OUT_ELTS},
+    iop::target::Target,
+    plonk::{circuit_builder::CircuitBuilder, config::GenericHashOut},
 };
 use plonky2_crypto::u32::arithmetic_u32::U32Target;
+use serde::Serialize;
 
-use crate::{keccak::OutputHash, types::PackedAddressTarget};
-
-#[derive(Clone, Copy, Debug)]
-#[repr(u8)]
-enum Inputs<const L: usize> {
-    BlockNumber,
-    Range,
-    MinBlockNumber,
-    MaxBlockNumber,
-    SmartContractAddress,
-    UserAddress,
-    MappingSlot,
-    MappingSlotLength,
-    NftIds,
-    BlockHeader,
+use crate::{
+    eth::try_left_pad32,
+    keccak::{OutputHash, HASH_LEN},
+    types::{HashOutput, PackedAddressTarget},
+    utils::convert_u32_fields_to_u8_vec,
+};
+
+use super::circuit::{CommitmentHash, QueryCommitment, RESULT_COMMITMENT_LEN};
+
+/// Return the name and the range of each region of the public inputs exposed by a revelation
+/// proof revealing `l` NFT IDs; this is meant for tools handling revelation proofs for any
+/// `L`, which cannot employ `RevelationPublicInputs`
+pub fn revelation_layout(l: usize) -> Vec<(&'static str, std::ops::Range<usize>)> {
+    revelation::layout(l)
 }
-impl<const L: usize> Inputs<L> {
-    const SIZES: [usize; 10] = [
-        // Block number
-        1,
-        // Range
-        1,
-        // Min block number
-        1,
-        // Max block number
-        1,
-        // Smart contract address
-        PackedAddressTarget::LEN,
-        // User address
-        PackedAddressTarget::LEN,
-        // Mapping Slot
-        1,
-        // Mapping slot length
-        1,
-        // L × NFT ID as u32
-        L,
-        // Block Header
-        OutputHash::LEN,
-    ];
-
-    const fn total_len() -> usize {
-        Self::SIZES[0]
-            + Self::SIZES[1]
-            + Self::SIZES[2]
-            + Self::SIZES[3]
-            + Self::SIZES[4]
-            + Self::SIZES[5]
-            + Self::SIZES[6]
-            + Self::SIZES[7]
-            + Self::SIZES[8]
-            + Self::SIZES[9]
-    }
-
-    fn range(&self) -> std::ops::Range<usize> {
-        let mut offset = 0;
-        let me = *self as u8;
-        for i in 0..me {
-            offset += Self::SIZES[i as usize];
-        }
 
-        offset..offset + Self::SIZES[me as usize]
-    }
+/// Decoded contents of a revelation proof, meant to be stored by off-chain indexers, e.g. as
+/// JSON with `serde_json::to_value`; the field names are part of the serialized format
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RevelationSummary {
+    /// NFT IDs in the revealed window, as returned by the Solidity verifier, in decimal
+    pub nft_ids: Vec<String>,
+    /// Minimum block number of the query, `None` if the proof doesn't expose it
+    pub min_block: Option<u32>,
+    /// Maximum block number of the query, `None` if the proof doesn't expose it
+    pub max_block: Option<u32>,
+    /// Root of the block db the proof is anchored to, as a `0x`-prefixed hex string
+    pub block_db_root: String,
 }
 
 #[derive(Clone)]
 pub struct RevelationPublicInputs<'input, T: Clone, const L: usize> {
-    pub inputs: &'input [T],
+    pub inputs: Cow<'input, [T]>,
 }
 
 impl<'a, T: Clone + Copy, const L: usize> From<&'a [T]> for RevelationPublicInputs<'a, T, L> {
     fn from(inputs: &'a [T]) -> Self {
         assert_eq!(inputs.len(), Self::total_len());
-        Self { inputs }
+        Self {
+            inputs: Cow::Borrowed(inputs),
+        }
+    }
+}
+
+impl<T: Clone + Copy, const L: usize> From<Vec<T>> for RevelationPublicInputs<'static, T, L> {
+    fn from(inputs: Vec<T>) -> Self {
+        assert_eq!(inputs.len(), Self::total_len());
+        Self {
+            inputs: Cow::Owned(inputs),
+        }
     }
 }
 
 impl<'a, T: Clone + Copy, const L: usize> RevelationPublicInputs<'a, T, L> {
     fn block_number_raw(&self) -> &[T] {
-        &self.inputs[Inputs::<L>::BlockNumber.range()]
+        &self.inputs[Region::BlockNumber.range(L)]
     }
     fn range_raw(&self) -> &[T] {
-        &self.inputs[Inputs::<L>::Range.range()]
+        &self.inputs[Region::Range.range(L)]
     }
     fn min_block_number_raw(&self) -> &[T] {
-        &self.inputs[Inputs::<L>::MinBlockNumber.range()]
+        &self.inputs[Region::MinBlockNumber.range(L)]
     }
     fn max_block_number_raw(&self) -> &[T] {
-        &self.inputs[Inputs::<L>::MaxBlockNumber.range()]
+        &self.inputs[Region::MaxBlockNumber.range(L)]
     }
     fn smart_contract_address_raw(&self) -> &[T] {
-        &self.inputs[Inputs::<L>::SmartContractAddress.range()]
+        &self.inputs[Region::SmartContractAddress.range(L)]
     }
     fn user_address_raw(&self) -> &[T] {
-        &self.inputs[Inputs::<L>::UserAddress.range()]
+        &self.inputs[Region::UserAddress.range(L)]
     }
     fn mapping_slot_raw(&self) -> &[T] {
-        &self.inputs[Inputs::<L>::MappingSlot.range()]
+        &self.inputs[Region::MappingSlot.range(L)]
     }
     fn mapping_slot_length_raw(&self) -> &[T] {
-        &self.inputs[Inputs::<L>::MappingSlotLength.range()]
+        &self.inputs[Region::MappingSlotLength.range(L)]
     }
     fn nft_ids_raw(&self) -> &[T] {
-        &self.inputs[Inputs::<L>::NftIds.range()]
+        &self.inputs[Region::NftIds.range(L)]
     }
     fn block_header_raw(&self) -> &[T] {
-        &self.inputs[Inputs::<L>::BlockHeader.range()]
+        &self.inputs[Region::BlockHeader.range(L)]
+    }
+    fn result_commitment_raw(&self) -> &[T] {
+        &self.inputs[Region::ResultCommitment.range(L)]
+    }
+    fn total_matching_raw(&self) -> &[T] {
+        &self.inputs[Region::TotalMatching.range(L)]
+    }
+    fn page_offset_raw(&self) -> &[T] {
+        &self.inputs[Region::PageOffset.range(L)]
+    }
+    fn page_limit_raw(&self) -> &[T] {
+        &self.inputs[Region::PageLimit.range(L)]
+    }
+    fn commitment_hash_raw(&self) -> &[T] {
+        &self.inputs[Region::CommitmentHash.range(L)]
+    }
+    fn salt_raw(&self) -> &[T] {
+        &self.inputs[Region::Salt.range(L)]
+    }
+    fn query_commitment_raw(&self) -> &[T] {
+        &self.inputs[Region::QueryCommitment.range(L)]
+    }
+    fn range_commitment_raw(&self) -> &[T] {
+        &self.inputs[Region::RangeCommitment.range(L)]
+    }
+    fn block_db_root_raw(&self) -> &[T] {
+        &self.inputs[Region::BlockDbRoot.range(L)]
+    }
+    fn client_address_raw(&self) -> &[T] {
+        &self.inputs[Region::ClientAddress.range(L)]
+    }
+    fn keys_commitment_raw(&self) -> &[T] {
+        &self.inputs[Region::KeysCommitment.range(L)]
     }
     pub const fn total_len() -> usize {
-        Inputs::<L>::total_len()
+        revelation::total_len(L)
     }
 }
 
@@ -133,19 +152,57 @@ impl<'a, const L: usize> RevelationPublicInputs<'a, Target, L> {
         // the block hash of the latest block inserted at time of building the circuit
         // i.e. the one who corresponds to the block db proof being verified here.
         lpn_latest_block: OutputHash,
+        // commitment to the sorted keys revealed by the query, for clients which
+        // store only the commitment on-chain and keep the result off-chain
+        result_commitment: &[Target; RESULT_COMMITMENT_LEN],
+        // number of keys matching the query, and window of the keys being revealed
+        total_matching: Target,
+        page_offset: Target,
+        page_limit: Target,
+        // hash function employed to compute the result commitment
+        commitment_hash: CommitmentHash,
+        // salt mixed in the result commitment, zero if the salt is not revealed
+        salt: Target,
+        // commitment to the parameters of the query, zero if they are exposed one by one
+        query_commitment: &[Target; RESULT_COMMITMENT_LEN],
+        // whether the parameters of the query are exposed or only their commitment, in which
+        // case the regions of the parameters are left out of the public inputs
+        query_commitment_mode: QueryCommitment,
+        // blinded commitment to the block range, zero if the block range is exposed
+        range_commitment: &HashOutTarget,
+        // root of the block db the block db proof being verified here is anchored to
+        block_db_root: &HashOutTarget,
+        // address of the client allowed to consume the proof
+        client_address: &PackedAddressTarget,
+        // Poseidon hash of the sorted packed keys matching the query, padding excluded
+        keys_commitment: &HashOutTarget,
     ) {
-        b.register_public_input(query_block_number);
-        b.register_public_input(query_range);
-        b.register_public_input(query_min_block);
-        b.register_public_input(query_max_block);
-        query_contract_address.register_as_public_input(b);
-        query_user_address.register_as_public_input(b);
-        b.register_public_input(query_mapping_slot);
-        b.register_public_input(mapping_slot_length);
+        if query_commitment_mode == QueryCommitment::Disabled {
+            b.register_public_input(query_block_number);
+            b.register_public_input(query_range);
+            b.register_public_input(query_min_block);
+            b.register_public_input(query_max_block);
+            query_contract_address.register_as_public_input(b);
+            query_user_address.register_as_public_input(b);
+            b.register_public_input(query_mapping_slot);
+            b.register_public_input(mapping_slot_length);
+        }
         for nft_id in query_nft_ids {
             b.register_public_input(nft_id.0);
         }
         b.register_public_inputs(&lpn_latest_block.to_targets().arr);
+        b.register_public_inputs(result_commitment);
+        b.register_public_input(total_matching);
+        b.register_public_input(page_offset);
+        b.register_public_input(page_limit);
+        let commitment_hash = b.constant(commitment_hash.to_field());
+        b.register_public_input(commitment_hash);
+        b.register_public_input(salt);
+        b.register_public_inputs(query_commitment);
+        b.register_public_inputs(&range_commitment.elements);
+        b.register_public_inputs(&block_db_root.elements);
+        client_address.register_as_public_input(b);
+        b.register_public_inputs(&keys_commitment.elements);
     }
 
     fn block_number(&self) -> Target {
@@ -200,10 +257,41 @@ impl<'a, const L: usize> RevelationPublicInputs<'a, Target, L> {
                 .unwrap(),
         )
     }
+
+    fn result_commitment(&self) -> &[Target] {
+        self.result_commitment_raw()
+    }
+
+    fn total_matching(&self) -> Target {
+        self.total_matching_raw()[0]
+    }
+
+    fn page_offset(&self) -> Target {
+        self.page_offset_raw()[0]
+    }
+
+    fn page_limit(&self) -> Target {
+        self.page_limit_raw()[0]
+    }
+}
+
+impl<const L: usize> RevelationPublicInputs<'static, GoldilocksField, L> {
+    /// Build the public inputs from the ones exposed by a revelation proof for `L`; the proofs
+    /// committing to the parameters of the query don't expose them, so zeros are put in place
+    /// of the parameters to find each region at the same position for any proof
+    pub fn from_exposed(inputs: Vec<GoldilocksField>) -> Self {
+        if inputs.len() == revelation::committed_total_len(L) {
+            let mut expanded = vec![GoldilocksField::ZERO; revelation::QUERY_PARAMS_LEN];
+            expanded.extend(inputs);
+            Self::from(expanded)
+        } else {
+            Self::from(inputs)
+        }
+    }
 }
 
 impl<'a, const L: usize> RevelationPublicInputs<'a, GoldilocksField, L> {
-    fn block_number(&self) -> GoldilocksField {
+    pub(crate) fn block_number(&self) -> GoldilocksField {
         self.block_number_raw()[0]
     }
 
@@ -219,7 +307,37 @@ impl<'a, const L: usize> RevelationPublicInputs<'a, GoldilocksField, L> {
         self.max_block_number_raw()[0]
     }
 
-    pub(crate) fn smart_contract_address(&self) -> &[GoldilocksField] {
+    /// Minimum block number of the block range of the query answered by the proof; an error
+    /// is returned for proofs exposing only a commitment to the query parameters or to the
+    /// block range
+    pub fn min_block(&self) -> Result<u32> {
+        self.exposed_block_number(self.min_block_number())
+    }
+
+    /// Maximum block number of the block range of the query answered by the proof; an error
+    /// is returned for proofs exposing only a commitment to the query parameters or to the
+    /// block range
+    pub fn max_block(&self) -> Result<u32> {
+        self.exposed_block_number(self.max_block_number())
+    }
+
+    fn exposed_block_number(&self, number: GoldilocksField) -> Result<u32> {
+        ensure!(
+            !self.is_query_committed(),
+            "the block range of the query is not exposed, as the proof exposes only a commitment to the query parameters",
+        );
+        ensure!(
+            !self.is_range_committed(),
+            "the block range of the query is not exposed, as the proof hides it behind a commitment",
+        );
+        let number = number.to_canonical_u64();
+        u32::try_from(number).map_err(|_| anyhow!("block number {} doesn't fit in a u32", number))
+    }
+
+    /// Address of the contract being queried, as packed u32 limbs; proofs built with
+    /// `QueryCommitment::Enabled` don't expose it, so it is zero if the public inputs of such
+    /// proofs are read with `from_exposed`
+    pub fn smart_contract_address(&self) -> &[GoldilocksField] {
         self.smart_contract_address_raw()
     }
 
@@ -242,4 +360,244 @@ impl<'a, const L: usize> RevelationPublicInputs<'a, GoldilocksField, L> {
     pub(crate) fn block_header(&self) -> &[GoldilocksField] {
         self.block_header_raw()
     }
+
+    /// Check that the revealed NFT IDs, decoded as in `decode_nft_ids`, are strictly increasing,
+    /// i.e. that they are sorted and without duplicates. Only the IDs in the revealed window are
+    /// checked, as the other ones are replaced by zeros. The circuit sorts the mapping keys in
+    /// the numeric order of their big-endian bytes, and the decoded IDs are the last 4 of these
+    /// bytes, so they are increasing as long as the revealed keys differ only in their last 4
+    /// bytes, as the keys of NFT IDs fitting in a u32 do.
+    pub fn assert_strictly_increasing(&self) -> Result<()> {
+        let ids = self.decode_nft_ids();
+        let window = self.revealed_window();
+        ids[window.clone()]
+            .windows(2)
+            .zip(window)
+            .try_for_each(|(pair, i)| {
+                ensure!(
+                    pair[0] < pair[1],
+                    "revealed NFT IDs are not strictly increasing: ID {} at position {} is followed by {}",
+                    pair[0],
+                    i,
+                    pair[1],
+                );
+                Ok(())
+            })
+    }
+
+    /// Return true if the proof is fresh with respect to the chain tip `current_block`, i.e. if
+    /// the maximum block number of the query is at most `max_age` blocks behind the tip. A
+    /// maximum block number beyond the tip is considered fresh. The freshness can't be
+    /// established for proofs exposing only a commitment to the query parameters or to the
+    /// block range, which are rejected.
+    pub fn is_fresh(&self, current_block: u32, max_age: u32) -> Result<bool> {
+        let query_max_block = self.max_block()?;
+        Ok(current_block.saturating_sub(query_max_block) <= max_age)
+    }
+
+    /// Check that the block range of the query answered by the proof is exactly `[min, max]`,
+    /// i.e. neither a subset nor a superset of the requested range. This check is meaningless
+    /// for proofs exposing only a commitment to the query parameters or to the block range,
+    /// which are rejected.
+    pub fn assert_range_eq(&self, min: u32, max: u32) -> Result<()> {
+        let proven_min = self.min_block()?;
+        let proven_max = self.max_block()?;
+        ensure!(
+            proven_min == min && proven_max == max,
+            "proof covers the block range [{}, {}], while [{}, {}] was requested",
+            proven_min,
+            proven_max,
+            min,
+            max,
+        );
+        Ok(())
+    }
+
+    /// Check that the number of matching keys exposed by the proof is consistent with the
+    /// revealed NFT IDs, i.e. that it doesn't exceed the number of slots and that all the
+    /// slots outside the revealed window, including padding ones, are zero.
+    pub fn verify_count_consistency(&self) -> Result<()> {
+        let total_matching = self.total_matching().to_canonical_u64();
+        ensure!(
+            total_matching <= L as u64,
+            "number of matching keys {} exceeds the number of slots {}",
+            total_matching,
+            L,
+        );
+        let window = self.revealed_window();
+        self.nft_ids()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !window.contains(i))
+            .try_for_each(|(i, id)| {
+                ensure!(
+                    id.is_zero(),
+                    "NFT ID {} at position {} is outside the revealed window {:?} of {} matching keys",
+                    id,
+                    i,
+                    window,
+                    total_matching,
+                );
+                Ok(())
+            })
+    }
+
+    /// Return each revealed NFT ID, decoded as in `revealed_nft_ids`, in the sorted order of
+    /// the proof, together with the index of the key it was derived from in `input_keys`, i.e.
+    /// the unsorted mapping keys provided by the caller to generate the proof. The NFT ID of a
+    /// key is given by its last 4 bytes read as a big-endian u32, so each key must be at most
+    /// 32 bytes long; each key is matched to at most one revealed ID, and the revealed IDs not
+    /// derived from any of the `input_keys` are skipped.
+    pub fn ids_with_original_index(&self, input_keys: &[Vec<u8>]) -> Result<Vec<(usize, U256)>> {
+        let input_ids = input_keys
+            .iter()
+            .map(|key| Ok(U256::from_big_endian(&try_left_pad32(key)?[32 - 4..])))
+            .collect::<Result<Vec<_>>>()?;
+        let mut consumed = vec![false; input_ids.len()];
+        Ok(self
+            .revealed_nft_ids()
+            .into_iter()
+            .filter_map(|id| {
+                let index = (0..input_ids.len()).find(|&i| !consumed[i] && input_ids[i] == id)?;
+                consumed[index] = true;
+                Some((index, id))
+            })
+            .collect())
+    }
+
+    /// Decode the `L` NFT IDs as the `processQuery` function of the Solidity verifier does,
+    /// i.e. reading each ID from the 4 low bytes of its public input, serialized as
+    /// little-endian, but interpreting them as a big-endian u32; the IDs outside the revealed
+    /// window are returned as well, as zeros
+    pub fn decode_nft_ids(&self) -> Vec<U256> {
+        self.decoder()
+            .decode_nft_ids()
+            .into_iter()
+            .map(U256::from)
+            .collect()
+    }
+
+    /// NFT IDs returned by the `processQuery` function of the Solidity verifier, i.e. the IDs
+    /// decoded as in `decode_nft_ids` found in the `revealed_window`. No ID is returned if no
+    /// key matches the query, so that an empty result can't be mistaken for a zero NFT ID.
+    pub fn revealed_nft_ids(&self) -> Vec<U256> {
+        self.decode_nft_ids()[self.revealed_window()].to_vec()
+    }
+
+    /// Number of keys matching the query, including the ones outside the revealed window
+    pub fn total_matching(&self) -> GoldilocksField {
+        self.total_matching_raw()[0]
+    }
+
+    /// Offset of the window of revealed keys
+    pub fn page_offset(&self) -> GoldilocksField {
+        self.page_offset_raw()[0]
+    }
+
+    /// Limit of the window of revealed keys
+    pub fn page_limit(&self) -> GoldilocksField {
+        self.page_limit_raw()[0]
+    }
+
+    /// Range of the positions of the revealed NFT IDs, i.e. the positions in the window
+    /// `[page_offset, page_offset + page_limit)` which are smaller than the number of
+    /// entries matching the query
+    pub fn revealed_window(&self) -> std::ops::Range<usize> {
+        self.decoder().revealed_window()
+    }
+
+    /// Decoder of the same public inputs provided by `mrp2_public_inputs`, which shares the
+    /// decoding logic with environments without the proving stack
+    fn decoder(&self) -> revelation::RevelationPublicInputs<'_, GoldilocksField, L> {
+        revelation::RevelationPublicInputs::new(&self.inputs).unwrap()
+    }
+
+    /// Poseidon result commitment, meaningful only if the commitment is computed with Poseidon
+    pub fn result_commitment(&self) -> HashOut<GoldilocksField> {
+        HashOut::from_partial(&self.result_commitment_raw()[..NUM_HASH_OUT_ELTS])
+    }
+
+    /// Keccak result commitment, meaningful only if the commitment is computed with Keccak
+    pub fn keccak_result_commitment(&self) -> [u8; HASH_LEN] {
+        convert_u32_fields_to_u8_vec(self.result_commitment_raw())
+            .try_into()
+            .unwrap()
+    }
+
+    /// Result commitment as exposed in the public inputs
+    pub fn raw_result_commitment(&self) -> &[GoldilocksField] {
+        self.result_commitment_raw()
+    }
+
+    /// Hash function employed to compute the result commitment
+    pub fn commitment_hash(&self) -> Result<CommitmentHash> {
+        CommitmentHash::from_field(self.commitment_hash_raw()[0])
+    }
+
+    /// Salt mixed in the result commitment, which is zero if the circuit doesn't reveal it
+    pub fn salt(&self) -> GoldilocksField {
+        self.salt_raw()[0]
+    }
+
+    /// Commitment to the parameters of the query, computed with the same hash as the result
+    /// commitment; it is zero if the circuit exposes the parameters one by one, otherwise the
+    /// parameters are exposed as zeros
+    pub fn query_commitment(&self) -> &[GoldilocksField] {
+        self.query_commitment_raw()
+    }
+
+    /// Return true if the proof exposes only a commitment to the parameters of the query
+    pub fn is_query_committed(&self) -> bool {
+        self.query_commitment().iter().any(|x| !x.is_zero())
+    }
+
+    /// Blinded commitment to the min and max block numbers of the query; it is zero if the
+    /// circuit exposes the block range, otherwise the block numbers are exposed as zeros
+    pub fn range_commitment(&self) -> HashOut<GoldilocksField> {
+        HashOut::from_partial(self.range_commitment_raw())
+    }
+
+    /// Return true if the proof hides the block range behind a commitment
+    pub fn is_range_committed(&self) -> bool {
+        self.range_commitment_raw().iter().any(|x| !x.is_zero())
+    }
+
+    /// Root of the block db the proof is anchored to, i.e., the root exposed by the block db
+    /// proof verified in the circuit, which can be checked against a known db snapshot
+    pub fn block_db_root(&self) -> HashOut<GoldilocksField> {
+        HashOut::from_partial(self.block_db_root_raw())
+    }
+
+    /// Root of the block db the proof is anchored to, serialized as bytes in the same way as
+    /// the db roots employed to reference block db proofs, e.g. in `BlockRef`
+    pub fn block_db_root_bytes(&self) -> HashOutput {
+        self.block_db_root().to_bytes().try_into().unwrap()
+    }
+
+    /// Address of the client allowed to consume the proof, zero if the proof is bound to no
+    /// client
+    pub fn client_address(&self) -> Address {
+        Address::from_slice(&convert_u32_fields_to_u8_vec(self.client_address_raw()))
+    }
+
+    /// Commitment to the set of keys matching the query, i.e., the Poseidon hash of the sorted
+    /// packed keys without the padding ones, which doesn't depend on the order the keys were
+    /// provided in nor on the revealed window
+    pub fn keys_commitment(&self) -> HashOut<GoldilocksField> {
+        HashOut::from_partial(self.keys_commitment_raw())
+    }
+
+    /// Decode the public inputs into a `RevelationSummary`, for off-chain indexing
+    pub fn summary(&self) -> RevelationSummary {
+        RevelationSummary {
+            nft_ids: self
+                .revealed_nft_ids()
+                .iter()
+                .map(|id| id.to_string())
+                .collect(),
+            min_block: self.min_block().ok(),
+            max_block: self.max_block().ok(),
+            block_db_root: Bytes::from(self.block_db_root_bytes().to_vec()).to_string(),
+        }
+    }
 }
@@ -0,0 +1,142 @@
+//! Query answered by a revelation proof, as exposed by its public inputs
+use anyhow::{anyhow, ensure, Result};
+use ethers::types::{Address, U256};
+use itertools::Itertools;
+use plonky2::{
+    field::types::{Field, PrimeField64},
+    hash::poseidon::PoseidonHash,
+    plonk::config::Hasher,
+};
+
+use crate::{
+    api::F,
+    types::PACKED_ADDRESS_LEN,
+    utils::{
+        convert_u32_fields_to_u8_vec, convert_u8_slice_to_u32_fields, convert_u8_to_u32_slice,
+        keccak256,
+    },
+};
+
+use super::{
+    circuit::{CommitmentHash, RESULT_COMMITMENT_LEN},
+    RevelationPublicInputs,
+};
+
+/// Query over a mapping of a smart contract, asking the NFT IDs owned by an user over a
+/// range of blocks
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Query {
+    /// Address of the smart contract storing the mapping
+    pub contract_address: Address,
+    /// Address of the user owning the NFT IDs
+    pub user_address: Address,
+    /// Storage slot of the mapping
+    pub mapping_slot: u64,
+    /// Storage slot of the length of the mapping
+    pub length_slot: u64,
+    /// First block of the queried range
+    pub min_block_number: u32,
+    /// Last block of the queried range
+    pub max_block_number: u32,
+    /// Hash of the latest block of the block db employed to prove the query
+    pub block_hash: U256,
+}
+
+impl Query {
+    /// Reconstruct the query answered by a revelation proof from its public inputs. The public
+    /// inputs must be taken from a proof which has already been verified, as no check is
+    /// performed here besides the ranges of the values.
+    pub fn from_public_inputs<const L: usize>(pis: &RevelationPublicInputs<F, L>) -> Result<Self> {
+        ensure!(
+            !pis.is_query_committed(),
+            "revelation proof exposes only a commitment to the query parameters",
+        );
+        ensure!(
+            !pis.is_range_committed(),
+            "revelation proof exposes only a commitment to the block range of the query",
+        );
+        let min_block_number = to_u32(pis.min_block_number(), "min block number")?;
+        let max_block_number = to_u32(pis.max_block_number(), "max block number")?;
+        // the block hash is exposed as u32 limbs, starting from the least significant one
+        for limb in pis.block_header() {
+            to_u32(*limb, "block hash limb")?;
+        }
+        let block_hash =
+            U256::from_little_endian(&convert_u32_fields_to_u8_vec(pis.block_header()));
+        Ok(Self {
+            contract_address: unpack_address(pis.smart_contract_address())?,
+            user_address: unpack_address(pis.user_address())?,
+            mapping_slot: pis.mapping_slot().to_canonical_u64(),
+            length_slot: pis.mapping_slot_length().to_canonical_u64(),
+            min_block_number,
+            max_block_number,
+            block_hash,
+        })
+    }
+
+    /// Compute the commitment to the parameters of the query exposed by a revelation proof
+    /// whose circuit is built with `QueryCommitment::Enabled`, where `commitment_hash` is the
+    /// hash employed for the result commitment. The Poseidon commitment is the hash of the
+    /// min and max block numbers, of the u32 limbs of the contract and user addresses and of
+    /// the mapping and length slots, padded with zeros; the Keccak commitment is
+    /// `keccak256(min_block || max_block || contract || user || mapping_slot || length_slot)`,
+    /// where the block numbers and the slots are encoded in 8 little-endian bytes. The block
+    /// hash is not part of the query parameters.
+    pub fn commitment(&self, commitment_hash: CommitmentHash) -> [F; RESULT_COMMITMENT_LEN] {
+        let elements = match commitment_hash {
+            CommitmentHash::Poseidon => {
+                let inputs = [self.min_block_number, self.max_block_number]
+                    .into_iter()
+                    .chain(convert_u8_to_u32_slice(self.contract_address.as_bytes()))
+                    .chain(convert_u8_to_u32_slice(self.user_address.as_bytes()))
+                    .map(F::from_canonical_u32)
+                    .chain(
+                        [self.mapping_slot, self.length_slot]
+                            .into_iter()
+                            .map(F::from_canonical_u64),
+                    )
+                    .collect_vec();
+                PoseidonHash::hash_no_pad(&inputs)
+                    .elements
+                    .into_iter()
+                    .chain(std::iter::repeat(F::ZERO))
+                    .take(RESULT_COMMITMENT_LEN)
+                    .collect_vec()
+            }
+            CommitmentHash::Keccak => {
+                let preimage = [self.min_block_number as u64, self.max_block_number as u64]
+                    .iter()
+                    .flat_map(|x| x.to_le_bytes())
+                    .chain(self.contract_address.as_bytes().iter().copied())
+                    .chain(self.user_address.as_bytes().iter().copied())
+                    .chain(
+                        [self.mapping_slot, self.length_slot]
+                            .iter()
+                            .flat_map(|x| x.to_le_bytes()),
+                    )
+                    .collect_vec();
+                convert_u8_slice_to_u32_fields(&keccak256(&preimage))
+            }
+        };
+        elements.try_into().unwrap()
+    }
+}
+
+fn to_u32(value: F, name: &str) -> Result<u32> {
+    let value = value.to_canonical_u64();
+    u32::try_from(value).map_err(|_| anyhow!("{} {} does not fit in 32 bits", name, value))
+}
+
+/// Unpack an address exposed as u32 limbs with little-endian bytes
+fn unpack_address(limbs: &[F]) -> Result<Address> {
+    ensure!(
+        limbs.len() == PACKED_ADDRESS_LEN,
+        "packed address has {} limbs, expected {}",
+        limbs.len(),
+        PACKED_ADDRESS_LEN,
+    );
+    for limb in limbs {
+        to_u32(*limb, "address limb")?;
+    }
+    Ok(Address::from_slice(&convert_u32_fields_to_u8_vec(limbs)))
+}
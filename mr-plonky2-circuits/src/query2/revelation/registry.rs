@@ -0,0 +1,258 @@
+//! Registry building lazily the revelation `Parameters` for a set of `L` chosen at compile time,
+//! and serving them by the `L` requested at runtime
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, bail, Result};
+use plonky2::{
+    hash::poseidon::PoseidonHash,
+    plonk::{circuit_data::VerifierOnlyCircuitData, config::Hasher},
+};
+use recursion_framework::framework::RecursiveCircuits;
+
+use crate::api::{C, D, F};
+
+use super::{Parameters, ParametersBuilder, ParametersInfo, RevelationRecursiveInput};
+
+/// Revelation `Parameters` for any `L`, with the inputs of the proofs provided in their
+/// serialized form, so that parameters for different `L` can be handled through the same type
+pub trait DynParameters: Send + Sync {
+    /// Number of NFT IDs revealed by the circuit
+    fn l(&self) -> usize;
+    /// Number of public inputs exposed by the revelation proofs
+    fn num_public_inputs(&self) -> usize;
+    /// Shape of the parameters, as returned by `Parameters::describe`
+    fn describe(&self) -> ParametersInfo;
+    /// Generate a revelation proof for the inputs expected by `RevelationRecursiveInput::new`
+    fn generate_proof(
+        &self,
+        mapping_keys: Vec<Vec<u8>>,
+        query_min_block: usize,
+        query_max_block: usize,
+        query2_block_proof: Vec<u8>,
+        block_db_proof: Vec<u8>,
+    ) -> Result<Vec<u8>>;
+    /// Verify a revelation proof
+    fn verify_proof(&self, proof: Vec<u8>) -> Result<()>;
+}
+
+impl<const BLOCK_DB_DEPTH: usize, const L: usize> DynParameters for Parameters<BLOCK_DB_DEPTH, L>
+where
+    [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+{
+    fn l(&self) -> usize {
+        L
+    }
+
+    fn num_public_inputs(&self) -> usize {
+        Self::num_public_inputs()
+    }
+
+    fn describe(&self) -> ParametersInfo {
+        self.describe()
+    }
+
+    fn generate_proof(
+        &self,
+        mapping_keys: Vec<Vec<u8>>,
+        query_min_block: usize,
+        query_max_block: usize,
+        query2_block_proof: Vec<u8>,
+        block_db_proof: Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        let input = RevelationRecursiveInput::<L>::new(
+            mapping_keys,
+            query_min_block,
+            query_max_block,
+            query2_block_proof,
+            block_db_proof,
+        )?;
+        self.generate_proof(input)
+    }
+
+    fn verify_proof(&self, proof: Vec<u8>) -> Result<()> {
+        self.verify_proof(proof)
+    }
+}
+
+/// Depths of the block db for which revelation parameters can be built with
+/// `build_dyn_parameters`, so that the depth can be chosen at runtime, e.g. to match the depth
+/// of the on-chain tree
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockDbDepth {
+    /// Block db of depth 16
+    D16,
+    /// Block db of depth 24
+    D24,
+    /// Block db of depth 32
+    D32,
+}
+
+impl BlockDbDepth {
+    /// Depth of the block db
+    pub const fn depth(self) -> usize {
+        match self {
+            Self::D16 => 16,
+            Self::D24 => 24,
+            Self::D32 => 32,
+        }
+    }
+}
+
+impl TryFrom<usize> for BlockDbDepth {
+    type Error = anyhow::Error;
+
+    fn try_from(depth: usize) -> Result<Self> {
+        Ok(match depth {
+            16 => Self::D16,
+            24 => Self::D24,
+            32 => Self::D32,
+            _ => bail!(
+                "unsupported block db depth {}, supported ones are 16, 24 and 32",
+                depth
+            ),
+        })
+    }
+}
+
+/// Build the revelation parameters for `L` and for the block db `depth` chosen at runtime;
+/// the other arguments are the ones of `Parameters::build`
+pub fn build_dyn_parameters<const L: usize>(
+    depth: BlockDbDepth,
+    query2_block_set: &RecursiveCircuits<F, C, D>,
+    block_db_circuit_set: &RecursiveCircuits<F, C, D>,
+    block_db_verifier_data: &VerifierOnlyCircuitData<C, D>,
+) -> Result<Box<dyn DynParameters>>
+where
+    [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+{
+    fn build<const BLOCK_DB_DEPTH: usize, const L: usize>(
+        query2_block_set: &RecursiveCircuits<F, C, D>,
+        block_db_circuit_set: &RecursiveCircuits<F, C, D>,
+        block_db_verifier_data: &VerifierOnlyCircuitData<C, D>,
+    ) -> Result<Box<dyn DynParameters>>
+    where
+        [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+    {
+        Ok(Box::new(Parameters::<BLOCK_DB_DEPTH, L>::build(
+            query2_block_set,
+            block_db_circuit_set,
+            block_db_verifier_data,
+        )?))
+    }
+
+    let build_for_depth = match depth {
+        BlockDbDepth::D16 => build::<16, L>,
+        BlockDbDepth::D24 => build::<24, L>,
+        BlockDbDepth::D32 => build::<32, L>,
+    };
+    build_for_depth(
+        query2_block_set,
+        block_db_circuit_set,
+        block_db_verifier_data,
+    )
+}
+
+type Builder<const BLOCK_DB_DEPTH: usize> =
+    fn(&ParametersBuilder<BLOCK_DB_DEPTH>) -> Result<Arc<dyn DynParameters>>;
+
+fn build_params<const BLOCK_DB_DEPTH: usize, const L: usize>(
+    builder: &ParametersBuilder<BLOCK_DB_DEPTH>,
+) -> Result<Arc<dyn DynParameters>>
+where
+    [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+{
+    Ok(Arc::new(builder.build_for::<L>()?))
+}
+
+/// Registry of revelation `Parameters` for the `L` registered with `register`, which are built
+/// on first use and then cached. The `params_registry!` macro builds a registry for a list of `L`.
+pub struct ParamsRegistry<const BLOCK_DB_DEPTH: usize> {
+    params_builder: ParametersBuilder<BLOCK_DB_DEPTH>,
+    builders: BTreeMap<usize, Builder<BLOCK_DB_DEPTH>>,
+    built: Mutex<BTreeMap<usize, Arc<dyn DynParameters>>>,
+}
+
+impl<const BLOCK_DB_DEPTH: usize> ParamsRegistry<BLOCK_DB_DEPTH>
+where
+    [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+{
+    /// Arguments are the ones of `Parameters::build`, shared by the parameters for all `L`
+    pub fn new(
+        query2_block_set: &RecursiveCircuits<F, C, D>,
+        block_db_circuit_set: &RecursiveCircuits<F, C, D>,
+        block_db_verifier_data: &VerifierOnlyCircuitData<C, D>,
+    ) -> Self {
+        Self {
+            params_builder: ParametersBuilder::new(
+                query2_block_set,
+                block_db_circuit_set,
+                block_db_verifier_data,
+            ),
+            builders: BTreeMap::new(),
+            built: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Register the parameters for `L`, without building them
+    pub fn register<const L: usize>(&mut self) -> &mut Self {
+        self.builders.insert(L, build_params::<BLOCK_DB_DEPTH, L>);
+        self
+    }
+
+    /// Values of `L` registered in the registry, in increasing order
+    pub fn registered(&self) -> Vec<usize> {
+        self.builders.keys().copied().collect()
+    }
+
+    /// Return the parameters for `l`, building them if this is the first time they are
+    /// requested; an error is returned if `l` was not registered or if the build fails
+    pub fn get(&self, l: usize) -> Result<Arc<dyn DynParameters>> {
+        let builder = self.builders.get(&l).ok_or_else(|| {
+            anyhow!(
+                "no revelation parameters registered for L = {}, registered ones are {:?}",
+                l,
+                self.registered(),
+            )
+        })?;
+        // the lock is held while building, so that concurrent requests build the parameters once
+        let mut built = self.built.lock().unwrap();
+        if let Some(params) = built.get(&l) {
+            return Ok(params.clone());
+        }
+        let params = builder(&self.params_builder)?;
+        built.insert(l, params.clone());
+        Ok(params)
+    }
+
+    /// Return true if the parameters for `l` have already been built
+    pub fn is_built(&self, l: usize) -> bool {
+        self.built.lock().unwrap().contains_key(&l)
+    }
+}
+
+/// Build a `ParamsRegistry` for the block db depth and the list of `L` provided, e.g.
+/// `params_registry!(query2_block_set, block_db_circuit_set, block_db_verifier_data; DEPTH; 2, 5)`,
+/// where the first arguments are the ones of `Parameters::build`
+#[macro_export]
+macro_rules! params_registry {
+    (
+        $query2_block_set:expr,
+        $block_db_circuit_set:expr,
+        $block_db_verifier_data:expr;
+        $depth:expr;
+        $($l:expr),+ $(,)?
+    ) => {{
+        let mut registry = $crate::query2::revelation::ParamsRegistry::<{ $depth }>::new(
+            $query2_block_set,
+            $block_db_circuit_set,
+            $block_db_verifier_data,
+        );
+        $(
+            registry.register::<{ $l }>();
+        )+
+        registry
+    }};
+}
@@ -0,0 +1,237 @@
+//! Utilities to generate inputs and to write precise assertions on the revelation proofs in tests
+use std::{array, iter::once};
+
+use anyhow::Result;
+use ethers::types::Address;
+use plonky2::{
+    field::types::Field,
+    hash::{hash_types::HashOut, poseidon::PoseidonHash},
+    plonk::config::Hasher,
+};
+use rand::Rng;
+use recursion_framework::framework_testing::TestingRecursiveCircuits;
+
+use crate::{
+    api::{serialize_proof, ProofWithVK, C, D, F},
+    block::{empty_merkle_root, PublicInputs as BlockDbPublicInputs},
+    eth::left_pad32,
+    group_hashing,
+    query2::block::BlockPublicInputs,
+    types::{MAPPING_KEY_LEN, PACKED_MAPPING_KEY_LEN},
+    utils::{Packer, ToFields},
+};
+
+use super::{Parameters, RevelationRecursiveInput, BLOCK_DB_NUM_IO, QUERY2_BLOCK_NUM_IO};
+
+/// Return the packed keys assigned to the revelation circuit by `RevelationRecursiveInput::new`
+/// for the unsorted `keys`, i.e. the keys packed in u32 limbs, sorted in the numeric order of
+/// the left-padded big-endian keys, without duplicates and padded with empty keys up to `L`. The NFT IDs revealed
+/// by the proof are the last limbs of these keys, in the same order. This is computed
+/// independently of the packing and sorting code employed to build the witness.
+pub fn expected_sorted_packed_keys<const L: usize>(
+    keys: &[Vec<u8>],
+) -> [[u32; PACKED_MAPPING_KEY_LEN]; L] {
+    // sort the left-padded keys as byte strings, i.e. in their numeric order
+    let mut padded_keys = keys
+        .iter()
+        .map(|key| {
+            let mut padded = [0u8; MAPPING_KEY_LEN];
+            padded[MAPPING_KEY_LEN - key.len()..].copy_from_slice(key);
+            padded
+        })
+        .collect::<Vec<_>>();
+    padded_keys.sort();
+    padded_keys.dedup();
+    // each limb is made of 4 bytes of the left-padded key, read in little-endian order
+    let mut packed_keys = padded_keys
+        .iter()
+        .map(|padded| array::from_fn(|i| u32::from_le_bytes(array::from_fn(|j| padded[4 * i + j]))))
+        .collect::<Vec<[u32; PACKED_MAPPING_KEY_LEN]>>();
+    packed_keys.resize(L, [0; PACKED_MAPPING_KEY_LEN]);
+    packed_keys.try_into().unwrap()
+}
+
+/// Values exposed by a fake query2/block proof
+#[derive(Clone, Debug)]
+pub struct Query2Values {
+    /// Last block aggregated by the proof
+    pub block_number: F,
+    /// Number of blocks aggregated by the proof
+    pub range: F,
+    /// Root of the block db the proof is computed over
+    pub root: HashOut<F>,
+    /// Address of the contract being queried
+    pub smc_address: Address,
+    /// Address of the user being queried
+    pub user_address: Address,
+    /// Slot of the mapping being queried
+    pub mapping_slot: F,
+    /// Slot of the length of the mapping
+    pub length_slot: F,
+}
+
+impl Query2Values {
+    /// Public inputs of a fake query2/block proof exposing these values, and the digest of the
+    /// `mapping_keys` matching the query
+    pub fn public_inputs(
+        &self,
+        mapping_keys: &[[u8; MAPPING_KEY_LEN]],
+    ) -> [F; QUERY2_BLOCK_NUM_IO] {
+        let digest = group_hashing::map_and_add(
+            &mapping_keys
+                .iter()
+                .map(|key| key.pack().to_fields())
+                .collect::<Vec<_>>(),
+        );
+        BlockPublicInputs::from_parts(
+            self.block_number,
+            self.range,
+            self.root,
+            &self
+                .smc_address
+                .as_fixed_bytes()
+                .pack()
+                .to_fields()
+                .try_into()
+                .unwrap(),
+            &left_pad32(self.user_address.as_fixed_bytes())
+                .pack()
+                .to_fields()
+                .try_into()
+                .unwrap(),
+            self.mapping_slot,
+            self.length_slot,
+            digest.to_weierstrass(),
+        )
+    }
+}
+
+/// Public inputs of a fake block db proof over the blocks from `first_block_number` to
+/// `last_block_number`, inserted in an empty block db of depth `DEPTH` to get the tree with
+/// root `last_root`; `last_block_hash` is the hash of the last block
+pub fn block_db_public_inputs<const DEPTH: usize>(
+    last_root: &HashOut<F>,
+    first_block_number: F,
+    last_block_number: F,
+    last_block_hash: &[u8; 32],
+) -> [F; BLOCK_DB_NUM_IO] {
+    block_db_range_public_inputs(
+        &empty_merkle_root::<F, D, DEPTH>(),
+        last_root,
+        first_block_number,
+        last_block_number,
+        last_block_hash,
+        F::ONE,
+    )
+}
+
+/// Public inputs of a fake non-dummy block db proof over the blocks from `first_block_number` to
+/// `last_block_number`, which turned the block db with root `init_root` into the one with root
+/// `last_root`; `last_block_hash` is the hash of the last block. Such proofs can be merged with
+/// the block db proofs over the adjacent ranges of blocks
+pub fn merged_block_db_public_inputs(
+    init_root: &HashOut<F>,
+    last_root: &HashOut<F>,
+    first_block_number: u32,
+    last_block_number: u32,
+    last_block_hash: &[u8; 32],
+) -> [F; BLOCK_DB_NUM_IO] {
+    block_db_range_public_inputs(
+        init_root,
+        last_root,
+        F::from_canonical_u32(first_block_number),
+        F::from_canonical_u32(last_block_number),
+        last_block_hash,
+        F::ZERO,
+    )
+}
+
+fn block_db_range_public_inputs(
+    init_root: &HashOut<F>,
+    last_root: &HashOut<F>,
+    first_block_number: F,
+    last_block_number: F,
+    last_block_hash: &[u8; 32],
+    is_dummy: F,
+) -> [F; BLOCK_DB_NUM_IO] {
+    BlockDbPublicInputs::from_parts(
+        &init_root.elements,
+        &last_root.elements,
+        first_block_number,
+        last_block_number,
+        &BlockDbPublicInputs::from_block_hash_bytes(last_block_hash),
+    )
+    .into_iter()
+    .chain(once(is_dummy))
+    .collect::<Vec<_>>()
+    .try_into()
+    .unwrap()
+}
+
+/// Build the revelation parameters for fake query2/block and block db circuit sets, and
+/// generate with them random inputs for which a revelation proof can be generated: up to `L`
+/// random mapping keys, a fake block db proof over a random range of blocks, and a fake
+/// query2/block proof over a random sub-range of the block db, queried with a random range of
+/// the block db including the blocks aggregated in the query2/block proof. All the random values are drawn
+/// from `rng`, so that failures can be reproduced from a seeded `rng`.
+pub fn random_query2_inputs<const BLOCK_DB_DEPTH: usize, const L: usize>(
+    rng: &mut impl Rng,
+) -> Result<(Parameters<BLOCK_DB_DEPTH, L>, RevelationRecursiveInput<L>)>
+where
+    [(); <PoseidonHash as Hasher<F>>::HASH_SIZE]:,
+{
+    let query2_circuits = TestingRecursiveCircuits::<F, C, D, QUERY2_BLOCK_NUM_IO>::default();
+    let block_db_circuits = TestingRecursiveCircuits::<F, C, D, BLOCK_DB_NUM_IO>::default();
+    let params = Parameters::build(
+        query2_circuits.get_recursive_circuit_set(),
+        block_db_circuits.get_recursive_circuit_set(),
+        block_db_circuits.verifier_data_for_input_proofs::<1>()[0],
+    )?;
+
+    // block numbers are kept small enough to be compared over 32 bits by the circuit
+    let init_block_number: u32 = rng.gen_range(0..1 << 30);
+    let last_block_number = init_block_number + rng.gen_range(1..1 << 20);
+    let last_root = HashOut {
+        elements: array::from_fn(|_| F::from_noncanonical_u64(rng.gen())),
+    };
+    let block_db_inputs = block_db_public_inputs::<BLOCK_DB_DEPTH>(
+        &last_root,
+        F::from_canonical_u32(init_block_number),
+        F::from_canonical_u32(last_block_number),
+        &rng.gen(),
+    );
+    let block_db_proof = block_db_circuits.generate_input_proofs([block_db_inputs])?;
+
+    // the query2/block proof aggregates the blocks in (block_number - range, block_number]
+    let block_number = rng.gen_range(init_block_number..=last_block_number);
+    let range = rng.gen_range(1..=block_number - init_block_number + 1);
+    let query_min_block = rng.gen_range(init_block_number..=block_number + 1 - range);
+    let query_max_block = rng.gen_range(block_number..=last_block_number);
+
+    let mapping_keys = (0..rng.gen_range(1..=L))
+        .map(|_| rng.gen::<[u8; MAPPING_KEY_LEN]>())
+        .collect::<Vec<_>>();
+    let query2_values = Query2Values {
+        block_number: F::from_canonical_u32(block_number),
+        range: F::from_canonical_u32(range),
+        root: last_root,
+        smc_address: Address::from(rng.gen::<[u8; 20]>()),
+        user_address: Address::from(rng.gen::<[u8; 20]>()),
+        mapping_slot: F::from_noncanonical_u64(rng.gen()),
+        length_slot: F::from_noncanonical_u64(rng.gen()),
+    };
+    let query2_proof = ProofWithVK::from_parts(
+        query2_circuits.generate_input_proofs([query2_values.public_inputs(&mapping_keys)])?[0]
+            .clone(),
+        query2_circuits.verifier_data_for_input_proofs::<1>()[0].clone(),
+    );
+
+    let inputs = RevelationRecursiveInput::new(
+        mapping_keys.iter().map(|key| key.to_vec()).collect(),
+        query_min_block as usize,
+        query_max_block as usize,
+        query2_proof.serialize()?,
+        serialize_proof(&block_db_proof[0])?,
+    )?;
+    Ok((params, inputs))
+}
@@ -104,7 +104,7 @@ impl Parameters {
     }
 
     pub fn generate_proof(&self, input: CircuitInput) -> Result<Vec<u8>> {
-        match input {
+        let proof = match input {
             CircuitInput::Leaf(leaf) => {
                 let proof = self.set.generate_proof(&self.leaf_circuit, [], [], leaf)?;
                 ProofWithVK {
@@ -138,8 +138,8 @@ impl Parameters {
                     vk: self.full_node_circuit.get_verifier_data().clone(),
                 }
             }
-        }
-        .serialize()
+        };
+        Ok(proof.serialize()?)
     }
 
     pub(crate) fn get_storage_circuit_set(&self) -> &RecursiveCircuits<F, C, D> {
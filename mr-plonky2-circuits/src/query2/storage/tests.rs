@@ -1,4 +1,4 @@
-use ethers::types::Address;
+use ethers::types::{Address, U256};
 use plonky2::field::types::Sample;
 use std::{
     array::{self},
@@ -29,7 +29,7 @@ use rand::{rngs::StdRng, RngCore, SeedableRng};
 use crate::{
     api::ProofWithVK,
     eth::left_pad32,
-    group_hashing::map_to_curve_point,
+    group_hashing::{digest_of_u256_ids, map_to_curve_point},
     storage::lpn::{intermediate_node_hash, leaf_hash_for_mapping},
     types::{MAPPING_KEY_LEN, PACKED_MAPPING_KEY_LEN, PACKED_VALUE_LEN},
     utils::convert_u8_to_u32_slice,
@@ -160,6 +160,28 @@ fn test_leaf_nonzero_zero() {
     test_leaf(b"1235", b"00");
 }
 
+#[test]
+fn test_digest_of_u256_ids() {
+    let ids = [U256::from(0xdeadbeefu32), U256::MAX];
+    let leaves = ids.map(|id| {
+        let mut k = [0u8; MAPPING_KEY_LEN];
+        id.to_big_endian(&mut k);
+        run_leaf_proof(&k, b"0badf00d")
+    });
+
+    // the digest of a single ID matches the one committed by a leaf proof
+    assert_eq!(digest_of_u256_ids(&ids[..1]), leaves[0].io().digest());
+
+    // the digest of several IDs matches the one committed by an inner node proof
+    let inner = FullInnerNodeCircuitValidator {
+        validated: FullInnerNodeCircuit {},
+        children: &[leaves[0].io(), leaves[1].io()],
+    };
+    let inner_proof = run_circuit::<F, D, C, _>(inner);
+    let inner_ios = PublicInputs::<F>::from(inner_proof.public_inputs.as_slice());
+    assert_eq!(digest_of_u256_ids(&ids), inner_ios.digest());
+}
+
 /// Builds & proves the following tree
 ///
 /// Top-level - PartialInnerCircuit
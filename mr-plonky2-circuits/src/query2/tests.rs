@@ -34,7 +34,10 @@ use super::{
         partial_node::{PartialNodeCircuit, PartialNodeWires},
         BlockPublicInputs as BlockQueryPublicInputs,
     },
-    revelation::circuit::{RevelationCircuit, RevelationWires},
+    revelation::circuit::{
+        BlockRangeVisibility, CommitmentHash, QueryCommitment, RevelationCircuit, RevelationWires,
+        SaltPolicy,
+    },
 };
 
 const D: usize = 2;
@@ -111,7 +114,15 @@ impl<const L: usize, const MAX_DEPTH: usize> UserCircuit<F, D>
         let root_proof_io = c.add_virtual_targets(BlockQueryPublicInputs::<Target>::total_len());
         let root_proof_pi = BlockQueryPublicInputs::<Target>::from(root_proof_io.as_slice());
 
-        let wires = RevelationCircuit::<L>::build::<MAX_DEPTH>(c, db_proof_pi, root_proof_pi);
+        let wires = RevelationCircuit::<L>::build::<MAX_DEPTH>(
+            c,
+            db_proof_pi,
+            root_proof_pi,
+            CommitmentHash::Poseidon,
+            SaltPolicy::Unsalted,
+            QueryCommitment::Disabled,
+            BlockRangeVisibility::Public,
+        );
         (wires, db_proof_io, root_proof_io)
     }
 
@@ -231,16 +242,31 @@ fn test_query2_mini_tree() {
         num_entries,
         query_min_block_number: query_min_block_number.to_canonical_u64() as usize,
         query_max_block_number: query_max_block_number.to_canonical_u64() as usize,
+        page: None,
+        salt: None,
+        range_blinding: None,
     };
 
+    // the result commitment is the hash of the number of entries and of the included keys
+    let expected_commitment = hash_n_to_hash_no_pad::<F, PoseidonPermutation<_>>(
+        &std::iter::once(F::from_canonical_u8(num_entries))
+            .chain(
+                nft_ids[..num_entries as usize]
+                    .iter()
+                    .flat_map(|v| convert_u8_to_u32_slice(v))
+                    .map(F::from_canonical_u32),
+            )
+            .chain(std::iter::repeat(F::ZERO).take((L - num_entries as usize) * 8))
+            .collect_vec(),
+    );
+    assert_eq!(revelation_circuit.result_commitment(), expected_commitment);
+
     let final_proof = run_circuit::<F, D, C, _>(RevelationCircuitValidator::<L, MAX_DEPTH> {
         validated: revelation_circuit,
         db_proof,
         root_proof: root_proof.clone(),
     });
-    let pi = RevelationPublicInputs::<_, L> {
-        inputs: final_proof.public_inputs.as_slice(),
-    };
+    let pi = RevelationPublicInputs::<_, L>::from(final_proof.public_inputs.as_slice());
 
     let padded_address = &left_leaf_pi.user_address();
     let address = &padded_address[padded_address.len() - 5..];
@@ -280,5 +306,6 @@ fn test_query2_mini_tree() {
     );
     assert_eq!(pi.mapping_slot(), root_proof.mapping_slot());
     assert_eq!(pi.mapping_slot_length(), root_proof.mapping_slot_length());
+    assert_eq!(pi.result_commitment(), expected_commitment);
     //
 }
@@ -199,7 +199,7 @@ impl PublicParameters {
         inputs.circuit_inputs.assign::<F, D>(&mut pw, &self.wires)?;
         pw.set_proof_with_pis_target(&self.storage_circuit_proof, &inputs.storage_proof);
         let proof = self.data.prove(pw)?;
-        serialize_proof(&proof)
+        Ok(serialize_proof(&proof)?)
     }
 
     /// Get the `CircuitData` of the digest equal circuit
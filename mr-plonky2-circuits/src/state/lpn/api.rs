@@ -67,7 +67,7 @@ impl Parameters {
                 (proof, self.node.get_verifier_data().clone()).into()
             }
         };
-        proof_with_vk.serialize()
+        Ok(proof_with_vk.serialize()?)
     }
 
     /// Verify proof generated by `generate_proof` method
@@ -217,7 +217,7 @@ impl Parameters {
             .unwrap();
         pw.set_proof_with_pis_target(&self.mpt_proof, mpt_proof);
         let proof = self.data.prove(pw)?;
-        serialize_proof(&proof)
+        Ok(serialize_proof(&proof)?)
     }
 
     /// Get the `CircuitData` of the digest equal circuit
@@ -227,7 +227,7 @@ where
         inputs.assign::<F, D>(&mut pw, &self.wires)?;
         let proof = self.data.prove(pw)?;
         // TODO: move serialization to common place
-        serialize_proof(&proof)
+        Ok(serialize_proof(&proof)?)
     }
 
     pub(crate) fn circuit_data(&self) -> &CircuitData<F, C, D> {
@@ -189,7 +189,7 @@ impl Parameters {
             .set_target(&mut pw, mapping_circuit_set, proof, vd)?;
         pw.set_proof_with_pis_target(&self.length_proof, length_proof);
         let proof = self.data.prove(pw)?;
-        serialize_proof(&proof)
+        Ok(serialize_proof(&proof)?)
     }
     /// Get the `CircuitData` associated to the length matching circuit
     pub(crate) fn circuit_data(&self) -> &CircuitData<F, C, D> {
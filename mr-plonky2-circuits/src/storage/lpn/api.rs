@@ -76,14 +76,13 @@ impl PublicParameters {
     /// Generate a proof for a leaf or node in the storage database and returns its
     /// serialized form.
     pub fn generate_proof(&self, inputs: Input) -> Result<Vec<u8>> {
-        match inputs {
+        let proof = match inputs {
             Input::Leaf(leaf) => {
                 let proof = self.set.generate_proof(&self.leaf_circuit, [], [], leaf)?;
                 ProofWithVK {
                     proof,
                     vk: self.leaf_circuit.get_verifier_data().clone(),
                 }
-                .serialize()
             }
             Input::Node(node) => {
                 let left = ProofWithVK::deserialize(&node.left)?;
@@ -98,9 +97,9 @@ impl PublicParameters {
                     proof,
                     vk: self.node_circuit.get_verifier_data().clone(),
                 }
-                .serialize()
             }
-        }
+        };
+        Ok(proof.serialize()?)
     }
     /// Get the set of circuits related to the storage database in LPN
     pub(crate) fn get_lpn_circuit_set(&self) -> &RecursiveCircuits<F, C, D> {
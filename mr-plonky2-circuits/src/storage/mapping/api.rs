@@ -96,7 +96,7 @@ pub fn generate_proof(
     circuit_params: &PublicParameters,
     circuit_type: CircuitInput,
 ) -> Result<Vec<u8>> {
-    circuit_params.generate_proof(circuit_type)?.serialize()
+    Ok(circuit_params.generate_proof(circuit_type)?.serialize()?)
 }
 #[derive(Serialize, Deserialize)]
 /// This data structure allows to specify the inputs for a circuit that needs to recursively verify
@@ -110,10 +110,11 @@ struct ProofInputSerialized<T> {
 impl<T> ProofInputSerialized<T> {
     /// Deserialize child proofs and return the set of deserialized 'MTPProof`s
     fn get_child_proofs(&self) -> Result<Vec<ProofWithVK>> {
-        self.serialized_child_proofs
+        Ok(self
+            .serialized_child_proofs
             .iter()
             .map(|proof| ProofWithVK::deserialize(proof))
-            .collect::<Result<Vec<_>, _>>()
+            .collect::<Result<Vec<_>, _>>()?)
     }
 }
 
@@ -0,0 +1,75 @@
+//! Decode the public inputs of a real revelation proof with the decoders of
+//! `mrp2_public_inputs`, as light clients do, and check that they read the same values as the
+//! proving stack
+use anyhow::Result;
+use ethers::types::U256;
+use mr_plonky2_circuits::{
+    api::{deserialize_proof, serialize_public_inputs},
+    query2::revelation::test_utils::random_query2_inputs,
+};
+use mrp2_public_inputs::{decode_public_inputs, revelation::RevelationPublicInputs};
+use plonky2::{
+    field::{goldilocks_field::GoldilocksField, types::PrimeField64},
+    plonk::config::PoseidonGoldilocksConfig,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+type F = GoldilocksField;
+type C = PoseidonGoldilocksConfig;
+const D: usize = 2;
+
+const BLOCK_DB_DEPTH: usize = 2;
+const L: usize = 3;
+
+#[test]
+fn test_decode_revelation_proof_public_inputs() -> Result<()> {
+    let mut rng = StdRng::seed_from_u64(0x300);
+    let (params, inputs) = random_query2_inputs::<BLOCK_DB_DEPTH, L>(&mut rng)?;
+    let proof = params.generate_proof(inputs)?;
+    params.verify_proof(proof.clone())?;
+    let expected = params.inspect(&proof)?;
+
+    // the light client only gets the serialized public inputs of the proof
+    let bytes = serialize_public_inputs(&deserialize_proof::<F, C, D>(&proof)?);
+    let inputs = decode_public_inputs::<F>(&bytes)?;
+    let decoded = RevelationPublicInputs::<F, L>::new(&inputs)?;
+
+    assert_eq!(decoded.min_block()?, expected.min_block()?);
+    assert_eq!(decoded.max_block()?, expected.max_block()?);
+    assert_eq!(
+        decoded.total_matching(),
+        expected.total_matching().to_canonical_u64()
+    );
+    assert_eq!(
+        decoded.page_offset(),
+        expected.page_offset().to_canonical_u64()
+    );
+    assert_eq!(
+        decoded.page_limit(),
+        expected.page_limit().to_canonical_u64()
+    );
+    assert_eq!(
+        decoded.commitment_hash(),
+        expected.commitment_hash()?.to_field().to_canonical_u64()
+    );
+    assert_eq!(decoded.salt(), expected.salt());
+    assert_eq!(decoded.block_db_root(), expected.block_db_root().elements);
+    assert_eq!(
+        decoded.keys_commitment(),
+        expected.keys_commitment().elements
+    );
+    assert_eq!(
+        decoded.client_address(),
+        expected.client_address().to_fixed_bytes()
+    );
+    assert_eq!(
+        decoded
+            .revealed_nft_ids()
+            .into_iter()
+            .map(U256::from)
+            .collect::<Vec<_>>(),
+        expected.revealed_nft_ids()
+    );
+    assert!(decoded.total_matching() > 0);
+    Ok(())
+}
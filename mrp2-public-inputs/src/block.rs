@@ -0,0 +1,121 @@
+//! Layout and decoder of the public inputs exposed by query2/block proofs
+use core::ops::Range;
+
+use plonky2_field::types::PrimeField64;
+
+use crate::{
+    check_length, region_offset, unpack_limbs, DecodeError, CURVE_POINT_LEN, NUM_HASH_OUT_ELTS,
+    PACKED_ADDRESS_LEN, PACKED_VALUE_LEN,
+};
+
+/// Number of public inputs of each region, in the order they are exposed by query2/block
+/// proofs: block number, range, root, smart contract address, user address, mapping slot,
+/// mapping slot length and digest
+pub const SIZES: [usize; 8] = [
+    1,
+    1,
+    NUM_HASH_OUT_ELTS,
+    PACKED_ADDRESS_LEN,
+    PACKED_VALUE_LEN,
+    1,
+    1,
+    CURVE_POINT_LEN,
+];
+
+/// Number of public inputs exposed by query2/block proofs
+pub const NUM_PUBLIC_INPUTS: usize = region_offset(&SIZES, SIZES.len());
+
+/// Regions of the public inputs exposed by query2/block proofs, in the order they are exposed;
+/// this is the layout employed by the circuits too
+#[derive(Clone, Copy, Debug)]
+pub enum Region {
+    /// Number of the latest block aggregated
+    BlockNumber,
+    /// Number of blocks aggregated
+    Range,
+    /// Root of the subtree of the block db covered by the proof
+    Root,
+    /// Address of the contract being queried, packed in u32 limbs
+    SmartContractAddress,
+    /// Address of the user being queried, left padded to 32 bytes and packed in u32 limbs
+    UserAddress,
+    /// Slot of the mapping being queried
+    MappingSlot,
+    /// Slot of the length of the mapping being queried
+    MappingSlotLength,
+    /// Digest of the keys matching the query, as a curve point
+    Digest,
+}
+
+impl Region {
+    /// Number of public inputs of the region
+    pub const fn size(self) -> usize {
+        SIZES[self as usize]
+    }
+
+    /// Positions of the public inputs of the region
+    pub const fn range(self) -> Range<usize> {
+        let offset = region_offset(&SIZES, self as usize);
+        offset..offset + self.size()
+    }
+}
+
+/// Decoder of the public inputs exposed by a query2/block proof
+#[derive(Clone, Copy, Debug)]
+pub struct BlockPublicInputs<'a, F> {
+    inputs: &'a [F],
+}
+
+impl<'a, F: PrimeField64> BlockPublicInputs<'a, F> {
+    /// Decode the public inputs of a query2/block proof, which must be exactly
+    /// `NUM_PUBLIC_INPUTS`
+    pub fn new(inputs: &'a [F]) -> Result<Self, DecodeError> {
+        check_length(inputs, NUM_PUBLIC_INPUTS)?;
+        Ok(Self { inputs })
+    }
+
+    fn region(&self, region: Region) -> &'a [F] {
+        &self.inputs[region.range()]
+    }
+
+    /// Number of the latest block aggregated by the proof
+    pub fn block_number(&self) -> u64 {
+        self.region(Region::BlockNumber)[0].to_canonical_u64()
+    }
+
+    /// Number of blocks aggregated by the proof
+    pub fn range(&self) -> u64 {
+        self.region(Region::Range)[0].to_canonical_u64()
+    }
+
+    /// Root of the subtree of the block db covered by the proof
+    pub fn root(&self) -> &'a [F] {
+        self.region(Region::Root)
+    }
+
+    /// Address of the contract being queried
+    pub fn smart_contract_address(&self) -> [u8; 20] {
+        unpack_limbs(self.region(Region::SmartContractAddress))
+    }
+
+    /// Address of the user being queried, left padded to 32 bytes
+    pub fn user_address(&self) -> [u8; 32] {
+        unpack_limbs(self.region(Region::UserAddress))
+    }
+
+    /// Slot of the mapping being queried
+    pub fn mapping_slot(&self) -> u64 {
+        self.region(Region::MappingSlot)[0].to_canonical_u64()
+    }
+
+    /// Slot of the length of the mapping being queried
+    pub fn mapping_slot_length(&self) -> u64 {
+        self.region(Region::MappingSlotLength)[0].to_canonical_u64()
+    }
+
+    /// Digest of the keys matching the query, as the coordinates of the curve point over the
+    /// quintic extension followed by the flag for the point at infinity
+    pub fn digest(&self) -> &'a [F] {
+        self.region(Region::Digest)
+    }
+}
@@ -0,0 +1,152 @@
+//! Decoders of the public inputs exposed by query2 proofs, i.e., by revelation proofs and by
+//! query2/block proofs. They depend only on the field of the proofs, and not on the proving
+//! stack, so they compile under `no_std` with `alloc`, e.g. for light clients which only need
+//! to read the values proven by a proof verified elsewhere.
+
+#![no_std]
+#![warn(missing_docs)]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use plonky2_field::types::PrimeField64;
+
+pub mod block;
+pub mod revelation;
+
+/// Number of u32 limbs of a packed address
+pub const PACKED_ADDRESS_LEN: usize = 5;
+/// Number of u32 limbs of a packed 32-byte value
+pub const PACKED_VALUE_LEN: usize = 8;
+/// Number of u32 limbs of a packed Keccak hash
+pub const PACKED_HASH_LEN: usize = 8;
+/// Number of field elements of a Poseidon hash
+pub const NUM_HASH_OUT_ELTS: usize = 4;
+/// Number of field elements of a curve point, i.e., its coordinates over the quintic extension
+/// and the flag for the point at infinity
+pub const CURVE_POINT_LEN: usize = 11;
+
+/// Number of bytes employed to encode each public input in serialized public inputs
+pub const PUBLIC_INPUT_BYTES: usize = 8;
+
+/// Errors raised when decoding public inputs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The number of public inputs is not the one of the proofs being decoded
+    Length {
+        /// Number of public inputs provided
+        got: usize,
+        /// Number of public inputs exposed by the proofs being decoded
+        expected: usize,
+    },
+    /// The length of the serialized public inputs is not a multiple of `PUBLIC_INPUT_BYTES`
+    Truncated {
+        /// Length of the serialized public inputs
+        len: usize,
+    },
+    /// A serialized public input is not a canonical field element
+    NonCanonical {
+        /// Value of the serialized public input
+        value: u64,
+    },
+    /// The block range of the query is not exposed, as the proof exposes only a commitment to it
+    HiddenBlockRange,
+    /// The block range of the query is not exposed, as the proof exposes only a commitment to
+    /// the parameters of the query
+    CommittedQuery,
+    /// An exposed block number doesn't fit in a `u32`
+    BlockNumberOverflow {
+        /// Value of the exposed block number
+        value: u64,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Length { got, expected } => write!(
+                f,
+                "proof exposes {} public inputs, expected {}",
+                got, expected
+            ),
+            DecodeError::Truncated { len } => write!(
+                f,
+                "serialized public inputs length {} is not a multiple of {}",
+                len, PUBLIC_INPUT_BYTES
+            ),
+            DecodeError::NonCanonical { value } => write!(
+                f,
+                "serialized public input {} is not a canonical field element",
+                value
+            ),
+            DecodeError::HiddenBlockRange => write!(
+                f,
+                "the block range of the query is hidden behind a commitment"
+            ),
+            DecodeError::CommittedQuery => write!(
+                f,
+                "the block range of the query is not exposed, as the proof exposes only a commitment to the query parameters"
+            ),
+            DecodeError::BlockNumberOverflow { value } => {
+                write!(f, "block number {} doesn't fit in a u32", value)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+/// Decode public inputs serialized as the little-endian bytes of the canonical `u64`
+/// representation of each public input, in the order they are exposed by the circuit, i.e.,
+/// the layout expected by the Solidity verifier
+pub fn decode_public_inputs<F: PrimeField64>(bytes: &[u8]) -> Result<Vec<F>, DecodeError> {
+    if bytes.len() % PUBLIC_INPUT_BYTES != 0 {
+        return Err(DecodeError::Truncated { len: bytes.len() });
+    }
+    bytes
+        .chunks(PUBLIC_INPUT_BYTES)
+        .map(|chunk| {
+            let value = u64::from_le_bytes(chunk.try_into().unwrap());
+            if value >= F::ORDER {
+                return Err(DecodeError::NonCanonical { value });
+            }
+            Ok(F::from_canonical_u64(value))
+        })
+        .collect()
+}
+
+/// Check that `inputs` are exactly `expected` public inputs
+fn check_length<F>(inputs: &[F], expected: usize) -> Result<(), DecodeError> {
+    if inputs.len() != expected {
+        return Err(DecodeError::Length {
+            got: inputs.len(),
+            expected,
+        });
+    }
+    Ok(())
+}
+
+/// Unpack u32 limbs, each one exposed as a field element, into their little-endian bytes
+fn unpack_limbs<F: PrimeField64, const N: usize>(limbs: &[F]) -> [u8; N] {
+    let mut bytes = [0u8; N];
+    bytes.chunks_mut(4).zip(limbs).for_each(|(chunk, limb)| {
+        chunk.copy_from_slice(&(limb.to_canonical_u64() as u32).to_le_bytes())
+    });
+    bytes
+}
+
+/// Offset of the region `index` in a layout made of regions of the given `sizes`
+const fn region_offset(sizes: &[usize], index: usize) -> usize {
+    let mut offset = 0;
+    let mut i = 0;
+    while i < index {
+        offset += sizes[i];
+        i += 1;
+    }
+    offset
+}
@@ -0,0 +1,392 @@
+//! Layout and decoder of the public inputs exposed by revelation proofs
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use plonky2_field::types::PrimeField64;
+
+use crate::{
+    check_length, region_offset, unpack_limbs, DecodeError, NUM_HASH_OUT_ELTS, PACKED_ADDRESS_LEN,
+    PACKED_HASH_LEN,
+};
+
+/// Number of regions of the public inputs exposed by revelation proofs
+pub const NUM_REGIONS: usize = 21;
+
+/// Number of field elements of the result commitment and of the query commitment
+pub const COMMITMENT_LEN: usize = PACKED_HASH_LEN;
+
+/// Regions of the public inputs exposed by revelation proofs, in the order they are exposed;
+/// this is the layout employed by the circuit too. The size of each region is found in
+/// `region_sizes`, and its name in `REGION_NAMES`.
+#[derive(Clone, Copy, Debug)]
+pub enum Region {
+    /// Number of the last block of the query2/block proof answering the query
+    BlockNumber,
+    /// Number of blocks covered by the query2/block proof
+    Range,
+    /// Minimum block number of the query
+    MinBlockNumber,
+    /// Maximum block number of the query
+    MaxBlockNumber,
+    /// Address of the contract being queried
+    SmartContractAddress,
+    /// Address of the user being queried
+    UserAddress,
+    /// Slot of the mapping being queried
+    MappingSlot,
+    /// Slot of the length of the mapping
+    MappingSlotLength,
+    /// NFT IDs exposed by the proof
+    NftIds,
+    /// Hash of the last block of the block db
+    BlockHeader,
+    /// Commitment to the revealed keys
+    ResultCommitment,
+    /// Number of keys matching the query
+    TotalMatching,
+    /// Offset of the window of revealed keys
+    PageOffset,
+    /// Limit of the window of revealed keys
+    PageLimit,
+    /// Identifier of the hash employed for the result commitment
+    CommitmentHash,
+    /// Salt mixed in the result commitment
+    Salt,
+    /// Commitment to the parameters of the query
+    QueryCommitment,
+    /// Commitment to the block range of the query
+    RangeCommitment,
+    /// Root of the block db the proof is anchored to
+    BlockDbRoot,
+    /// Address of the client allowed to consume the proof
+    ClientAddress,
+    /// Commitment to the set of keys matching the query
+    KeysCommitment,
+}
+
+impl Region {
+    /// Positions of the public inputs of the region in the public inputs exposed by revelation
+    /// proofs revealing `l` NFT IDs
+    pub const fn range(self, l: usize) -> Range<usize> {
+        let sizes = region_sizes(l);
+        let offset = region_offset(&sizes, self as usize);
+        offset..offset + sizes[self as usize]
+    }
+}
+
+/// Names of the regions of the public inputs exposed by revelation proofs, in the order they
+/// are exposed
+pub const REGION_NAMES: [&str; NUM_REGIONS] = [
+    "block_number",
+    "range",
+    "min_block_number",
+    "max_block_number",
+    "smart_contract_address",
+    "user_address",
+    "mapping_slot",
+    "mapping_slot_length",
+    "nft_ids",
+    "block_header",
+    "result_commitment",
+    "total_matching",
+    "page_offset",
+    "page_limit",
+    "commitment_hash",
+    "salt",
+    "query_commitment",
+    "range_commitment",
+    "block_db_root",
+    "client_address",
+    "keys_commitment",
+];
+
+/// Number of public inputs of each region, in the order they are exposed, for revelation
+/// proofs revealing `l` NFT IDs
+pub const fn region_sizes(l: usize) -> [usize; NUM_REGIONS] {
+    [
+        // Block number
+        1,
+        // Range
+        1,
+        // Min block number
+        1,
+        // Max block number
+        1,
+        // Smart contract address
+        PACKED_ADDRESS_LEN,
+        // User address
+        PACKED_ADDRESS_LEN,
+        // Mapping Slot
+        1,
+        // Mapping slot length
+        1,
+        // L × NFT ID as u32
+        l,
+        // Block Header
+        PACKED_HASH_LEN,
+        // Commitment to the revealed keys
+        COMMITMENT_LEN,
+        // Total number of keys matching the query
+        1,
+        // Offset of the window of revealed keys
+        1,
+        // Limit of the window of revealed keys
+        1,
+        // Identifier of the hash employed for the result commitment
+        1,
+        // Salt mixed in the result commitment, if exposed
+        1,
+        // Commitment to the parameters of the query, if they are not exposed
+        COMMITMENT_LEN,
+        // Commitment to the block range of the query, if it is hidden
+        NUM_HASH_OUT_ELTS,
+        // Root of the block db the proof is anchored to
+        NUM_HASH_OUT_ELTS,
+        // Address of the client allowed to consume the proof
+        PACKED_ADDRESS_LEN,
+        // Commitment to the set of keys matching the query
+        NUM_HASH_OUT_ELTS,
+    ]
+}
+
+/// Number of public inputs exposed by revelation proofs revealing `l` NFT IDs
+pub const fn total_len(l: usize) -> usize {
+    region_offset(&region_sizes(l), NUM_REGIONS)
+}
+
+/// Number of public inputs of the regions exposing the parameters of the query, i.e. the
+/// regions before the NFT IDs, which are not exposed by the proofs committing to the query
+pub const QUERY_PARAMS_LEN: usize = region_offset(&region_sizes(0), Region::NftIds as usize);
+
+/// Number of public inputs exposed by revelation proofs revealing `l` NFT IDs which commit to
+/// the parameters of the query rather than exposing them
+pub const fn committed_total_len(l: usize) -> usize {
+    total_len(l) - QUERY_PARAMS_LEN
+}
+
+/// Return the name and the range of each region of the public inputs exposed by revelation
+/// proofs revealing `l` NFT IDs which commit to the parameters of the query, i.e. the regions
+/// of `layout` without the ones of the query parameters
+pub fn committed_layout(l: usize) -> Vec<(&'static str, Range<usize>)> {
+    layout(l)
+        .into_iter()
+        .skip(Region::NftIds as usize)
+        .map(|(name, range)| {
+            (
+                name,
+                range.start - QUERY_PARAMS_LEN..range.end - QUERY_PARAMS_LEN,
+            )
+        })
+        .collect()
+}
+
+/// Return the name and the range of each region of the public inputs exposed by revelation
+/// proofs revealing `l` NFT IDs
+pub fn layout(l: usize) -> Vec<(&'static str, Range<usize>)> {
+    let sizes = region_sizes(l);
+    REGION_NAMES
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let offset = region_offset(&sizes, i);
+            (*name, offset..offset + sizes[i])
+        })
+        .collect()
+}
+
+/// Decoder of the public inputs exposed by a revelation proof revealing `L` NFT IDs
+#[derive(Clone, Copy, Debug)]
+pub struct RevelationPublicInputs<'a, F, const L: usize> {
+    inputs: &'a [F],
+    /// Number of public inputs of the query parameters missing from `inputs`, i.e.
+    /// `QUERY_PARAMS_LEN` for the proofs committing to the parameters of the query, and zero
+    /// otherwise
+    offset: usize,
+}
+
+impl<'a, F: PrimeField64, const L: usize> RevelationPublicInputs<'a, F, L> {
+    /// Decode the public inputs of a revelation proof, which must be exactly `total_len(L)`, or
+    /// `committed_total_len(L)` for the proofs committing to the parameters of the query; the
+    /// parameters of the query are decoded as zeros for the latter proofs
+    pub fn new(inputs: &'a [F]) -> Result<Self, DecodeError> {
+        if inputs.len() == committed_total_len(L) {
+            return Ok(Self {
+                inputs,
+                offset: QUERY_PARAMS_LEN,
+            });
+        }
+        check_length(inputs, total_len(L))?;
+        Ok(Self { inputs, offset: 0 })
+    }
+
+    /// Public inputs of `region`, which are empty for the parameters of the query if the proof
+    /// doesn't expose them
+    fn region(&self, region: Region) -> &'a [F] {
+        let range = region.range(L);
+        if range.start < self.offset {
+            return &[];
+        }
+        &self.inputs[range.start - self.offset..range.end - self.offset]
+    }
+
+    fn value(&self, region: Region) -> u64 {
+        self.region(region)
+            .first()
+            .map_or(0, |x| x.to_canonical_u64())
+    }
+
+    /// Whether the proof exposes only a commitment to the parameters of the query
+    pub fn is_query_committed(&self) -> bool {
+        self.offset != 0 || self.query_commitment().iter().any(|x| !x.is_zero())
+    }
+
+    /// Number of the last block of the query2/block proof answering the query, zero if the
+    /// query parameters are committed
+    pub fn block_number(&self) -> u64 {
+        self.value(Region::BlockNumber)
+    }
+
+    /// Number of blocks covered by the query2/block proof answering the query, zero if the
+    /// query parameters are committed
+    pub fn range(&self) -> u64 {
+        self.value(Region::Range)
+    }
+
+    /// Block number exposed in `region`, which is either the minimum or the maximum block
+    /// number of the query; an error is returned if the block range is not exposed
+    fn block(&self, region: Region) -> Result<u32, DecodeError> {
+        if self.is_query_committed() {
+            return Err(DecodeError::CommittedQuery);
+        }
+        if self.range_commitment().iter().any(|x| !x.is_zero()) {
+            return Err(DecodeError::HiddenBlockRange);
+        }
+        let value = self.value(region);
+        u32::try_from(value).map_err(|_| DecodeError::BlockNumberOverflow { value })
+    }
+
+    /// Minimum block number of the query; an error is returned if the proof exposes only a
+    /// commitment to the query parameters or to the block range
+    pub fn min_block(&self) -> Result<u32, DecodeError> {
+        self.block(Region::MinBlockNumber)
+    }
+
+    /// Maximum block number of the query; an error is returned if the proof exposes only a
+    /// commitment to the query parameters or to the block range
+    pub fn max_block(&self) -> Result<u32, DecodeError> {
+        self.block(Region::MaxBlockNumber)
+    }
+
+    /// Address of the contract being queried, zero if the query parameters are committed
+    pub fn smart_contract_address(&self) -> [u8; 20] {
+        unpack_limbs(self.region(Region::SmartContractAddress))
+    }
+
+    /// Address of the user being queried, zero if the query parameters are committed
+    pub fn user_address(&self) -> [u8; 20] {
+        unpack_limbs(self.region(Region::UserAddress))
+    }
+
+    /// Slot of the mapping being queried, zero if the query parameters are committed
+    pub fn mapping_slot(&self) -> u64 {
+        self.value(Region::MappingSlot)
+    }
+
+    /// Slot of the length of the mapping, zero if the query parameters are committed
+    pub fn mapping_slot_length(&self) -> u64 {
+        self.value(Region::MappingSlotLength)
+    }
+
+    /// The `L` NFT IDs exposed by the proof, including the ones outside the revealed window,
+    /// which are zero
+    pub fn nft_ids(&self) -> &'a [F] {
+        self.region(Region::NftIds)
+    }
+
+    /// Hash of the last block of the block db the proof is anchored to
+    pub fn block_header(&self) -> [u8; 32] {
+        unpack_limbs(self.region(Region::BlockHeader))
+    }
+
+    /// Result commitment as exposed in the public inputs
+    pub fn result_commitment(&self) -> &'a [F] {
+        self.region(Region::ResultCommitment)
+    }
+
+    /// Number of keys matching the query
+    pub fn total_matching(&self) -> u64 {
+        self.value(Region::TotalMatching)
+    }
+
+    /// Offset of the window of revealed keys
+    pub fn page_offset(&self) -> u64 {
+        self.value(Region::PageOffset)
+    }
+
+    /// Limit of the window of revealed keys
+    pub fn page_limit(&self) -> u64 {
+        self.value(Region::PageLimit)
+    }
+
+    /// Identifier of the hash function employed to compute the result commitment
+    pub fn commitment_hash(&self) -> u64 {
+        self.value(Region::CommitmentHash)
+    }
+
+    /// Salt mixed in the result commitment, zero if the circuit doesn't reveal it
+    pub fn salt(&self) -> F {
+        self.region(Region::Salt)[0]
+    }
+
+    /// Commitment to the parameters of the query, zero if they are exposed one by one
+    pub fn query_commitment(&self) -> &'a [F] {
+        self.region(Region::QueryCommitment)
+    }
+
+    /// Blinded commitment to the block range of the query, zero if the range is exposed
+    pub fn range_commitment(&self) -> &'a [F] {
+        self.region(Region::RangeCommitment)
+    }
+
+    /// Root of the block db the proof is anchored to
+    pub fn block_db_root(&self) -> &'a [F] {
+        self.region(Region::BlockDbRoot)
+    }
+
+    /// Address of the client allowed to consume the proof, zero if the proof is bound to no
+    /// client
+    pub fn client_address(&self) -> [u8; 20] {
+        unpack_limbs(self.region(Region::ClientAddress))
+    }
+
+    /// Commitment to the set of keys matching the query
+    pub fn keys_commitment(&self) -> &'a [F] {
+        self.region(Region::KeysCommitment)
+    }
+
+    /// Range of the positions of the revealed NFT IDs, i.e. the positions in the window
+    /// `[page_offset, page_offset + page_limit)` which are smaller than the number of
+    /// entries matching the query
+    pub fn revealed_window(&self) -> Range<usize> {
+        let end = (self.page_offset() + self.page_limit()) as usize;
+        let end = end.min(self.total_matching() as usize).min(L);
+        let start = (self.page_offset() as usize).min(end);
+        start..end
+    }
+
+    /// Decode the `L` NFT IDs as the `processQuery` function of the Solidity verifier does,
+    /// i.e. reading each ID from the 4 low bytes of its public input, serialized as
+    /// little-endian, but interpreting them as a big-endian u32
+    pub fn decode_nft_ids(&self) -> Vec<u32> {
+        self.nft_ids()
+            .iter()
+            .map(|id| (id.to_canonical_u64() as u32).swap_bytes())
+            .collect()
+    }
+
+    /// NFT IDs returned by the `processQuery` function of the Solidity verifier, i.e. the IDs
+    /// decoded as in `decode_nft_ids` found in the `revealed_window`
+    pub fn revealed_nft_ids(&self) -> Vec<u32> {
+        self.decode_nft_ids()[self.revealed_window()].to_vec()
+    }
+}
@@ -0,0 +1,204 @@
+//! Decode the public inputs of proofs depending only on the decoders, as light clients do,
+//! without the proving stack
+use std::ops::Range;
+
+use mrp2_public_inputs::{
+    block::{self, BlockPublicInputs},
+    decode_public_inputs,
+    revelation::{self, RevelationPublicInputs},
+    DecodeError, PUBLIC_INPUT_BYTES,
+};
+use plonky2_field::{
+    goldilocks_field::GoldilocksField as F,
+    types::{Field, Field64, PrimeField64},
+};
+
+const L: usize = 3;
+
+/// Serialize public inputs in the same layout as `api::serialize_public_inputs`, i.e., the one
+/// light clients receive along with a proof
+fn serialize(inputs: &[F]) -> Vec<u8> {
+    inputs
+        .iter()
+        .flat_map(|x| x.to_canonical_u64().to_le_bytes())
+        .collect()
+}
+
+/// Pack bytes into little-endian u32 limbs, as the circuits expose them
+fn pack(bytes: &[u8]) -> Vec<F> {
+    bytes
+        .chunks(4)
+        .map(|chunk| F::from_canonical_u32(u32::from_le_bytes(chunk.try_into().unwrap())))
+        .collect()
+}
+
+fn region(name: &str) -> Range<usize> {
+    revelation::layout(L)
+        .into_iter()
+        .find(|(region, _)| *region == name)
+        .unwrap()
+        .1
+}
+
+#[test]
+fn test_decode_revelation_public_inputs() {
+    let contract_address = [0x11u8; 20];
+    let user_address = [0x22u8; 20];
+    let client_address = [0x33u8; 20];
+    let block_header = [0x44u8; 32];
+    let mut inputs = vec![F::ZERO; revelation::total_len(L)];
+    let mut set = |name: &str, values: &[F]| inputs[region(name)].copy_from_slice(values);
+    set("block_number", &[F::from_canonical_u32(1000)]);
+    set("range", &[F::from_canonical_u32(10)]);
+    set("min_block_number", &[F::from_canonical_u32(991)]);
+    set("max_block_number", &[F::from_canonical_u32(1000)]);
+    set("smart_contract_address", &pack(&contract_address));
+    set("user_address", &pack(&user_address));
+    set("mapping_slot", &[F::from_canonical_u32(7)]);
+    set("mapping_slot_length", &[F::from_canonical_u32(8)]);
+    // IDs are exposed as little-endian u32, while the Solidity verifier reads them as big-endian
+    let ids = [1u32, 2, 3].map(|id| F::from_canonical_u32(id.swap_bytes()));
+    set("nft_ids", &ids);
+    set("block_header", &pack(&block_header));
+    set("total_matching", &[F::from_canonical_u32(3)]);
+    set("page_offset", &[F::ONE]);
+    set("page_limit", &[F::from_canonical_u32(5)]);
+    let root = [1, 2, 3, 4].map(F::from_canonical_u32);
+    set("block_db_root", &root);
+    set("client_address", &pack(&client_address));
+
+    let decoded = decode_public_inputs::<F>(&serialize(&inputs)).unwrap();
+    assert_eq!(decoded, inputs);
+    let pis = RevelationPublicInputs::<F, L>::new(&decoded).unwrap();
+    assert_eq!(pis.block_number(), 1000);
+    assert_eq!(pis.range(), 10);
+    assert_eq!((pis.min_block(), pis.max_block()), (Ok(991), Ok(1000)));
+    assert_eq!(pis.smart_contract_address(), contract_address);
+    assert_eq!(pis.user_address(), user_address);
+    assert_eq!((pis.mapping_slot(), pis.mapping_slot_length()), (7, 8));
+    assert_eq!(pis.block_header(), block_header);
+    assert_eq!(pis.decode_nft_ids(), vec![1, 2, 3]);
+    // the window starts from the second key and it is truncated at the matching keys
+    assert_eq!(pis.revealed_window(), 1..3);
+    assert_eq!(pis.revealed_nft_ids(), vec![2, 3]);
+    assert_eq!(pis.block_db_root(), &root);
+    assert_eq!(pis.client_address(), client_address);
+    assert!(pis.keys_commitment().iter().all(|x| x.is_zero()));
+
+    // public inputs of a proof revealing a different number of IDs are rejected
+    assert_eq!(
+        RevelationPublicInputs::<F, { L + 1 }>::new(&decoded).unwrap_err(),
+        DecodeError::Length {
+            got: revelation::total_len(L),
+            expected: revelation::total_len(L + 1),
+        }
+    );
+}
+
+#[test]
+fn test_decode_unavailable_block_range() {
+    let mut inputs = vec![F::ZERO; revelation::total_len(L)];
+    inputs[region("min_block_number")].fill(F::from_canonical_u64(1 << 32));
+    let pis = RevelationPublicInputs::<F, L>::new(&inputs).unwrap();
+    // block numbers which don't fit in a u32 are rejected rather than truncated
+    assert_eq!(
+        pis.min_block().unwrap_err(),
+        DecodeError::BlockNumberOverflow { value: 1 << 32 }
+    );
+    assert_eq!(pis.max_block(), Ok(0));
+
+    // the block range is not available if the proof exposes only a commitment to it
+    inputs[region("range_commitment")].fill(F::ONE);
+    let pis = RevelationPublicInputs::<F, L>::new(&inputs).unwrap();
+    assert_eq!(pis.min_block().unwrap_err(), DecodeError::HiddenBlockRange);
+    assert_eq!(pis.max_block().unwrap_err(), DecodeError::HiddenBlockRange);
+}
+
+#[test]
+fn test_decode_committed_query() {
+    // proofs committing to the query don't expose the regions before the NFT IDs
+    let mut inputs = vec![F::ZERO; revelation::committed_total_len(L)];
+    let committed_region = |name: &str| {
+        revelation::committed_layout(L)
+            .into_iter()
+            .find(|(region, _)| *region == name)
+            .unwrap()
+            .1
+    };
+    let ids = [4u32, 5, 6].map(|id| F::from_canonical_u32(id.swap_bytes()));
+    inputs[committed_region("nft_ids")].copy_from_slice(&ids);
+    inputs[committed_region("total_matching")].fill(F::from_canonical_u32(3));
+    inputs[committed_region("page_limit")].fill(F::from_canonical_u32(3));
+    inputs[committed_region("query_commitment")].fill(F::ONE);
+    let root = [1, 2, 3, 4].map(F::from_canonical_u32);
+    inputs[committed_region("block_db_root")].copy_from_slice(&root);
+
+    let pis = RevelationPublicInputs::<F, L>::new(&inputs).unwrap();
+    assert!(pis.is_query_committed());
+    // the regions following the query parameters are decoded as for the proofs exposing the
+    // parameters
+    assert_eq!(pis.revealed_nft_ids(), vec![4, 5, 6]);
+    assert_eq!(
+        pis.query_commitment(),
+        &[F::ONE; revelation::COMMITMENT_LEN]
+    );
+    assert_eq!(pis.block_db_root(), &root);
+    // the parameters of the query are decoded as zeros, and the block range is not available
+    assert_eq!((pis.block_number(), pis.range()), (0, 0));
+    assert_eq!(pis.smart_contract_address(), [0u8; 20]);
+    assert_eq!((pis.mapping_slot(), pis.mapping_slot_length()), (0, 0));
+    assert_eq!(pis.min_block().unwrap_err(), DecodeError::CommittedQuery);
+    assert_eq!(pis.max_block().unwrap_err(), DecodeError::CommittedQuery);
+
+    // the block range is not available either if the public inputs of a proof committing to
+    // the query are expanded to the full layout
+    let mut expanded = vec![F::ZERO; revelation::QUERY_PARAMS_LEN];
+    expanded.extend(&inputs);
+    let pis = RevelationPublicInputs::<F, L>::new(&expanded).unwrap();
+    assert!(pis.is_query_committed());
+    assert_eq!(pis.revealed_nft_ids(), vec![4, 5, 6]);
+    assert_eq!(pis.min_block().unwrap_err(), DecodeError::CommittedQuery);
+}
+
+#[test]
+fn test_decode_block_public_inputs() {
+    let contract_address = [0x55u8; 20];
+    let mut user_address = [0u8; 32];
+    user_address[12..].copy_from_slice(&[0x66u8; 20]);
+    let root = [5, 6, 7, 8].map(F::from_canonical_u32);
+    let inputs = [F::from_canonical_u32(42), F::from_canonical_u32(3)]
+        .into_iter()
+        .chain(root)
+        .chain(pack(&contract_address))
+        .chain(pack(&user_address))
+        .chain([F::from_canonical_u32(2), F::from_canonical_u32(9)])
+        .chain([F::ONE; 11])
+        .collect::<Vec<_>>();
+    assert_eq!(inputs.len(), block::NUM_PUBLIC_INPUTS);
+
+    let decoded = decode_public_inputs::<F>(&serialize(&inputs)).unwrap();
+    let pis = BlockPublicInputs::new(&decoded).unwrap();
+    assert_eq!((pis.block_number(), pis.range()), (42, 3));
+    assert_eq!(pis.root(), &root);
+    assert_eq!(pis.smart_contract_address(), contract_address);
+    assert_eq!(pis.user_address(), user_address);
+    assert_eq!((pis.mapping_slot(), pis.mapping_slot_length()), (2, 9));
+    assert_eq!(pis.digest(), &[F::ONE; 11]);
+
+    assert!(BlockPublicInputs::new(&decoded[1..]).is_err());
+}
+
+#[test]
+fn test_decode_invalid_serialized_public_inputs() {
+    let bytes = serialize(&[F::ONE, F::TWO]);
+    assert_eq!(
+        decode_public_inputs::<F>(&bytes[..bytes.len() - 1]).unwrap_err(),
+        DecodeError::Truncated {
+            len: 2 * PUBLIC_INPUT_BYTES - 1
+        }
+    );
+    assert_eq!(
+        decode_public_inputs::<F>(&F::ORDER.to_le_bytes()).unwrap_err(),
+        DecodeError::NonCanonical { value: F::ORDER }
+    );
+}
@@ -239,6 +239,25 @@ pub fn left_pad<const N: usize>(slice: &[u8]) -> [u8; N] {
     }
 }
 
+/// Same as `left_pad32`, but returns an error rather than panicking if `slice` is longer
+/// than 32 bytes
+pub fn try_left_pad32(slice: &[u8]) -> Result<[u8; 32]> {
+    try_left_pad::<32>(slice)
+}
+
+/// Same as `left_pad`, but returns an error rather than panicking if `slice` is longer
+/// than `N` bytes
+pub fn try_left_pad<const N: usize>(slice: &[u8]) -> Result<[u8; N]> {
+    if slice.len() > N {
+        bail!(
+            "cannot left pad a slice of {} bytes to {} bytes",
+            slice.len(),
+            N
+        );
+    }
+    Ok(left_pad::<N>(slice))
+}
+
 pub struct ProofQuery {
     pub contract: Address,
     pub(crate) slot: StorageSlot,
@@ -399,6 +418,17 @@ mod test {
     }
 
     use super::*;
+    #[test]
+    fn test_try_left_pad() -> Result<()> {
+        assert_eq!(try_left_pad::<4>(&[1, 2])?, [0, 0, 1, 2]);
+        assert_eq!(try_left_pad::<2>(&[1, 2])?, [1, 2]);
+        assert_eq!(try_left_pad32(&[7])?, left_pad32(&[7]));
+        // oversized slices are rejected rather than truncated
+        assert!(try_left_pad::<2>(&[1, 2, 3]).is_err());
+        assert!(try_left_pad32(&[0; 33]).is_err());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_sepolia_slot() -> Result<()> {
         #[cfg(feature = "ci")]
@@ -13,7 +13,10 @@ use plonky2_ecgfp5::{
     },
 };
 
-/// Calculate the curve point addition.
+/// Calculate the curve point addition. As the curve addition is commutative and associative,
+/// the result doesn't depend on the order of the inputs, so the digest of a set of keys is the
+/// same however the keys are ordered, e.g. before or after being sorted by the revelation
+/// circuit.
 pub fn add_curve_point(inputs: &[Point]) -> Point {
     assert!(!inputs.is_empty());
 
@@ -59,7 +62,7 @@ mod tests {
         curve::curve::{Point, WeierstrassPoint},
         gadgets::curve::PartialWitnessCurve,
     };
-    use rand::thread_rng;
+    use rand::{seq::SliceRandom, thread_rng};
 
     const ARITY: usize = 4;
     const D: usize = 2;
@@ -118,4 +121,17 @@ mod tests {
         // Verify the proof.
         data.verify(proof)
     }
+
+    /// Test the curve point addition doesn't depend on the order of the inputs.
+    #[test]
+    fn test_curve_point_addition_order_independent() {
+        let mut rng = thread_rng();
+        let mut points = [0; 10].map(|_| Point::sample(&mut rng));
+        let expected_point = add_curve_point(&points).to_weierstrass();
+
+        for _ in 0..10 {
+            points.shuffle(&mut rng);
+            assert_eq!(add_curve_point(&points).to_weierstrass(), expected_point);
+        }
+    }
 }
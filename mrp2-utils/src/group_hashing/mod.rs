@@ -1,13 +1,21 @@
 //! Group hashing arithmetic and circuit functions
 
+use crate::{eth::left_pad32, utils::convert_u8_to_u32_slice};
+use ethers::types::U256;
 use plonky2::{
-    field::extension::Extendable, hash::hash_types::RichField, iop::target::Target,
+    field::{extension::Extendable, goldilocks_field::GoldilocksField, types::Field},
+    hash::hash_types::RichField,
+    iop::target::Target,
     plonk::circuit_builder::CircuitBuilder,
 };
-use plonky2_ecgfp5::gadgets::{
-    base_field::CircuitBuilderGFp5,
-    curve::{CircuitBuilderEcGFp5, CurveTarget},
+use plonky2_ecgfp5::{
+    curve::curve::{Point, WeierstrassPoint},
+    gadgets::{
+        base_field::CircuitBuilderGFp5,
+        curve::{CircuitBuilderEcGFp5, CurveTarget},
+    },
 };
+use std::{panic::resume_unwind, thread};
 
 mod curve_add;
 pub mod field_to_curve;
@@ -22,6 +30,98 @@ pub use curve_add::add_curve_point;
 /// Field-to-curve and curve point addition functions
 pub use field_to_curve::map_to_curve_point;
 
+/// Calculate the digest of the given IDs in the same way as the query2 circuits do: each ID
+/// is encoded as a 32-byte big-endian mapping key, packed into u32 limbs and mapped to a
+/// curve point, and the digest is the sum of these points (the neutral point if there is no ID).
+pub fn digest_of_u256_ids(ids: &[U256]) -> WeierstrassPoint {
+    let keys = ids
+        .iter()
+        .map(|id| {
+            let mut key = [0u8; 32];
+            id.to_big_endian(&mut key);
+            key
+        })
+        .collect::<Vec<_>>();
+
+    sum_points(keys_to_points(&keys))
+}
+
+/// Calculate the digest of the given mapping keys in the same way as the query2 circuits do,
+/// i.e., the sum of the points returned by `map_to_curve_points`.
+pub fn digest_of_keys(keys: &[Vec<u8>]) -> WeierstrassPoint {
+    sum_points(keys_to_points(keys))
+}
+
+/// Map each of the given mapping keys to the curve point added to the digest by the query2
+/// circuits, before summation; this is meant to find which key diverges when a digest doesn't
+/// match the expected one. Each key is left padded to 32 bytes, so it must not be longer.
+pub fn map_to_curve_points(keys: &[Vec<u8>]) -> Vec<WeierstrassPoint> {
+    keys_to_points(keys)
+        .into_iter()
+        .map(|point| point.to_weierstrass())
+        .collect()
+}
+
+/// Map each mapping key, left padded to 32 bytes and packed into u32 limbs, to a curve point.
+fn keys_to_points<K: AsRef<[u8]>>(keys: &[K]) -> Vec<Point> {
+    keys.iter()
+        .map(|key| {
+            let packed_key = convert_u8_to_u32_slice(&left_pad32(key.as_ref()))
+                .into_iter()
+                .map(GoldilocksField::from_canonical_u32)
+                .collect::<Vec<_>>();
+            map_to_curve_point(&packed_key)
+        })
+        .collect()
+}
+
+/// Map each of the given keys, already packed into field elements, to a curve point and sum
+/// these points, i.e., compute the digest of the keys as `add_curve_point` over the points
+/// returned by `map_to_curve_point`. The keys are mapped in parallel on the available cores,
+/// as the mapping dominates the cost for many keys. The neutral point is returned if there is
+/// no key.
+pub fn map_and_add(keys: &[Vec<GoldilocksField>]) -> Point {
+    if keys.is_empty() {
+        return Point::NEUTRAL;
+    }
+    let num_threads = thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(keys.len());
+    let chunk_size = keys.len().div_ceil(num_threads);
+    thread::scope(|scope| {
+        let handles = keys
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    let points = chunk
+                        .iter()
+                        .map(|key| map_to_curve_point(key))
+                        .collect::<Vec<_>>();
+                    add_curve_point(&points)
+                })
+            })
+            .collect::<Vec<_>>();
+        let partial_sums = handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|payload| resume_unwind(payload))
+            })
+            .collect::<Vec<_>>();
+        add_curve_point(&partial_sums)
+    })
+}
+
+/// Sum the curve points, returning the neutral point if there is no point.
+fn sum_points(points: Vec<Point>) -> WeierstrassPoint {
+    if points.is_empty() {
+        Point::NEUTRAL.to_weierstrass()
+    } else {
+        add_curve_point(&points).to_weierstrass()
+    }
+}
+
 /// Trait for adding field-to-curve and curve point addition functions to
 /// circuit builder
 pub trait CircuitBuilderGroupHashing {
@@ -71,3 +171,62 @@ where
         self.connect(b_is_inf.target, ffalse.target);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test the per-key curve points sum up to the digest of the keys.
+    #[test]
+    fn test_map_to_curve_points() {
+        let ids = [1u64, 0x1234, u64::MAX].map(U256::from);
+        // The keys are given in their minimal big-endian encoding, and left
+        // padded when mapped to curve points.
+        let keys = ids
+            .iter()
+            .map(|id| {
+                let mut key = [0u8; 32];
+                id.to_big_endian(&mut key);
+                key[(id.leading_zeros() / 8) as usize..].to_vec()
+            })
+            .collect::<Vec<_>>();
+
+        let points = map_to_curve_points(&keys);
+        assert_eq!(points.len(), keys.len());
+        points.iter().zip(&ids).for_each(|(point, id)| {
+            assert_eq!(*point, digest_of_u256_ids(&[*id]));
+        });
+
+        let digest = digest_of_keys(&keys);
+        assert_eq!(sum_points(keys_to_points(&keys)), digest);
+        assert_eq!(digest, digest_of_u256_ids(&ids));
+        assert_eq!(digest_of_keys(&[]), Point::NEUTRAL.to_weierstrass());
+    }
+
+    /// Test the parallel digest is the same as the sequential one.
+    #[test]
+    fn test_map_and_add() {
+        let keys = (0..100u32)
+            .map(|i| {
+                (0..8)
+                    .map(|j| GoldilocksField::from_canonical_u32(i * 8 + j))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let points = keys
+            .iter()
+            .map(|key| map_to_curve_point(key))
+            .collect::<Vec<_>>();
+        let expected = add_curve_point(&points).to_weierstrass();
+        assert_eq!(map_and_add(&keys).to_weierstrass(), expected);
+        assert_eq!(
+            map_and_add(&keys[..1]).to_weierstrass(),
+            points[0].to_weierstrass()
+        );
+        assert_eq!(
+            map_and_add(&[]).to_weierstrass(),
+            Point::NEUTRAL.to_weierstrass()
+        );
+    }
+}
@@ -45,6 +45,30 @@ pub const fn compute_padding_size(data_len: usize) -> usize {
     compute_size_with_padding(data_len) - data_len
 }
 
+/// Pack a hash in u32 limbs, each one built from 4 bytes of the hash in little-endian order,
+/// starting from the first bytes of the hash. This is the packing employed by the circuits,
+/// e.g. for the block hashes exposed as public inputs, so it is the one to employ when
+/// building the public inputs of a proof from a hash.
+pub fn pack_hash_le(hash: &[u8; HASH_LEN]) -> [u32; PACKED_HASH_LEN] {
+    std::array::from_fn(|i| u32::from_le_bytes(hash[i * 4..(i + 1) * 4].try_into().unwrap()))
+}
+
+/// Same as `pack_hash_le`, but each limb is built from 4 bytes of the hash in big-endian order;
+/// this packing is not employed by the circuits
+pub fn pack_hash_be(hash: &[u8; HASH_LEN]) -> [u32; PACKED_HASH_LEN] {
+    std::array::from_fn(|i| u32::from_be_bytes(hash[i * 4..(i + 1) * 4].try_into().unwrap()))
+}
+
+/// Recover the hash packed with `pack_hash_le`
+pub fn unpack_hash_le(packed: &[u32; PACKED_HASH_LEN]) -> [u8; HASH_LEN] {
+    std::array::from_fn(|i| packed[i / 4].to_le_bytes()[i % 4])
+}
+
+/// Recover the hash packed with `pack_hash_be`
+pub fn unpack_hash_be(packed: &[u32; PACKED_HASH_LEN]) -> [u8; HASH_LEN] {
+    std::array::from_fn(|i| packed[i / 4].to_be_bytes()[i % 4])
+}
+
 /// Represents the output of the keccak hash function. This output
 /// is in a packed representation where bytes are packed into
 /// 32bits.
@@ -277,8 +301,11 @@ mod test {
     use super::{InputData, KeccakCircuit, KeccakWires};
     use crate::{
         array::{Array, Vector, VectorWire},
-        keccak::{compute_size_with_padding, ByteKeccakWires, OutputByteHash, HASH_LEN},
-        utils::{keccak256, read_le_u32},
+        keccak::{
+            compute_size_with_padding, pack_hash_be, pack_hash_le, unpack_hash_be, unpack_hash_le,
+            ByteKeccakWires, OutputByteHash, HASH_LEN,
+        },
+        utils::{convert_u8_to_u32_slice, keccak256, read_le_u32},
     };
     use mrp2_test_utils::circuit::{run_circuit, PCDCircuit, ProofOrDummyTarget, UserCircuit};
     use plonky2::{
@@ -336,6 +363,21 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_pack_hash_endianness() {
+        let hash: [u8; HASH_LEN] = thread_rng().gen();
+        // the circuits pack hashes as `convert_u8_to_u32_slice` does
+        assert_eq!(pack_hash_le(&hash).to_vec(), convert_u8_to_u32_slice(&hash));
+        assert_eq!(unpack_hash_le(&pack_hash_le(&hash)), hash);
+        assert_eq!(unpack_hash_be(&pack_hash_be(&hash)), hash);
+        // hashes unpacked with the other endianness are mangled
+        assert_ne!(unpack_hash_be(&pack_hash_le(&hash)), hash);
+        assert_ne!(unpack_hash_le(&pack_hash_be(&hash)), hash);
+        let mut swapped = hash;
+        swapped.chunks_mut(4).for_each(|chunk| chunk.reverse());
+        assert_eq!(unpack_hash_be(&pack_hash_le(&hash)), swapped);
+    }
+
     #[test]
     fn test_keccak_output() {
         const SIZE: usize = 64;
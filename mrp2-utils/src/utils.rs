@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use itertools::Itertools;
 use plonky2::field::{extension::Extendable, types::Field};
 use plonky2::hash::hash_types::{HashOut, HashOutTarget, RichField};
@@ -231,6 +231,17 @@ pub fn convert_u8_to_u32_slice(data: &[u8]) -> Vec<u32> {
     converted
 }
 
+/// Same as `convert_u8_to_u32_slice`, but it fails if the number of bytes is not a multiple
+/// of 4 rather than padding the last u32 with zero bytes
+pub fn try_convert_u8_to_u32_slice(data: &[u8]) -> Result<Vec<u32>> {
+    ensure!(
+        data.len() % 4 == 0,
+        "cannot pack {} bytes into u32 without padding: the number of bytes must be a multiple of 4",
+        data.len(),
+    );
+    Ok(convert_u8_to_u32_slice(data))
+}
+
 // taken from rust doc https://doc.rust-lang.org/std/primitive.u32.html#method.from_be_bytes
 pub fn read_le_u32(input: &mut &[u8]) -> u32 {
     let (int_bytes, rest) = input.split_at(std::mem::size_of::<u32>());
@@ -347,9 +358,29 @@ impl Fieldable for u32 {
     }
 }
 
+/// Pack bytes into u32 limbs, each one made of 4 consecutive bytes in little-endian order
 pub trait Packer {
     type T;
+    /// Pack the bytes; if their number is not a multiple of 4, the last limb is padded with
+    /// zero bytes in its most significant positions, i.e., same as `pack_padded`
     fn pack(&self) -> Vec<Self::T>;
+    /// Pack the bytes, explicitly padding the last limb with zero bytes in its most
+    /// significant positions if the number of bytes is not a multiple of 4
+    fn pack_padded(&self) -> Vec<Self::T> {
+        self.pack()
+    }
+    /// Pack the bytes, failing if their number is not a multiple of 4; it is provided for all
+    /// the implementors which can be viewed as bytes and packed into u32 limbs
+    fn try_pack(&self) -> Result<Vec<Self::T>>
+    where
+        Self: AsRef<[u8]>,
+        Self::T: From<u32>,
+    {
+        Ok(try_convert_u8_to_u32_slice(self.as_ref())?
+            .into_iter()
+            .map(Self::T::from)
+            .collect())
+    }
 }
 
 impl Packer for &[u8] {
@@ -405,6 +436,30 @@ mod test {
         let _: Vec<GoldilocksField> = addr.as_fixed_bytes().pack().to_fields();
     }
 
+    #[test]
+    fn test_pack_unaligned() {
+        // 33 bytes: the last limb only holds the last byte, in its least significant position
+        let data = (1..=33u8).collect::<Vec<_>>();
+        assert!(data.try_pack().is_err());
+        let packed = data.pack_padded();
+        assert_eq!(packed.len(), 9);
+        assert_eq!(packed[..8], data[..32].to_vec().try_pack().unwrap());
+        assert_eq!(packed[8], 33);
+        assert_eq!(packed, data.pack());
+
+        // 3 bytes: a single limb with the most significant byte set to zero
+        let data = [0xaa, 0xbb, 0xcc];
+        assert!(data.try_pack().is_err());
+        assert!(data.as_slice().try_pack().is_err());
+        assert_eq!(data.pack_padded(), vec![0x00ccbbaa]);
+        assert_eq!(data.pack(), data.pack_padded());
+
+        // aligned inputs are packed in the same way by all the methods
+        let data = [0xaa, 0xbb, 0xcc, 0xdd];
+        assert_eq!(data.try_pack().unwrap(), vec![0xddccbbaa]);
+        assert_eq!(data.pack_padded(), vec![0xddccbbaa]);
+    }
+
     #[test]
     fn test_convert_u8_to_u32_slice() {
         const SIZE: usize = 45; // size of the byte array
@@ -15,12 +15,18 @@ use crate::{
     circuit_builder::{public_input_targets, CircuitLogicWires, CircuitWithUniversalVerifier},
     serialization::circuit_data_serialization::SerializableRichField,
     universal_verifier_gadget::{
-        verifier_gadget::{UniversalVerifierBuilder, UniversalVerifierTarget},
+        verifier_gadget::{
+            UniversalVerifierBuilder, UniversalVerifierTarget, WhitelistedVerifierTarget,
+        },
         CircuitSet, CircuitSetDigest, CircuitSetTarget,
     },
 };
 
-use anyhow::Result;
+use anyhow::{ensure, Result};
+
+/// Number of public inputs, representing the digest of the set of circuits, which are exposed by
+/// the proofs of the circuits in a `RecursiveCircuits` set after the ones exposed by the circuit logic
+pub const NUM_CIRCUIT_SET_DIGEST_PUBLIC_INPUTS: usize = CircuitSetTarget::num_targets();
 
 /// This trait is employed to fetch the `VerifierOnlyCircuitData` of a circuit, which is needed to verify
 /// a proof with the universal verifier
@@ -120,6 +126,20 @@ where
         )
     }
 
+    /// Check whether the circuit with digest `circuit_digest` belongs to the set of circuits
+    pub fn contains_circuit_digest(&self, circuit_digest: &HashOut<F>) -> bool {
+        self.circuit_set.contains(&circuit_digest.elements)
+    }
+
+    /// Digests of the circuits belonging to the set, in the order they were provided to build it
+    pub fn circuit_digests(&self) -> Vec<HashOut<F>> {
+        self.circuit_set
+            .circuit_digests()
+            .into_iter()
+            .map(HashOut::from_vec)
+            .collect()
+    }
+
     /// Get the digest of the circuit set as a list of field elements, which should be equal to
     /// the list of public inputs corresponding to the circuit set digest in the generated proofs
     pub fn get_circuit_set_digest(&self) -> CircuitSetDigest<F, C, D> {
@@ -172,6 +192,12 @@ impl<const D: usize> RecursiveCircuitsVerifierTarget<D> {
         )
     }
 
+    /// Returns the number of public inputs of the proof being recursively verified, excluding the
+    /// ones exposing the digest of the circuit set
+    pub fn num_public_inputs(&self) -> usize {
+        self.0.get_proof_target().public_inputs.len() - CircuitSetTarget::num_targets()
+    }
+
     /// Returns a set of targets corresponding to the public inputs of the proof being recursively
     /// verified
     pub fn get_public_input_targets<F: SerializableRichField<D>, const NUM_PUBLIC_INPUTS: usize>(
@@ -181,6 +207,32 @@ impl<const D: usize> RecursiveCircuitsVerifierTarget<D> {
         public_input_targets::<F, D, NUM_PUBLIC_INPUTS>(pt)
     }
 }
+/// Targets instantiated by the `RecursiveCircuitsVerifierGadget` to verify proofs generated by a whitelist of
+/// circuits, which needs to be assigned with a witness value by the prover
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct WhitelistedCircuitsVerifierTarget<const D: usize>(WhitelistedVerifierTarget<D>);
+
+impl<const D: usize> WhitelistedCircuitsVerifierTarget<D> {
+    /// Assign witness values to the targets in `self`, employing the following input data:
+    /// - `proof`: proof to be verified
+    /// - `verifier_data`: verifier data of the circuit employed to generate `proof`, which must be
+    ///   one of the whitelisted circuits
+    pub fn set_target<F: SerializableRichField<D>, C: GenericConfig<D, F = F>>(
+        &self,
+        pw: &mut PartialWitness<F>,
+        proof: &ProofWithPublicInputs<F, C, D>,
+        verifier_data: &VerifierOnlyCircuitData<C, D>,
+    ) where
+        C::Hasher: AlgebraicHasher<F>,
+    {
+        self.0.set_target(pw, proof, verifier_data)
+    }
+
+    /// Returns the targets of the proof being recursively verified
+    pub fn get_proof_target(&self) -> &ProofWithPublicInputsTarget<D> {
+        self.0.get_proof_target()
+    }
+}
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(bound = "")]
 /// `RecursiveCircuitsVerifierGadget` is a gadget that can be employed in circuits that need to verify proofs generated
@@ -268,6 +320,41 @@ impl<
         );
         proof
     }
+
+    /// Gadget to verify a proof generated with the `RecursiveCircuits` framework for any of the circuits in
+    /// `whitelist`, which must be non-empty and belong to the set of recursive circuits bounded to `self`; this
+    /// allows to accept proofs generated by different versions of a circuit, e.g., while migrating to a new version
+    pub fn verify_proof_whitelisted_circuits_in_circuit_set(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        whitelist: &[VerifierOnlyCircuitData<C, D>],
+    ) -> Result<WhitelistedCircuitsVerifierTarget<D>>
+    where
+        C::Hasher: AlgebraicHasher<F>,
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        ensure!(
+            !whitelist.is_empty(),
+            "at least one circuit must be whitelisted"
+        );
+        ensure!(
+            whitelist.iter().all(|verifier_data| self
+                .recursive_circuits
+                .contains_circuit_digest(&verifier_data.circuit_digest)),
+            "whitelisted circuits must belong to the set of recursive circuits"
+        );
+        let circuit_set_target = CircuitSetTarget::from_circuit_set_digest(
+            builder,
+            self.recursive_circuits.get_circuit_set_digest(),
+        );
+        Ok(WhitelistedCircuitsVerifierTarget(
+            self.gadget_builder.whitelisted_verifier_circuit(
+                builder,
+                &circuit_set_target,
+                whitelist,
+            ),
+        ))
+    }
 }
 
 #[cfg(test)]
@@ -708,4 +795,92 @@ pub(crate) mod tests {
             while recursively verifying a proof generated with `leaf_circuit`"
         );
     }
+
+    #[test]
+    fn test_whitelisted_verifier_circuit() {
+        const INPUT_SIZE: usize = 8;
+        const CIRCUIT_SET_SIZE: usize = 2;
+        let config = CircuitConfig::standard_recursion_config();
+
+        const NUM_PUBLIC_INPUTS: usize =
+            <LeafCircuitWires<F, INPUT_SIZE> as CircuitLogicWires<F, D, 0>>::NUM_PUBLIC_INPUTS;
+        let circuit_builder = CircuitWithUniversalVerifierBuilder::<F, D, NUM_PUBLIC_INPUTS>::new::<
+            C,
+        >(config.clone(), CIRCUIT_SET_SIZE);
+        let leaf_circuit = circuit_builder
+            .build_circuit::<C, 0, LeafCircuitWires<F, INPUT_SIZE>>((1usize << 12, false));
+        let recursive_circuit =
+            circuit_builder.build_circuit::<C, 1, RecursiveCircuitWires<INPUT_SIZE>>(());
+        let recursive_framework = RecursiveCircuits::new(vec![
+            prepare_recursive_circuit_for_circuit_set(&leaf_circuit),
+            prepare_recursive_circuit_for_circuit_set(&recursive_circuit),
+        ]);
+
+        let base_proof = {
+            let inputs = array::from_fn(|_| F::rand());
+            recursive_framework
+                .generate_proof(&leaf_circuit, [], [], (inputs, F::rand()))
+                .unwrap()
+        };
+        let leaf_circuit_vd = leaf_circuit.get_verifier_data();
+        let recursive_circuit_vd = recursive_circuit.get_verifier_data();
+        let rec_proof = recursive_framework
+            .generate_proof(
+                &recursive_circuit,
+                [base_proof.clone()],
+                [leaf_circuit_vd],
+                array::from_fn(|_| F::rand()),
+            )
+            .unwrap();
+
+        // build a circuit accepting only proofs generated with `recursive_circuit`
+        let verifier_gadget = RecursiveCircuitsVerifierGagdet::<F, C, D, NUM_PUBLIC_INPUTS>::new(
+            config.clone(),
+            &recursive_framework,
+        );
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let whitelisted_target = verifier_gadget
+            .verify_proof_whitelisted_circuits_in_circuit_set(
+                &mut builder,
+                &[recursive_circuit_vd.clone()],
+            )
+            .unwrap();
+        let verifier_circuit = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        whitelisted_target.set_target(&mut pw, &rec_proof, recursive_circuit_vd);
+        let proof = verifier_circuit.prove(pw).unwrap();
+        verifier_circuit.verify(proof).unwrap();
+
+        // the proof generated with `leaf_circuit`, which belongs to the set but is not whitelisted,
+        // must be rejected
+        let mut pw = PartialWitness::new();
+        whitelisted_target.set_target(&mut pw, &base_proof, leaf_circuit_vd);
+        check_panic!(
+            || verifier_circuit.prove(pw).unwrap(),
+            "whitelisted verifier did not fail while recursively verifying a proof generated with \
+            a circuit not in the whitelist"
+        );
+
+        // the whitelist can't be empty or contain circuits outside the set
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        assert!(verifier_gadget
+            .verify_proof_whitelisted_circuits_in_circuit_set(&mut builder, &[])
+            .is_err());
+        let other_circuit = {
+            let mut builder = CircuitBuilder::<F, D>::new(config);
+            let target = builder.add_virtual_target();
+            builder.register_public_input(target);
+            builder.build::<C>()
+        };
+        assert!(verifier_gadget
+            .verify_proof_whitelisted_circuits_in_circuit_set(
+                &mut builder,
+                &[
+                    recursive_circuit_vd.clone(),
+                    other_circuit.verifier_only.clone()
+                ],
+            )
+            .is_err());
+    }
 }
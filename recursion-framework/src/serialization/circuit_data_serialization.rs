@@ -37,7 +37,9 @@ use plonky2::{
         ConstantGenerator, CopyGenerator, NonzeroTestGenerator, RandomValueGenerator,
     },
     plonk::{
-        circuit_data::{CircuitData, CommonCircuitData, VerifierOnlyCircuitData},
+        circuit_data::{
+            CircuitData, CommonCircuitData, ProverCircuitData, VerifierOnlyCircuitData,
+        },
         config::{AlgebraicHasher, GenericConfig, Hasher},
     },
     read_gate_impl, read_generator_impl,
@@ -164,6 +166,37 @@ pub trait SerializableRichField<const D: usize>:
 {
 }
 
+impl<F: SerializableRichField<D>, C: GenericConfig<D, F = F> + 'static, const D: usize> ToBytes
+    for ProverCircuitData<F, C, D>
+where
+    C::Hasher: AlgebraicHasher<F>,
+{
+    fn to_bytes(&self) -> Vec<u8> {
+        let generator_serializer = CustomGeneratorSerializer::<C, D> {
+            _phantom: PhantomData,
+        };
+        self.to_bytes(&CustomGateSerializer, &generator_serializer)
+            .expect("Writing to a byte-vector cannot fail.")
+    }
+}
+
+impl<F: SerializableRichField<D>, C: GenericConfig<D, F = F> + 'static, const D: usize> FromBytes
+    for ProverCircuitData<F, C, D>
+where
+    C::Hasher: AlgebraicHasher<F>,
+{
+    fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let generator_serializer = CustomGeneratorSerializer::<C, D> {
+            _phantom: PhantomData,
+        };
+        Ok(ProverCircuitData::<F, C, D>::from_bytes(
+            bytes,
+            &CustomGateSerializer,
+            &generator_serializer,
+        )?)
+    }
+}
+
 impl<const D: usize, T: RichField + Extendable<D> + Extendable<5> + InverseOrZero>
     SerializableRichField<D> for T
 {
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use plonky2::{
     field::extension::Extendable,
     hash::{
-        hash_types::{HashOut, HashOutTarget, MerkleCapTarget, RichField},
+        hash_types::{HashOut, HashOutTarget, MerkleCapTarget, RichField, NUM_HASH_OUT_ELTS},
         merkle_proofs::MerkleProofTarget,
         merkle_tree::{MerkleCap, MerkleTree},
     },
@@ -59,6 +59,11 @@ impl CircuitSetTarget {
         merkle_cap_to_targets(&self.0)
     }
 
+    /// Number of targets employed to represent the set of circuits
+    pub(crate) const fn num_targets() -> usize {
+        NUM_HASH_OUT_ELTS << CIRCUIT_SET_CAP_HEIGHT
+    }
+
     pub(crate) fn from_circuit_set_digest<
         F: RichField + Extendable<D>,
         H: Hasher<F, Hash = HashOut<F>>,
@@ -235,9 +240,27 @@ where
         Ok(())
     }
 
+    /// Check whether `circuit_digest` belongs to the set of circuits
+    pub(crate) fn contains(&self, circuit_digest: &[F]) -> bool {
+        self.leaf_index(circuit_digest).is_some()
+    }
+
     pub(crate) fn circuit_set_size(&self) -> usize {
         self.circuit_digests_to_leaf_indexes.len()
     }
+
+    /// Digests of the circuits in the set, sorted by their position in the set
+    pub(crate) fn circuit_digests(&self) -> Vec<Vec<F>> {
+        let mut digests = self
+            .circuit_digests_to_leaf_indexes
+            .iter()
+            .collect::<Vec<_>>();
+        digests.sort_by_key(|(_, index)| **index);
+        digests
+            .into_iter()
+            .map(|(digest, _)| digest.clone())
+            .collect()
+    }
 }
 
 /// A short representation (e.g., a digest) of the set of circuits whose proofs can be verified with the
@@ -298,6 +321,24 @@ mod tests {
     type C = PoseidonGoldilocksConfig;
     type F = <C as GenericConfig<D>>::F;
 
+    #[test]
+    fn test_circuit_digests() {
+        const NUM_ELEMENTS: usize = 5;
+
+        let elements = (0..NUM_ELEMENTS)
+            .map(|_| {
+                let hash_input = vec![F::rand(); 4];
+                <C as GenericConfig<D>>::Hasher::hash_no_pad(hash_input.as_slice())
+            })
+            .collect::<Vec<_>>();
+        let circuit_set = CircuitSet::<F, C, D>::build_circuit_set(elements.clone());
+        // the digests are listed in the order they were provided
+        assert_eq!(
+            circuit_set.circuit_digests(),
+            elements.iter().map(|e| e.to_vec()).collect::<Vec<_>>()
+        );
+    }
+
     #[test]
     fn test_circuit_set_gadgets() {
         const NUM_ELEMENTS: usize = 42;
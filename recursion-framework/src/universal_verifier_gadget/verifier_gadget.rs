@@ -67,6 +67,35 @@ impl<const D: usize> UniversalVerifierTarget<D> {
         )
     }
 }
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+/// `WhitelistedVerifierTarget` comprises all the targets that are employed to recursively verify a proof
+/// generated by one of a whitelist of circuits belonging to the set of circuits bound to the universal verifier
+pub(crate) struct WhitelistedVerifierTarget<const D: usize> {
+    #[serde(serialize_with = "serialize", deserialize_with = "deserialize")]
+    verified_proof: ProofWithPublicInputsTarget<D>,
+    #[serde(serialize_with = "serialize", deserialize_with = "deserialize")]
+    verifier_data: VerifierCircuitTarget,
+}
+
+impl<const D: usize> WhitelistedVerifierTarget<D> {
+    pub(crate) fn get_proof_target(&self) -> &ProofWithPublicInputsTarget<D> {
+        &self.verified_proof
+    }
+
+    /// Assigns the proof and the verifier data of the whitelisted circuit employed to generate the proof
+    pub(crate) fn set_target<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>>(
+        &self,
+        pw: &mut PartialWitness<F>,
+        proof: &ProofWithPublicInputs<F, C, D>,
+        verifier_data: &VerifierOnlyCircuitData<C, D>,
+    ) where
+        C::Hasher: AlgebraicHasher<F>,
+    {
+        pw.set_proof_with_pis_target(&self.verified_proof, proof);
+        pw.set_verifier_data_target(&self.verifier_data, verifier_data);
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(bound = "")]
 /// `UniversalVerifierBuilder` is a data structure necessary to build instances of the universal verifier
@@ -169,6 +198,53 @@ impl<F: SerializableRichField<D>, const D: usize, const NUM_PUBLIC_INPUTS: usize
             verifier_data,
         }
     }
+
+    /// Gadget to add an instance of the universal verifier, bound to the circuit set specified in `circuit_set_target`,
+    /// which accepts only proofs generated by the circuits whose verifier data are in `whitelist`
+    pub(crate) fn whitelisted_verifier_circuit<C: GenericConfig<D, F = F>>(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        circuit_set_target: &CircuitSetTarget,
+        whitelist: &[VerifierOnlyCircuitData<C, D>],
+    ) -> WhitelistedVerifierTarget<D>
+    where
+        C::Hasher: AlgebraicHasher<F>,
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        // allocate verifier data targets
+        let verifier_data = VerifierCircuitTarget {
+            constants_sigmas_cap: builder
+                .add_virtual_cap(self.rec_data.config.fri_config.cap_height),
+            circuit_digest: builder.add_virtual_hash(),
+        };
+        // verify proof
+        let proof = self.verify_proof_for_universal_verifier::<C>(builder, &verifier_data);
+        // the circuit digest is bound to the verifier data, so it is enough to check that the digest is
+        // one of the whitelisted ones
+        check_circuit_digest_target::<_, C, D>(builder, &verifier_data, RECURSION_THRESHOLD);
+        let mut is_whitelisted = builder._false();
+        for whitelisted_data in whitelist {
+            let whitelisted_digest = builder.constant_hash(whitelisted_data.circuit_digest);
+            let mut is_same_digest = builder._true();
+            for (whitelisted, actual) in whitelisted_digest
+                .elements
+                .iter()
+                .zip(verifier_data.circuit_digest.elements.iter())
+            {
+                let is_same_element = builder.is_equal(*whitelisted, *actual);
+                is_same_digest = builder.and(is_same_digest, is_same_element);
+            }
+            is_whitelisted = builder.or(is_whitelisted, is_same_digest);
+        }
+        builder.assert_one(is_whitelisted.target);
+        // check that the circuit set employed as public input in the recursively verified proof is the same as the one exposed by the recursive proof
+        Self::check_circuit_set_equality(builder, circuit_set_target, &proof);
+
+        WhitelistedVerifierTarget {
+            verified_proof: proof,
+            verifier_data,
+        }
+    }
 }
 
 #[cfg(test)]